@@ -0,0 +1,1065 @@
+use std::rc::Rc;
+use std::mem;
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+const KEYWORDS: [&str; 58] = [
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else",
+    "enum", "extern", "float", "for", "goto", "if", "inline", "int", "long", "register",
+    "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
+    "union", "unsigned", "void", "volatile", "while", "bool", "true", "false", "main",
+    // names of the c library the generated code calls
+    "printf", "putchar", "fputs", "snprintf", "strtod", "strcmp", "stdout", "sqrt", "fabs",
+    "floor", "ceil", "round", "sin", "cos", "tan", "exp", "log", "pow", "fmod", "isnan",
+];
+
+// printing a double the way the evaluator displays numbers, the shortest
+// precision that reads back as the same value
+const PRINT_DOUBLE: &str = r#"static void itu_print_double(double v) {
+    char buffer[32];
+    int precision;
+
+    if (isnan(v)) {
+        fputs("NaN", stdout);
+        return;
+    }
+
+    if (isinf(v)) {
+        fputs(v > 0 ? "inf" : "-inf", stdout);
+        return;
+    }
+
+    if (v == floor(v) && fabs(v) < 1e15) {
+        printf("%.0f", v);
+        return;
+    }
+
+    for (precision = 1; precision <= 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*g", precision, v);
+
+        if (strtod(buffer, NULL) == v) {
+            break;
+        }
+    }
+
+    fputs(buffer, stdout);
+}
+"#;
+
+// where the value of whatever is being generated goes
+#[derive(Debug, Clone, PartialEq)]
+enum Tail {
+    Return,
+    Discard,
+    Assign(String),
+}
+
+#[derive(Debug, Clone)]
+struct Local {
+    name:     String,
+    // the length of a fixed-size array, which c does not keep around
+    len:      Option<usize>,
+    function: bool,
+}
+
+type Scope = HashMap<Rc<String>, Local>;
+
+pub struct C<'a> {
+    inferer:    &'a Inferer,
+    out:        String,
+    indent:     usize,
+    scopes:     Vec<Scope>,
+    // the scopes of the functions a hoisted lambda sits in, out of its reach
+    outer:      Vec<Vec<Scope>>,
+    globals:    Scope,
+    function:   String,
+    names:      HashSet<String>,
+    statics:    Vec<String>,
+    prototypes: Vec<String>,
+    functions:  Vec<String>,
+    temps:      usize,
+    math:       bool,
+    string:     bool,
+    print:      bool,
+}
+
+// translates a type-checked program into a c99 translation unit, top-level
+// functions become c functions, lambdas that capture nothing are hoisted
+// next to them and the rest of the program becomes `main`
+pub fn emit(ast: &[Statement], inferer: &Inferer) -> CodegenResult<String> {
+    let mut c = C {
+        inferer,
+        out:        String::new(),
+        indent:     1,
+        scopes:     vec![HashMap::new()],
+        outer:      Vec::new(),
+        globals:    HashMap::new(),
+        function:   "main".to_owned(),
+        names:      HashSet::new(),
+        statics:    Vec::new(),
+        prototypes: Vec::new(),
+        functions:  Vec::new(),
+        temps:      0,
+        math:       false,
+        string:     false,
+        print:      false,
+    };
+
+    let statements = ast.iter().filter(|s| !is_eof(s)).collect::<Vec<_>>();
+
+    // everything at the top level is in reach of every function
+    for statement in &statements {
+        if let Statement::Definition(ref definition) = **statement {
+            let name = name(&definition.name)?;
+            let len  = match definition.right.as_deref() {
+                Some(Expression::Array(content)) => Some(content.len()),
+                _                                => None,
+            };
+
+            let local = Local { name: mangle(name), len, function: item(statement).is_some() };
+
+            c.names.insert(local.name.clone());
+            c.globals.insert(name.clone(), local);
+        }
+    }
+
+    for statement in &statements {
+        if let Some((name, lambda, position)) = item(statement) {
+            c.function = mangle(name);
+            c.function(&mangle(name), lambda, false).map_err(|err| located(err, position, Some(name)))?
+        }
+    }
+
+    c.function = "main".to_owned();
+
+    for (i, statement) in statements.iter().enumerate() {
+        match **statement {
+            _ if item(statement).is_some() => (),
+
+            // the value of the program is printed, like `itu run` does
+            Statement::Expression(ref expression, _) if i + 1 == statements.len() => {
+                let t = c.type_of(expression);
+
+                if t == Ty::Unit {
+                    c.tail(expression, &Tail::Discard)?
+                } else {
+                    let len   = c.len(expression)?;
+                    let value = c.expression(expression)?;
+
+                    c.show(&value, &t, len, false)?;
+                    c.line("putchar('\\n');")
+                }
+            },
+
+            Statement::Definition(ref definition) => c.global(definition).map_err(|err| located(err, definition.position, None))?,
+            Statement::Assignment(ref assignment) => c.statement(statement).map_err(|err| located(err, assignment.position, None))?,
+
+            ref statement => c.statement(statement)?,
+        }
+    }
+
+    let mut out = String::from("/* generated by itu */\n#include <stdio.h>\n#include <stdint.h>\n#include <stdbool.h>\n");
+
+    if c.math || c.print {
+        out.push_str("#include <math.h>\n")
+    }
+
+    if c.string {
+        out.push_str("#include <string.h>\n")
+    }
+
+    if c.print {
+        out.push_str("#include <stdlib.h>\n\n");
+        out.push_str(PRINT_DOUBLE)
+    }
+
+    for (i, statics) in c.statics.iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { "" });
+        out.push_str(statics);
+        out.push('\n')
+    }
+
+    for (i, prototype) in c.prototypes.iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { "" });
+        out.push_str(prototype);
+        out.push('\n')
+    }
+
+    for function in &c.functions {
+        out.push('\n');
+        out.push_str(function)
+    }
+
+    out.push_str("\nint main(void) {\n");
+    out.push_str(&c.out);
+    out.push_str("    return 0;\n}\n");
+
+    Ok(out)
+}
+
+fn is_eof(statement: &Statement) -> bool {
+    match *statement {
+        Statement::Expression(ref e, _) => **e == Expression::EOF,
+        _                            => false,
+    }
+}
+
+// places an error raised without a position at `position`, naming the
+// function it was raised in like the wasm backend does
+fn located(err: CodegenError, position: TokenPosition, function: Option<&Rc<String>>) -> CodegenError {
+    if err.position.is_some() {
+        return err
+    }
+
+    let CodegenErrorValue::Constant(ref value) = err.value;
+
+    match function {
+        Some(name) => CodegenError::new_pos(position, &format!("in `{}`: {}", name, value)),
+        None       => CodegenError::new_pos(position, value),
+    }
+}
+
+fn name(expression: &Expression) -> CodegenResult<&Rc<String>> {
+    match *expression {
+        Expression::Identifier(ref name) => Ok(name),
+        _                                => Err(CodegenError::new(&format!("expected name, found: {:?}", expression))),
+    }
+}
+
+// a lambda definition that is never reassigned, and so can be a c function
+fn item(statement: &Statement) -> Option<(&Rc<String>, &Expression, TokenPosition)> {
+    if let Statement::Definition(ref definition) = *statement {
+        if let (Expression::Identifier(name), Some(right)) = (&*definition.name, definition.right.as_ref()) {
+            if let Expression::Lambda(_) = **right {
+                if !matches!(definition.t, Some(Type::Mut(_))) {
+                    return Some((name, right, definition.position))
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// whether `expression` can be evaluated in place, without statements
+// hoisted ahead of it running regardless of the branch taken
+fn simple(expression: &Expression) -> bool {
+    match *expression {
+        Expression::Block(_) => false,
+        Expression::If(ref i) => match i.otherwise {
+            Some(ref otherwise) => simple(&i.condition) && simple(&i.body) && simple(otherwise),
+            None                => false,
+        },
+        Expression::Operation(ref o) => simple(&o.left) && simple(&o.right),
+        Expression::Call(ref call) => {
+            if let Expression::Field(ref field) = *call.callee {
+                if *field.object == Expression::Identifier(Rc::new("io".to_owned())) {
+                    return false
+                }
+            }
+
+            simple(&call.callee) && call.args.iter().all(|a| simple(a))
+        },
+        Expression::Array(ref content) => content.iter().all(|e| simple(e)),
+        Expression::Index(ref i)       => simple(&i.id) && simple(&i.index),
+        _                              => true,
+    }
+}
+
+fn pure(expression: &Expression) -> bool {
+    matches!(
+        *expression,
+        Expression::Number(_) | Expression::Bool(_) | Expression::Str(_) | Expression::Char(_) | Expression::Identifier(_)
+    )
+}
+
+pub fn mangle(name: &str) -> String {
+    let mut out = String::new();
+
+    for c in name.chars() {
+        match c {
+            '?'  => out.push_str("_p"),
+            '\'' => out.push_str("_q"),
+            c    => out.push(c),
+        }
+    }
+
+    if KEYWORDS.contains(&out.as_str()) || out.starts_with("itu_") {
+        out.push('_')
+    }
+
+    out
+}
+
+pub fn primitive(t: &Type) -> CodegenResult<&'static str> {
+    let name = match *t {
+        Type::I08  => "int8_t",
+        Type::I16  => "int16_t",
+        Type::I32  => "int32_t",
+        Type::I64  => "int64_t",
+        Type::U08  => "uint8_t",
+        Type::U16  => "uint16_t",
+        Type::U32  => "uint32_t",
+        Type::U64  => "uint64_t",
+        Type::F32  => "float",
+        Type::F64  => "double",
+        Type::Char => "char",
+        Type::Str  => "const char *",
+        Type::Bool => "bool",
+        ref t      => return Err(CodegenError::new(&format!("{:?} has no c99 equivalent", t))),
+    };
+
+    Ok(name)
+}
+
+fn string(s: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"'  => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\{:03o}", c as u32)),
+            c    => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+fn character(c: char) -> CodegenResult<String> {
+    let escaped = match c {
+        '\'' => "\\'".to_owned(),
+        '\\' => "\\\\".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\t' => "\\t".to_owned(),
+        '\r' => "\\r".to_owned(),
+        c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+        c    => return Err(CodegenError::new(&format!("{:?} does not fit a c char", c))),
+    };
+
+    Ok(format!("'{}'", escaped))
+}
+
+#[allow(dead_code)]
+impl<'a> C<'a> {
+    fn line(&mut self, text: &str) {
+        for _ in 0 .. self.indent {
+            self.out.push_str("    ")
+        }
+
+        self.out.push_str(text);
+        self.out.push('\n')
+    }
+
+    fn type_of(&self, expression: &Expression) -> Ty {
+        self.inferer.type_of(expression).unwrap_or(Ty::Unit)
+    }
+
+    fn unique(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut n      = 1;
+
+        while self.names.contains(&unique) {
+            n += 1;
+            unique = format!("{}{}", name, n)
+        }
+
+        self.names.insert(unique.clone());
+        unique
+    }
+
+    fn temporary(&mut self) -> String {
+        self.temps += 1;
+        format!("itu_t{}", self.temps)
+    }
+
+    fn resolve(&self, name: &Rc<String>) -> CodegenResult<Local> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(local) = scope.get(name) {
+                return Ok(local.clone())
+            }
+        }
+
+        for scopes in self.outer.iter().rev() {
+            for scope in scopes.iter().rev() {
+                if let Some(local) = scope.get(name) {
+                    if local.function {
+                        return Ok(local.clone())
+                    }
+
+                    return Err(CodegenError::new(&format!("lambdas capturing values, like `{}` here, have no c equivalent", name)))
+                }
+            }
+        }
+
+        if let Some(local) = self.globals.get(name) {
+            return Ok(local.clone())
+        }
+
+        Err(CodegenError::new(&format!("`{}` is not a value in c", name)))
+    }
+
+    fn bind(&mut self, name: Rc<String>, local: Local) {
+        self.scopes.last_mut().unwrap().insert(name, local);
+    }
+
+    fn scalar(&self, t: &Ty) -> CodegenResult<String> {
+        let name = match *t {
+            Ty::Prim(ref t)     => primitive(t)?.to_owned(),
+            Ty::Unit            => "void".to_owned(),
+            Ty::Named(ref name) => format!("struct {}", mangle(name)),
+            Ty::Var(_) if self.inferer.numeric(t) => "double".to_owned(),
+            Ty::Var(_)          => return Err(CodegenError::new("generic functions have no c99 equivalent")),
+            ref t               => return Err(CodegenError::new(&format!("{} has no c99 equivalent", t))),
+        };
+
+        Ok(name)
+    }
+
+    // a c declarator of `name` as a `t`, c spells pointers and functions
+    // around the name rather than beside it
+    fn declare(&self, t: &Ty, name: &str) -> CodegenResult<String> {
+        match self.inferer.resolve(t) {
+            Ty::Function(params, ret) => {
+                let mut ps = Vec::new();
+
+                for p in &params {
+                    ps.push(self.declare(p, "")?)
+                }
+
+                let ps = if ps.is_empty() { "void".to_owned() } else { ps.join(", ") };
+
+                self.declare(&ret, &format!("(*{})({})", name, ps))
+            },
+
+            Ty::Array(ref element) => self.declare(element, &format!("*{}", name)),
+
+            ref t => {
+                let base = self.scalar(t)?;
+
+                Ok(if name.is_empty() {
+                    base
+                } else if base.ends_with('*') || name.starts_with('[') {
+                    format!("{}{}", base, name)
+                } else {
+                    format!("{} {}", base, name)
+                })
+            },
+        }
+    }
+
+    // the length of an array where it is known
+    fn len(&self, expression: &Expression) -> CodegenResult<Option<usize>> {
+        match *expression {
+            Expression::Identifier(ref name) => Ok(self.resolve(name)?.len),
+            Expression::Array(ref content)   => Ok(Some(content.len())),
+            _                                => Ok(None),
+        }
+    }
+
+    // a lambda as a c function called `name`
+    fn function(&mut self, name: &str, expression: &Expression, hoisted: bool) -> CodegenResult<()> {
+        let lambda = match *expression {
+            Expression::Lambda(ref lambda) => lambda,
+            _                              => unreachable!(),
+        };
+
+        let (params, ret) = match self.type_of(expression) {
+            Ty::Function(params, ret) => (params, self.inferer.resolve(&ret)),
+            _                         => return Err(CodegenError::new("expected a function type")),
+        };
+
+        if let Ty::Array(_) = ret {
+            return Err(CodegenError::new("functions returning arrays have no c equivalent"))
+        }
+
+        let mut ps    = Vec::new();
+        let mut scope = HashMap::new();
+
+        for ((_, param), t) in lambda.params.iter().zip(&params) {
+            let local = Local { name: mangle(param), len: None, function: false };
+
+            ps.push(self.declare(t, &local.name)?);
+            scope.insert(param.clone(), local);
+        }
+
+        let ps     = if ps.is_empty() { "void".to_owned() } else { ps.join(", ") };
+        let header = format!("{}{}", if hoisted { "static " } else { "" }, self.declare(&ret, &format!("{}({})", name, ps))?);
+
+        let out    = mem::take(&mut self.out);
+        let indent = mem::replace(&mut self.indent, 1);
+        let scopes = mem::replace(&mut self.scopes, vec![scope]);
+
+        self.outer.push(scopes);
+
+        let tail   = if ret == Ty::Unit { Tail::Discard } else { Tail::Return };
+        let result = match *lambda.body {
+            Expression::Block(ref statements) => self.statements(statements, &tail),
+            ref body                          => self.tail(body, &tail),
+        };
+
+        self.scopes = self.outer.pop().unwrap();
+        self.indent = indent;
+
+        let body = mem::replace(&mut self.out, out);
+
+        result?;
+
+        self.prototypes.push(format!("{};", header));
+        self.functions.push(format!("{} {{\n{}}}\n", header, body));
+
+        Ok(())
+    }
+
+    // moves a lambda out to a static function of its own, it must not
+    // capture anything as c has no closures
+    fn hoist(&mut self, name: Option<&Rc<String>>, expression: &Expression) -> CodegenResult<String> {
+        let hoisted = match name {
+            Some(name) => format!("{}_{}", self.function, mangle(name)),
+            None       => format!("{}_lambda", self.function),
+        };
+
+        let hoisted = self.unique(hoisted);
+
+        if let Some(name) = name {
+            self.bind(name.clone(), Local { name: hoisted.clone(), len: None, function: true })
+        }
+
+        let function = mem::replace(&mut self.function, hoisted.clone());
+        let result   = self.function(&hoisted, expression, true);
+
+        self.function = function;
+
+        result.map(|_| hoisted)
+    }
+
+    fn statements(&mut self, statements: &[Statement], tail: &Tail) -> CodegenResult<()> {
+        let statements = statements.iter().filter(|s| !is_eof(s)).collect::<Vec<_>>();
+
+        for (i, statement) in statements.iter().enumerate() {
+            match **statement {
+                Statement::Expression(ref expression, _) if i + 1 == statements.len() => self.tail(expression, tail)?,
+                ref statement => self.statement(statement)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> CodegenResult<()> {
+        match *statement {
+            Statement::Expression(ref expression, _) => self.tail(expression, &Tail::Discard),
+            Statement::Definition(ref definition) => self.definition(definition),
+
+            Statement::Assignment(ref assignment) => {
+                if let Ty::Array(_) = self.type_of(&assignment.right) {
+                    return Err(CodegenError::new_pos(assignment.position, "c cannot assign whole arrays"))
+                }
+
+                let place = match *assignment.left {
+                    Expression::Identifier(ref name) => {
+                        let local = self.resolve(name)?;
+
+                        if local.function {
+                            return Err(CodegenError::new_pos(assignment.position, &format!("`{}` is a function", name)))
+                        }
+
+                        local.name
+                    },
+                    Expression::Index(ref index) => self.index(index)?,
+                    _ => return Err(CodegenError::new_pos(assignment.position, "only names and their elements can be changed")),
+                };
+
+                let value = self.expression(&assignment.right)?;
+                self.line(&format!("{} = {};", place, value));
+
+                Ok(())
+            },
+
+            Statement::Test(_) => unreachable!(),
+
+            Statement::Import(ref import) => Err(CodegenError::new_pos(import.position, "imports cannot be emitted as c")),
+        }
+    }
+
+    fn definition(&mut self, definition: &Definition) -> CodegenResult<()> {
+        let name  = name(&definition.name)?;
+        let right = match definition.right {
+            Some(ref right) => right,
+            None            => return Err(CodegenError::new_pos(definition.position, "definitions need a value in c")),
+        };
+
+        let local = mangle(name);
+
+        match **right {
+            Expression::Lambda(_) if !matches!(definition.t, Some(Type::Mut(_))) => {
+                self.hoist(Some(name), right).map_err(|err| located(err, definition.position, Some(name)))?;
+            },
+
+            Expression::Lambda(_) => {
+                let hoisted = self.hoist(None, right)?;
+                let declare = self.declare(&self.type_of(right), &local)?;
+
+                self.line(&format!("{} = {};", declare, hoisted));
+                self.bind(name.clone(), Local { name: local, len: None, function: false })
+            },
+
+            Expression::Array(ref content) => {
+                let element = match self.type_of(right) {
+                    Ty::Array(element) => element,
+                    _                  => unreachable!(),
+                };
+
+                let mut elements = Vec::new();
+
+                for e in content {
+                    elements.push(self.expression(e)?)
+                }
+
+                let declare = self.declare(&element, &format!("{}[{}]", local, content.len()))?;
+                self.line(&format!("{} = {{{}}};", declare, elements.join(", ")));
+
+                self.bind(name.clone(), Local { name: local, len: Some(content.len()), function: false })
+            },
+
+            _ => {
+                let t = self.type_of(right);
+
+                if let Ty::Array(_) = t {
+                    return Err(CodegenError::new_pos(definition.position, "arrays can only be defined by a literal in c"))
+                }
+
+                let value   = self.expression(right)?;
+                let declare = self.declare(&t, &local)?;
+
+                self.line(&format!("{} = {};", declare, value));
+                self.bind(name.clone(), Local { name: local, len: None, function: false })
+            },
+        }
+
+        Ok(())
+    }
+
+    // a top-level definition, a static every function can see and which
+    // `main` gives its value
+    fn global(&mut self, definition: &Definition) -> CodegenResult<()> {
+        let name  = name(&definition.name)?;
+        let local = self.globals[name].name.clone();
+
+        let right = match definition.right {
+            Some(ref right) => right,
+            None            => return Err(CodegenError::new_pos(definition.position, "definitions need a value in c")),
+        };
+
+        let t = self.type_of(right);
+
+        match **right {
+            Expression::Lambda(_) => {
+                let hoisted = self.hoist(None, right)?;
+
+                self.statics.push(format!("static {};", self.declare(&t, &local)?));
+                self.line(&format!("{} = {};", local, hoisted))
+            },
+
+            Expression::Array(ref content) => {
+                let element = match t {
+                    Ty::Array(ref element) => element.clone(),
+                    _                      => unreachable!(),
+                };
+
+                self.statics.push(format!("static {};", self.declare(&element, &format!("{}[{}]", local, content.len()))?));
+
+                for (i, e) in content.iter().enumerate() {
+                    let value = self.expression(e)?;
+                    self.line(&format!("{}[{}] = {};", local, i, value))
+                }
+            },
+
+            _ => {
+                if let Ty::Array(_) = t {
+                    return Err(CodegenError::new_pos(definition.position, "arrays can only be defined by a literal in c"))
+                }
+
+                self.statics.push(format!("static {};", self.declare(&t, &local)?));
+
+                let value = self.expression(right)?;
+                self.line(&format!("{} = {};", local, value))
+            },
+        }
+
+        Ok(())
+    }
+
+    // generates `expression` as statements, its value going to `tail`
+    fn tail(&mut self, expression: &Expression, tail: &Tail) -> CodegenResult<()> {
+        // a function without a result just runs off its end
+        let tail = if *tail == Tail::Return && self.type_of(expression) == Ty::Unit { &Tail::Discard } else { tail };
+
+        match *expression {
+            Expression::Block(ref statements) => {
+                self.line("{");
+                self.indent += 1;
+                self.scopes.push(HashMap::new());
+
+                let result = self.statements(statements, tail);
+
+                self.scopes.pop();
+                self.indent -= 1;
+                self.line("}");
+
+                result
+            },
+
+            Expression::If(ref conditional) if !simple(expression) || *tail == Tail::Discard => {
+                let condition = self.expression(&conditional.condition)?;
+                self.line(&format!("if ({}) {{", condition));
+
+                let branch = if conditional.otherwise.is_some() { tail } else { &Tail::Discard };
+
+                self.branch(&conditional.body, branch)?;
+
+                if let Some(ref otherwise) = conditional.otherwise {
+                    self.line("} else {");
+                    self.branch(otherwise, tail)?
+                }
+
+                self.line("}");
+
+                Ok(())
+            },
+
+            ref expression => {
+                let value = self.expression(expression)?;
+
+                match *tail {
+                    Tail::Return           => self.line(&format!("return {};", value)),
+                    Tail::Assign(ref name) => self.line(&format!("{} = {};", name, value)),
+                    Tail::Discard          => if !value.is_empty() && !pure(expression) {
+                        self.line(&format!("{};", value))
+                    },
+                }
+
+                Ok(())
+            },
+        }
+    }
+
+    fn branch(&mut self, expression: &Expression, tail: &Tail) -> CodegenResult<()> {
+        self.indent += 1;
+        self.scopes.push(HashMap::new());
+
+        let result = match *expression {
+            Expression::Block(ref statements) => self.statements(statements, tail),
+            ref expression                    => self.tail(expression, tail),
+        };
+
+        self.scopes.pop();
+        self.indent -= 1;
+
+        result
+    }
+
+    fn index(&mut self, index: &Index) -> CodegenResult<String> {
+        let array = match *index.id {
+            Expression::Identifier(_) | Expression::Index(_) => self.expression(&index.id)?,
+            ref id                                           => format!("({})", self.expression(id)?),
+        };
+
+        Ok(format!("{}[(size_t)({})]", array, self.expression(&index.index)?))
+    }
+
+    fn number(&self, n: f64, t: &Ty) -> String {
+        match self.inferer.resolve(t) {
+            Ty::Prim(Type::F32) => format!("{:?}f", n as f32),
+            Ty::Prim(Type::F64) => format!("{:?}", n),
+            ref t if t.is_numeric() => format!("{}", n as i128),
+            _ => format!("{:?}", n),
+        }
+    }
+
+    fn operand(&mut self, expression: &Expression) -> CodegenResult<String> {
+        let code = self.expression(expression)?;
+
+        match *expression {
+            Expression::Operation(_)         => Ok(format!("({})", code)),
+            Expression::Number(n) if n < 0.0 => Ok(format!("({})", code)),
+            _                                => Ok(code),
+        }
+    }
+
+    fn operation(&mut self, operation: &Operation) -> CodegenResult<String> {
+        let t     = self.inferer.resolve(&self.type_of(&operation.left));
+        let left  = self.operand(&operation.left)?;
+        let right = self.operand(&operation.right)?;
+
+        let float = match t {
+            Ty::Prim(Type::F32) | Ty::Prim(Type::F64) => true,
+            ref t @ Ty::Var(_)                        => self.inferer.numeric(t),
+            _                                         => false,
+        };
+
+        let suffix = if t == Ty::Prim(Type::F32) { "f" } else { "" };
+
+        let code = match operation.op {
+            _ if matches!(t, Ty::Array(_) | Ty::Function(..)) => {
+                return Err(CodegenError::new(&format!("c has no `{}` on {}", symbol(operation.op), t)))
+            },
+
+            Operand::Add if t == Ty::Prim(Type::Str) => {
+                return Err(CodegenError::new("joining strings needs memory c leaves to the programmer"))
+            },
+
+            Operand::Equal | Operand::NEqual if t == Ty::Prim(Type::Str) => {
+                self.string = true;
+                format!("strcmp({}, {}) {} 0", left, right, symbol(operation.op))
+            },
+
+            // the evaluator divides every number as f64
+            Operand::Div if !float => {
+                return Err(CodegenError::new(&format!("`/` on {} drops the fraction in c where `itu run` keeps it, divide f64 instead", t)))
+            },
+
+            Operand::Mod if float => {
+                self.math = true;
+                format!("fmod{}({}, {})", suffix, left, right)
+            },
+
+            Operand::Pow if float => {
+                self.math = true;
+                format!("pow{}({}, {})", suffix, left, right)
+            },
+
+            Operand::Pow => {
+                self.math = true;
+                format!("({})pow({}, {})", self.scalar(&t)?, left, right)
+            },
+
+            op => format!("{} {} {}", left, symbol(op), right),
+        };
+
+        Ok(code)
+    }
+
+    fn call(&mut self, call: &Call) -> CodegenResult<String> {
+        if let Expression::Field(ref field) = *call.callee {
+            if let Expression::Identifier(ref module) = *field.object {
+                let library = module.as_str();
+
+                if self.resolve(module).is_err() && ["math", "io", "array", "str"].contains(&library) {
+                    return self.library(library, &field.name, &call.args)
+                }
+            }
+        }
+
+        let callee = match *call.callee {
+            Expression::Identifier(ref name) => self.resolve(name)?.name,
+            ref callee                       => format!("({})", self.expression(callee)?),
+        };
+
+        let mut args = Vec::new();
+
+        for arg in &call.args {
+            args.push(self.expression(arg)?)
+        }
+
+        Ok(format!("{}({})", callee, args.join(", ")))
+    }
+
+    fn library(&mut self, module: &str, name: &str, args: &[Rc<Expression>]) -> CodegenResult<String> {
+        match (module, name) {
+            ("io", "print") => {
+                let t     = self.type_of(&args[0]);
+                let len   = self.len(&args[0])?;
+                let value = self.expression(&args[0])?;
+
+                self.show(&value, &t, len, false)?;
+                self.line("putchar('\\n');");
+
+                Ok(String::new())
+            },
+
+            ("array", "len") => match self.len(&args[0])? {
+                Some(len) => Ok(format!("{:?}", len as f64)),
+                None      => Err(CodegenError::new("array.len needs an array of known length in c")),
+            },
+
+            ("math", name) => {
+                let function = match name {
+                    "abs" => "fabs",
+                    "ln"  => "log",
+                    "min" => "fmin",
+                    "max" => "fmax",
+                    "sqrt" | "floor" | "ceil" | "round" | "sin" | "cos" | "tan" | "exp" | "pow" => name,
+                    _ => return Err(CodegenError::new(&format!("math.{} has no c equivalent", name))),
+                };
+
+                let mut values = Vec::new();
+
+                for arg in args {
+                    values.push(self.expression(arg)?)
+                }
+
+                self.math = true;
+
+                Ok(format!("{}({})", function, values.join(", ")))
+            },
+
+            (module, name) => Err(CodegenError::new(&format!("{}.{} has no c equivalent", module, name))),
+        }
+    }
+
+    // writes the statements printing `value`, as the evaluator displays it
+    fn show(&mut self, value: &str, t: &Ty, len: Option<usize>, quoted: bool) -> CodegenResult<()> {
+        match self.inferer.resolve(t) {
+            Ty::Prim(Type::Str) if quoted => self.line(&format!("printf(\"\\\"%s\\\"\", {});", value)),
+            Ty::Prim(Type::Str)           => self.line(&format!("fputs({}, stdout);", value)),
+            Ty::Prim(Type::Char) if quoted => self.line(&format!("printf(\"'%c'\", {});", value)),
+            Ty::Prim(Type::Char)          => self.line(&format!("putchar({});", value)),
+            Ty::Prim(Type::Bool)          => self.line(&format!("fputs({} ? \"true\" : \"false\", stdout);", value)),
+
+            Ty::Prim(Type::U08) | Ty::Prim(Type::U16) | Ty::Prim(Type::U32) | Ty::Prim(Type::U64) => {
+                self.line(&format!("printf(\"%llu\", (unsigned long long)({}));", value))
+            },
+
+            Ty::Prim(Type::I08) | Ty::Prim(Type::I16) | Ty::Prim(Type::I32) | Ty::Prim(Type::I64) => {
+                self.line(&format!("printf(\"%lld\", (long long)({}));", value))
+            },
+
+            ref t if t.is_numeric() || self.inferer.numeric(t) => {
+                self.print = true;
+                self.line(&format!("itu_print_double({});", value))
+            },
+
+            Ty::Unit => self.line("fputs(\"()\", stdout);"),
+
+            Ty::Array(ref element) => {
+                let len = match len {
+                    Some(len) => len,
+                    None      => return Err(CodegenError::new("only arrays of known length can be printed in c")),
+                };
+
+                // the array is evaluated once, then walked
+                let array = self.temporary();
+                let i     = self.temporary();
+
+                let declare = self.declare(element, &format!("*{}", array))?;
+
+                self.line(&format!("{} = {};", declare, value));
+                self.line("fputs(\"{\", stdout);");
+                self.line(&format!("for (size_t {0} = 0; {0} < {1}; {0}++) {{", i, len));
+
+                self.indent += 1;
+                self.line(&format!("if ({} > 0) fputs(\", \", stdout);", i));
+                self.show(&format!("{}[{}]", array, i), element, None, true)?;
+                self.indent -= 1;
+
+                self.line("}");
+                self.line("fputs(\"}\", stdout);")
+            },
+
+            ref t => return Err(CodegenError::new(&format!("values of type {} cannot be printed in c", t))),
+        }
+
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> CodegenResult<String> {
+        let code = match *expression {
+            Expression::Number(n)  => self.number(n, &self.type_of(expression)),
+            Expression::Bool(b)    => b.to_string(),
+            Expression::Str(ref s) => string(s),
+            Expression::Char(c)    => character(c)?,
+
+            Expression::Identifier(ref name) => self.resolve(name)?.name,
+
+            Expression::Operation(ref operation) => self.operation(operation)?,
+            Expression::Call(ref call)           => self.call(call)?,
+            Expression::Lambda(_)                => self.hoist(None, expression)?,
+            Expression::Index(ref index)         => self.index(index)?,
+
+            // a compound literal, which is how c hands an array to a function
+            Expression::Array(ref content) => {
+                let element = match self.type_of(expression) {
+                    Ty::Array(element) => element,
+                    _                  => unreachable!(),
+                };
+
+                let mut elements = Vec::new();
+
+                for e in content {
+                    elements.push(self.expression(e)?)
+                }
+
+                format!("({}){{{}}}", self.declare(&element, "[]")?, elements.join(", "))
+            },
+
+            Expression::Field(ref field) => match (&*field.object, field.name.as_str()) {
+                (Expression::Identifier(module), "pi")  if **module == "math" => format!("{:?}", ::std::f64::consts::PI),
+                (Expression::Identifier(module), "tau") if **module == "math" => format!("{:?}", ::std::f64::consts::TAU),
+                (Expression::Identifier(module), "e")   if **module == "math" => format!("{:?}", ::std::f64::consts::E),
+                _ => return Err(CodegenError::new(&format!("`{}` has no c equivalent", field.name))),
+            },
+
+            Expression::If(ref conditional) if simple(expression) => {
+                let condition = self.operand(&conditional.condition)?;
+                let body      = self.operand(&conditional.body)?;
+                let otherwise = self.operand(conditional.otherwise.as_ref().unwrap())?;
+
+                format!("({} ? {} : {})", condition, body, otherwise)
+            },
+
+            // anything needing statements goes through a temporary ahead of it
+            Expression::If(_) | Expression::Block(_) => {
+                let t = self.type_of(expression);
+
+                match t {
+                    Ty::Unit     => {
+                        self.tail(expression, &Tail::Discard)?;
+                        return Ok(String::new())
+                    },
+                    Ty::Array(_) => return Err(CodegenError::new("c cannot assign whole arrays")),
+                    _            => (),
+                }
+
+                let temporary = self.temporary();
+                let declare   = self.declare(&t, &temporary)?;
+
+                self.line(&format!("{};", declare));
+                self.tail(expression, &Tail::Assign(temporary.clone()))?;
+
+                temporary
+            },
+
+            Expression::Match(ref subject) => {
+                return Err(CodegenError::new_pos(subject.position, "match expressions have no c equivalent yet"))
+            },
+
+            Expression::EOF => String::new(),
+        };
+
+        Ok(code)
+    }
+}
+
+fn symbol(op: Operand) -> &'static str {
+    match op {
+        Operand::Pow     => "^",
+        Operand::Mul     => "*",
+        Operand::Div     => "/",
+        Operand::Mod     => "%",
+        Operand::Add     => "+",
+        Operand::Sub     => "-",
+        Operand::Equal   => "==",
+        Operand::NEqual  => "!=",
+        Operand::Lt      => "<",
+        Operand::Gt      => ">",
+        Operand::LtEqual => "<=",
+        Operand::GtEqual => ">=",
+    }
+}
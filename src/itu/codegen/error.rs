@@ -0,0 +1,42 @@
+use super::*;
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CodegenErrorValue {
+    Constant(String),
+}
+
+#[derive(Debug)]
+pub struct CodegenError {
+    pub value:    CodegenErrorValue,
+    pub position: Option<TokenPosition>,
+}
+
+#[allow(dead_code)]
+impl CodegenError {
+    pub fn new(value: &str) -> CodegenError {
+        CodegenError {
+            value:    CodegenErrorValue::Constant(value.to_owned()),
+            position: None,
+        }
+    }
+
+    pub fn new_pos(position: TokenPosition, value: &str) -> CodegenError {
+        CodegenError {
+            value:    CodegenErrorValue::Constant(value.to_owned()),
+            position: Some(position),
+        }
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            CodegenErrorValue::Constant(ref s) => match self.position {
+                Some(p) => write!(f, "{}: {}", p, s),
+                None    => write!(f, "{}", s),
+            }
+        }
+    }
+}
@@ -0,0 +1,774 @@
+use std::rc::Rc;
+use std::mem;
+use std::collections::HashSet;
+
+use super::*;
+
+const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+    "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+// helpers the generated code leans on where lua and itu disagree, they
+// are emitted ahead of the program only when something refers to them
+const SHOW: &str = r#"local function itu_show(v)
+  if type(v) == "number" then
+    if v == math.floor(v) and v > -1e15 and v < 1e15 then
+      return string.format("%d", v)
+    end
+    return tostring(v)
+  elseif type(v) == "table" then
+    local parts = {}
+    for i, x in ipairs(v) do
+      parts[i] = type(x) == "string" and string.format("%q", x) or itu_show(x)
+    end
+    return "{" .. table.concat(parts, ", ") .. "}"
+  elseif v == nil then
+    return "()"
+  end
+  return tostring(v)
+end
+"#;
+
+const EQ: &str = r#"local function itu_eq(a, b)
+  if type(a) ~= "table" or type(b) ~= "table" then
+    return a == b
+  end
+  if #a ~= #b then
+    return false
+  end
+  for i = 1, #a do
+    if not itu_eq(a[i], b[i]) then
+      return false
+    end
+  end
+  return true
+end
+"#;
+
+const FMOD: &str = "local itu_fmod = math.fmod\n";
+
+const MATH: &str = r#"local math = {
+  pi = math.pi,
+  tau = 2 * math.pi,
+  e = math.exp(1),
+  sqrt = math.sqrt,
+  abs = math.abs,
+  floor = math.floor,
+  ceil = math.ceil,
+  round = function(x)
+    if x < 0 then
+      return -math.floor(-x + 0.5)
+    end
+    return math.floor(x + 0.5)
+  end,
+  sin = math.sin,
+  cos = math.cos,
+  tan = math.tan,
+  exp = math.exp,
+  ln = math.log,
+  pow = function(a, b) return a ^ b end,
+  min = math.min,
+  max = math.max,
+}
+"#;
+
+const ARRAY: &str = r#"local array = {
+  push = function(xs, x) xs[#xs + 1] = x end,
+  pop = function(xs)
+    if #xs == 0 then
+      error("pop from empty array")
+    end
+    return table.remove(xs)
+  end,
+  len = function(xs) return #xs end,
+  map = function(xs, f)
+    local result = {}
+    for i, x in ipairs(xs) do
+      result[i] = f(x)
+    end
+    return result
+  end,
+  filter = function(xs, f)
+    local result = {}
+    for _, x in ipairs(xs) do
+      if f(x) then
+        result[#result + 1] = x
+      end
+    end
+    return result
+  end,
+  fold = function(xs, acc, f)
+    for _, x in ipairs(xs) do
+      acc = f(acc, x)
+    end
+    return acc
+  end,
+}
+"#;
+
+const STR: &str = r#"local str = {
+  split = function(s, sep)
+    local parts = {}
+    if sep == "" then
+      parts[1] = ""
+      for c in string.gmatch(s, ".") do
+        parts[#parts + 1] = c
+      end
+      parts[#parts + 1] = ""
+      return parts
+    end
+    local start = 1
+    while true do
+      local i, j = string.find(s, sep, start, true)
+      if not i then
+        parts[#parts + 1] = string.sub(s, start)
+        return parts
+      end
+      parts[#parts + 1] = string.sub(s, start, i - 1)
+      start = j + 1
+    end
+  end,
+  join = function(xs, sep)
+    local parts = {}
+    for i, x in ipairs(xs) do
+      parts[i] = itu_show(x)
+    end
+    return table.concat(parts, sep)
+  end,
+  trim = function(s) return (string.match(s, "^%s*(.-)%s*$")) end,
+  format = function(template, values)
+    local i = 0
+    return (string.gsub(template, "{}", function()
+      i = i + 1
+      if values[i] == nil then
+        error("too few values for format string")
+      end
+      return itu_show(values[i])
+    end))
+  end,
+}
+"#;
+
+const IO: &str = r#"local io = {
+  print = function(v) print(itu_show(v)) end,
+  read_line = function() return io.read("*l") or "" end,
+  read_file = function(path)
+    local file, err = io.open(path, "r")
+    if not file then
+      error("unable to read " .. path .. ": " .. err)
+    end
+    local content = file:read("*a")
+    file:close()
+    return content
+  end,
+  write_file = function(path, content)
+    local file, err = io.open(path, "w")
+    if not file then
+      error("unable to write " .. path .. ": " .. err)
+    end
+    file:write(content)
+    file:close()
+  end,
+}
+"#;
+
+// where the value of whatever is being generated goes
+#[derive(Debug, Clone, PartialEq)]
+enum Tail {
+    Return,
+    Discard,
+    Assign(String),
+}
+
+pub struct Lua<'a> {
+    inferer: &'a Inferer,
+    out:     String,
+    indent:  usize,
+    helpers: HashSet<&'static str>,
+    modules: HashSet<String>,
+}
+
+// translates a program into a lua chunk returning the value of its last
+// expression, `inferer` must have checked the same ast
+pub fn emit(ast: &[Statement], inferer: &Inferer) -> CodegenResult<String> {
+    let mut lua = Lua {
+        inferer,
+        out:     String::new(),
+        indent:  0,
+        helpers: HashSet::new(),
+        modules: HashSet::new(),
+    };
+
+    let tail = match ast.iter().rev().find(|s| !is_eof(s)) {
+        Some(&Statement::Expression(_, _)) => Tail::Return,
+        _                               => Tail::Discard,
+    };
+
+    lua.block(ast, &tail)?;
+
+    Ok(lua.prelude() + &lua.out)
+}
+
+fn is_eof(statement: &Statement) -> bool {
+    match *statement {
+        Statement::Expression(ref e, _) => **e == Expression::EOF,
+        _                            => false,
+    }
+}
+
+// itu names may carry `?` and `'`, lua names may not
+pub fn mangle(name: &str) -> String {
+    let mut out = String::new();
+
+    for c in name.chars() {
+        match c {
+            '?'                                        => out.push_str("_p"),
+            '\''                                       => out.push_str("_q"),
+            c if c.is_ascii_alphanumeric() || c == '_' => out.push(c),
+            c                                          => out.push_str(&format!("_u{:x}", c as u32)),
+        }
+    }
+
+    if KEYWORDS.contains(&out.as_str()) {
+        out.push('_')
+    }
+
+    out
+}
+
+fn string(s: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"'  => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\{}", c as u32)),
+            c    => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+fn number(n: f64) -> String {
+    if n.is_nan() {
+        "(0 / 0)".to_owned()
+    } else if n.is_infinite() {
+        if n > 0.0 { "(1 / 0)".to_owned() } else { "(-1 / 0)".to_owned() }
+    } else if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{:?}", n)
+    }
+}
+
+// whether `name` shows up anywhere inside `expression`
+fn mentions(expression: &Expression, name: &str) -> bool {
+    let mut mentions = Mentions { name, found: false };
+    mentions.visit_expression(expression);
+
+    mentions.found
+}
+
+struct Mentions<'a> {
+    name:  &'a str,
+    found: bool,
+}
+
+impl<'a> Visitor for Mentions<'a> {
+    fn visit_identifier(&mut self, name: &Rc<String>) {
+        self.found |= **name == self.name
+    }
+}
+
+fn lambda_definition(statement: &Statement) -> Option<(&Rc<String>, &Lambda)> {
+    if let Statement::Definition(ref definition) = *statement {
+        if let (Expression::Identifier(name), Some(Expression::Lambda(lambda))) = (&*definition.name, definition.right.as_deref()) {
+            return Some((name, lambda))
+        }
+    }
+
+    None
+}
+
+#[allow(dead_code)]
+impl<'a> Lua<'a> {
+    fn prelude(&self) -> String {
+        let mut out = String::new();
+
+        let show = self.helpers.contains("show")
+            || ["str", "io"].iter().any(|m| self.modules.contains(*m));
+
+        // helpers first, they must see lua's own math before it is shadowed
+        if self.helpers.contains("fmod") {
+            out.push_str(FMOD)
+        }
+
+        if show {
+            out.push_str(SHOW)
+        }
+
+        if self.helpers.contains("eq") {
+            out.push_str(EQ)
+        }
+
+        for &(name, source) in &[("math", MATH), ("array", ARRAY), ("str", STR), ("io", IO)] {
+            if self.modules.contains(name) {
+                out.push_str(source)
+            }
+        }
+
+        if !out.is_empty() {
+            out.push('\n')
+        }
+
+        out
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0 .. self.indent {
+            self.out.push_str("  ")
+        }
+
+        self.out.push_str(text);
+        self.out.push('\n')
+    }
+
+    fn is_str(&self, expression: &Expression) -> bool {
+        self.inferer.type_of(expression) == Some(Ty::Prim(Type::Str))
+    }
+
+    fn is_array(&self, expression: &Expression) -> bool {
+        matches!(self.inferer.type_of(expression), Some(Ty::Array(_)))
+    }
+
+    fn unit(&mut self, tail: &Tail) {
+        match *tail {
+            Tail::Return        => self.line("return nil"),
+            Tail::Assign(ref t) => self.line(&format!("{} = nil", t)),
+            Tail::Discard       => (),
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement], tail: &Tail) -> CodegenResult<()> {
+        let statements = statements.iter().filter(|s| !is_eof(s)).collect::<Vec<_>>();
+
+        // lambdas an earlier lambda of the block refers to must already be
+        // declared when that one is created
+        let mut forward = Vec::new();
+
+        for (i, statement) in statements.iter().enumerate() {
+            if let Some((name, _)) = lambda_definition(statement) {
+                let used = statements[.. i].iter().any(|earlier| match lambda_definition(earlier) {
+                    Some((_, lambda)) => mentions(&lambda.body, name),
+                    None              => false,
+                });
+
+                if used && !forward.contains(name) {
+                    forward.push(name.clone())
+                }
+            }
+        }
+
+        if !forward.is_empty() {
+            let names = forward.iter().map(|n| mangle(n)).collect::<Vec<_>>();
+            self.line(&format!("local {}", names.join(", ")))
+        }
+
+        if statements.is_empty() {
+            self.unit(tail)
+        }
+
+        for (i, statement) in statements.iter().enumerate() {
+            let last = i + 1 == statements.len();
+
+            // the first definition of a forward declared name fills it in
+            let declared = match lambda_definition(statement) {
+                Some((name, _)) => match forward.iter().position(|n| n == name) {
+                    Some(at) => {
+                        forward.remove(at);
+                        true
+                    },
+                    None => false,
+                },
+                None => false,
+            };
+
+            self.statement(statement, if last { tail } else { &Tail::Discard }, declared)?
+        }
+
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement, tail: &Tail, declared: bool) -> CodegenResult<()> {
+        match *statement {
+            Statement::Expression(ref expression, _) => self.tail(expression, tail),
+
+            Statement::Assignment(ref assignment) => {
+                let target = match *assignment.left {
+                    Expression::Identifier(ref name) => mangle(name),
+                    Expression::Index(ref index)     => self.index(index)?,
+                    _ => return Err(CodegenError::new_pos(assignment.position, "invalid left-hand side of assignment")),
+                };
+
+                self.tail(&assignment.right, &Tail::Assign(target))?;
+                self.unit(tail);
+
+                Ok(())
+            },
+
+            Statement::Definition(ref definition) => {
+                let name = match *definition.name {
+                    Expression::Identifier(ref name) => mangle(name),
+                    _ => return Err(CodegenError::new_pos(definition.position, "expected name")),
+                };
+
+                match definition.right.as_deref() {
+                    Some(Expression::Lambda(lambda)) => {
+                        let header = if declared { "function" } else { "local function" };
+                        let params = lambda.params.iter().map(|(_, p)| mangle(p)).collect::<Vec<_>>();
+
+                        self.line(&format!("{} {}({})", header, name, params.join(", ")));
+                        self.body(&lambda.body)?;
+                        self.line("end")
+                    },
+
+                    Some(right @ Expression::If(_)) | Some(right @ Expression::Block(_)) => {
+                        self.line(&format!("local {}", name));
+                        self.tail(right, &Tail::Assign(name))?
+                    },
+
+                    Some(right) => {
+                        let value = self.expression(right)?;
+                        self.line(&format!("local {} = {}", name, value))
+                    },
+
+                    None => self.line(&format!("local {}", name)),
+                }
+
+                self.unit(tail);
+
+                Ok(())
+            },
+
+            Statement::Test(_) => unreachable!(),
+
+            Statement::Import(ref import) => Err(CodegenError::new_pos(import.position, "imports cannot be emitted as lua")),
+        }
+    }
+
+    fn body(&mut self, body: &Expression) -> CodegenResult<()> {
+        self.indent += 1;
+
+        let result = match *body {
+            Expression::Block(ref statements) => self.block(statements, &Tail::Return),
+            ref body                          => self.tail(body, &Tail::Return),
+        };
+
+        self.indent -= 1;
+
+        result
+    }
+
+    // generates `expression` as statements, handing its value to `tail`
+    fn tail(&mut self, expression: &Expression, tail: &Tail) -> CodegenResult<()> {
+        match *expression {
+            Expression::If(ref branch) => {
+                let mut branch    = branch;
+                let mut condition = self.expression(&branch.condition)?;
+
+                self.line(&format!("if {} then", condition));
+
+                loop {
+                    self.branch(&branch.body, tail)?;
+
+                    match branch.otherwise.as_deref() {
+                        Some(Expression::If(next)) => {
+                            branch    = next;
+                            condition = self.expression(&branch.condition)?;
+
+                            self.line(&format!("elseif {} then", condition))
+                        },
+
+                        Some(otherwise) => {
+                            self.line("else");
+                            self.branch(otherwise, tail)?;
+
+                            break
+                        },
+
+                        None => {
+                            if let Tail::Assign(_) = *tail {
+                                self.line("else");
+
+                                self.indent += 1;
+                                self.unit(tail);
+                                self.indent -= 1;
+                            }
+
+                            break
+                        },
+                    }
+                }
+
+                self.line("end");
+            },
+
+            Expression::Block(ref statements) => {
+                self.line("do");
+
+                self.indent += 1;
+                self.block(statements, tail)?;
+                self.indent -= 1;
+
+                self.line("end");
+            },
+
+            ref expression => {
+                let value = self.expression(expression)?;
+
+                match *tail {
+                    Tail::Return        => self.line(&format!("return {}", value)),
+                    Tail::Assign(ref t) => self.line(&format!("{} = {}", t, value)),
+                    Tail::Discard       => match *expression {
+                        Expression::Call(ref call) if self.simple(&call.callee) => self.line(&value),
+                        _ => self.line(&format!("local _ = {}", value)),
+                    },
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    // an indented branch of an `if`, already a scope of its own in lua
+    fn branch(&mut self, body: &Expression, tail: &Tail) -> CodegenResult<()> {
+        self.indent += 1;
+
+        let result = match *body {
+            Expression::Block(ref statements) => self.block(statements, tail),
+            ref body                          => self.tail(body, tail),
+        };
+
+        self.indent -= 1;
+
+        result
+    }
+
+    // whether `expression` may start a call statement without parentheses
+    fn simple(&self, expression: &Expression) -> bool {
+        match *expression {
+            Expression::Identifier(_)    => true,
+            Expression::Field(ref field) => self.simple(&field.object),
+            Expression::Index(ref index) => self.simple(&index.id),
+            Expression::Call(ref call)   => self.simple(&call.callee),
+            _                            => false,
+        }
+    }
+
+    fn precedence(&self, expression: &Expression) -> u8 {
+        match *expression {
+            Expression::Operation(ref operation) => match operation.op {
+                Operand::Equal | Operand::NEqual if self.is_array(&operation.left) => if operation.op == Operand::Equal { 10 } else { 7 },
+
+                Operand::Equal | Operand::NEqual |
+                Operand::Lt | Operand::Gt | Operand::LtEqual | Operand::GtEqual => 3,
+
+                Operand::Add if self.is_str(expression) => 4,
+                Operand::Add | Operand::Sub             => 5,
+                Operand::Mul | Operand::Div             => 6,
+                Operand::Mod                            => 10,
+                Operand::Pow                            => 8,
+            },
+
+            Expression::Number(n) if n < 0.0 => 7,
+
+            _ => 10,
+        }
+    }
+
+    fn operand(&mut self, expression: &Expression, min: u8) -> CodegenResult<String> {
+        let value = self.expression(expression)?;
+
+        if self.precedence(expression) < min {
+            Ok(format!("({})", value))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn operation(&mut self, expression: &Expression, operation: &Operation) -> CodegenResult<String> {
+        let (left, right) = (&*operation.left, &*operation.right);
+
+        let symbol = match operation.op {
+            Operand::Equal | Operand::NEqual if self.is_array(left) => {
+                self.helpers.insert("eq");
+
+                let call = format!("itu_eq({}, {})", self.expression(left)?, self.expression(right)?);
+
+                return Ok(if operation.op == Operand::Equal { call } else { format!("not {}", call) })
+            },
+
+            Operand::Mod => {
+                self.helpers.insert("fmod");
+                return Ok(format!("itu_fmod({}, {})", self.expression(left)?, self.expression(right)?))
+            },
+
+            Operand::Add if self.is_str(expression) => {
+                // joining strings is associative, so neither side needs parentheses
+                let (l, r) = (self.concatenated(left, 4)?, self.concatenated(right, 4)?);
+
+                return Ok(format!("{} .. {}", l, r))
+            },
+
+            Operand::Pow => {
+                let (l, r) = (self.operand(left, 9)?, self.operand(right, 8)?);
+                return Ok(format!("{} ^ {}", l, r))
+            },
+
+            Operand::Add     => "+",
+            Operand::Sub     => "-",
+            Operand::Mul     => "*",
+            Operand::Div     => "/",
+            Operand::Equal   => "==",
+            Operand::NEqual  => "~=",
+            Operand::Lt      => "<",
+            Operand::Gt      => ">",
+            Operand::LtEqual => "<=",
+            Operand::GtEqual => ">=",
+        };
+
+        let precedence = self.precedence(expression);
+
+        let l = self.operand(left, precedence)?;
+        let r = self.operand(right, precedence + 1)?;
+
+        Ok(format!("{} {} {}", l, symbol, r))
+    }
+
+    // lua only joins strings and numbers, anything else is shown the way itu would
+    fn concatenated(&mut self, expression: &Expression, min: u8) -> CodegenResult<String> {
+        if self.is_str(expression) {
+            self.operand(expression, min)
+        } else {
+            self.helpers.insert("show");
+            Ok(format!("itu_show({})", self.expression(expression)?))
+        }
+    }
+
+    fn index(&mut self, index: &Index) -> CodegenResult<String> {
+        let id = match *index.id {
+            Expression::Identifier(_) | Expression::Field(_) | Expression::Index(_) | Expression::Call(_) => self.expression(&index.id)?,
+            ref id => format!("({})", self.expression(id)?),
+        };
+
+        // itu counts from zero, lua from one
+        let at = match *index.index {
+            Expression::Number(n) => number(n + 1.0),
+            ref at                => format!("{} + 1", self.operand(at, 5)?),
+        };
+
+        if self.is_str(&index.id) {
+            Ok(format!("string.sub({}, {}, {})", id, at, at))
+        } else {
+            Ok(format!("{}[{}]", id, at))
+        }
+    }
+
+    fn lambda(&mut self, lambda: &Lambda) -> CodegenResult<String> {
+        let params = lambda.params.iter().map(|(_, p)| mangle(p)).collect::<Vec<_>>().join(", ");
+
+        match *lambda.body {
+            Expression::Block(_) | Expression::If(_) => {
+                let outer = mem::take(&mut self.out);
+
+                self.body(&lambda.body)?;
+
+                let body = mem::replace(&mut self.out, outer);
+
+                Ok(format!("function({})\n{}{}end", params, body, "  ".repeat(self.indent)))
+            },
+
+            ref body => Ok(format!("function({}) return {} end", params, self.expression(body)?)),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> CodegenResult<String> {
+        let value = match *expression {
+            Expression::Number(n)  => number(n),
+            Expression::Bool(b)    => b.to_string(),
+            Expression::Str(ref s) => string(s),
+            Expression::Char(c)    => string(&c.to_string()),
+
+            Expression::Identifier(ref name) => {
+                if ["math", "array", "str", "io"].contains(&name.as_str()) {
+                    self.modules.insert(name.to_string());
+                }
+
+                mangle(name)
+            },
+
+            Expression::Operation(ref operation) => self.operation(expression, operation)?,
+
+            Expression::Call(ref call) => {
+                let callee = if self.simple(&call.callee) {
+                    self.expression(&call.callee)?
+                } else {
+                    format!("({})", self.expression(&call.callee)?)
+                };
+
+                let mut args = Vec::new();
+
+                for arg in &call.args {
+                    args.push(self.expression(arg)?)
+                }
+
+                format!("{}({})", callee, args.join(", "))
+            },
+
+            Expression::Lambda(ref lambda) => self.lambda(lambda)?,
+
+            Expression::Array(ref content) => {
+                let mut values = Vec::new();
+
+                for element in content {
+                    values.push(self.expression(element)?)
+                }
+
+                format!("{{{}}}", values.join(", "))
+            },
+
+            Expression::Index(ref index) => self.index(index)?,
+
+            Expression::Field(ref field) => format!("{}.{}", self.expression(&field.object)?, mangle(&field.name)),
+
+            // statements in expression position run inside a function of their own
+            Expression::If(_) | Expression::Block(_) => {
+                let outer = mem::take(&mut self.out);
+
+                self.indent += 1;
+                let result = self.tail(expression, &Tail::Return);
+                self.indent -= 1;
+
+                let body = mem::replace(&mut self.out, outer);
+                result?;
+
+                format!("(function()\n{}{}end)()", body, "  ".repeat(self.indent))
+            },
+
+            Expression::Match(ref subject) => {
+                return Err(CodegenError::new_pos(subject.position, "match expressions have no lua equivalent yet"))
+            },
+
+            Expression::EOF => "nil".to_owned(),
+        };
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,26 @@
+pub mod error;
+pub mod c;
+pub mod lua;
+pub mod rust;
+pub mod wasm;
+
+pub use super::*;
+
+pub use self::error::*;
+
+pub type CodegenResult<T> = Result<T, CodegenError>;
+
+// the program and its types, as `itu emit` hands them to a backend
+#[cfg(test)]
+pub fn typed(source: &str) -> (Vec<Statement>, Inferer) {
+    let lexer      = lexer(&mut source.chars());
+    let mut parser = Parser::new(Traveler::new(lexer.collect()));
+
+    let ast = parser.parse().unwrap();
+
+    let mut inferer = Inferer::new();
+    stdlib::declare(&mut inferer);
+    inferer.infer(&ast).unwrap();
+
+    (ast, inferer)
+}
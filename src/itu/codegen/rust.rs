@@ -0,0 +1,1188 @@
+use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+    "true", "type", "unsafe", "use", "where", "while",
+];
+
+const HEADER: &str = "// generated by itu\n#![allow(unused_mut, unused_variables, unused_parens, unused_braces, non_snake_case, non_upper_case_globals, dead_code)]\n\nuse std::rc::Rc;\n";
+
+// how values are printed, mirroring the evaluator's display of them
+const SHOW: &str = r#"pub trait Show {
+    fn show(&self) -> String;
+
+    fn repr(&self) -> String {
+        self.show()
+    }
+}
+
+macro_rules! show {
+    ($($t:ty),*) => {
+        $(impl Show for $t {
+            fn show(&self) -> String {
+                format!("{}", self)
+            }
+        })*
+    };
+}
+
+show!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64, bool);
+
+impl Show for () {
+    fn show(&self) -> String {
+        "()".to_owned()
+    }
+}
+
+impl Show for char {
+    fn show(&self) -> String {
+        self.to_string()
+    }
+
+    fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl Show for String {
+    fn show(&self) -> String {
+        self.clone()
+    }
+
+    fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl<A: Show> Show for Vec<A> {
+    fn show(&self) -> String {
+        format!("{{{}}}", self.iter().map(|v| v.repr()).collect::<Vec<_>>().join(", "))
+    }
+}
+
+impl<A: Show, const N: usize> Show for [A; N] {
+    fn show(&self) -> String {
+        format!("{{{}}}", self.iter().map(|v| v.repr()).collect::<Vec<_>>().join(", "))
+    }
+}
+"#;
+
+const MATH: &str = r#"mod itu_math {
+    pub const pi:  f64 = std::f64::consts::PI;
+    pub const tau: f64 = std::f64::consts::TAU;
+    pub const e:   f64 = std::f64::consts::E;
+
+    pub fn sqrt(x: f64) -> f64 { x.sqrt() }
+    pub fn abs(x: f64) -> f64 { x.abs() }
+    pub fn floor(x: f64) -> f64 { x.floor() }
+    pub fn ceil(x: f64) -> f64 { x.ceil() }
+    pub fn round(x: f64) -> f64 { x.round() }
+    pub fn sin(x: f64) -> f64 { x.sin() }
+    pub fn cos(x: f64) -> f64 { x.cos() }
+    pub fn tan(x: f64) -> f64 { x.tan() }
+    pub fn exp(x: f64) -> f64 { x.exp() }
+    pub fn ln(x: f64) -> f64 { x.ln() }
+
+    pub fn pow(a: f64, b: f64) -> f64 { a.powf(b) }
+    pub fn min(a: f64, b: f64) -> f64 { a.min(b) }
+    pub fn max(a: f64, b: f64) -> f64 { a.max(b) }
+}
+"#;
+
+const ARRAY: &str = r#"mod itu_array {
+    use std::rc::Rc;
+
+    pub fn push<A>(xs: &mut Vec<A>, x: A) {
+        xs.push(x)
+    }
+
+    pub fn pop<A>(xs: &mut Vec<A>) -> A {
+        xs.pop().expect("pop from empty array")
+    }
+
+    pub fn len<A>(xs: Vec<A>) -> f64 {
+        xs.len() as f64
+    }
+
+    pub fn map<A, B>(xs: Vec<A>, f: Rc<dyn Fn(A) -> B>) -> Vec<B> {
+        xs.into_iter().map(|x| f(x)).collect()
+    }
+
+    pub fn filter<A: Clone>(xs: Vec<A>, f: Rc<dyn Fn(A) -> bool>) -> Vec<A> {
+        xs.into_iter().filter(|x| f(x.clone())).collect()
+    }
+
+    pub fn fold<A, B>(xs: Vec<A>, acc: B, f: Rc<dyn Fn(B, A) -> B>) -> B {
+        xs.into_iter().fold(acc, |acc, x| f(acc, x))
+    }
+}
+"#;
+
+const STR: &str = r#"mod itu_str {
+    use super::Show;
+
+    pub fn split(s: String, sep: String) -> Vec<String> {
+        s.split(sep.as_str()).map(String::from).collect()
+    }
+
+    pub fn join(xs: Vec<String>, sep: String) -> String {
+        xs.join(&sep)
+    }
+
+    pub fn trim(s: String) -> String {
+        s.trim().to_owned()
+    }
+
+    pub fn format<A: Show>(template: String, values: Vec<A>) -> String {
+        let mut pieces = template.split("{}");
+        let mut result = pieces.next().unwrap_or("").to_owned();
+
+        for (i, piece) in pieces.enumerate() {
+            result.push_str(&values.get(i).expect("too few values for format string").show());
+            result.push_str(piece)
+        }
+
+        result
+    }
+}
+"#;
+
+const IO: &str = r#"mod itu_io {
+    use super::Show;
+
+    pub fn print<A: Show>(v: A) {
+        println!("{}", v.show())
+    }
+
+    pub fn read_line() -> String {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).expect("unable to read line");
+
+        line.trim_end_matches(&['\n', '\r'][..]).to_owned()
+    }
+
+    pub fn read_file(path: String) -> String {
+        std::fs::read_to_string(&path).unwrap_or_else(|why| panic!("unable to read {}: {}", path, why))
+    }
+
+    pub fn write_file(path: String, content: String) {
+        std::fs::write(&path, content).unwrap_or_else(|why| panic!("unable to write {}: {}", path, why))
+    }
+}
+"#;
+
+const LIBRARIES: [&str; 4] = ["math", "array", "str", "io"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    // a fn item, callable from anywhere it is in scope
+    Item,
+    Local,
+    Module,
+}
+
+// what a scope sits behind: fn items see no locals of the scopes around
+// them, closures see them through a clone of their own
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Boundary {
+    None,
+    Item,
+    Closure(usize),
+}
+
+struct Scope {
+    names:    HashMap<Rc<String>, Kind>,
+    boundary: Boundary,
+}
+
+pub struct Rust<'a> {
+    inferer:  &'a Inferer,
+    indent:   usize,
+    scopes:   Vec<Scope>,
+    captures: Vec<Vec<Rc<String>>>,
+    generics: HashMap<usize, String>,
+    bounds:   HashMap<String, Vec<&'static str>>,
+    mutated:  HashSet<Rc<String>>,
+    modules:  HashSet<&'static str>,
+    show:     bool,
+    // set when a fn item reached for a local, it is then retried as a closure
+    crossed:  bool,
+}
+
+// translates a type-checked program into a rust crate root, top-level
+// functions become fn items and everything else the body of `main`
+pub fn emit(ast: &[Statement], inferer: &Inferer) -> CodegenResult<String> {
+    let mut rust = Rust {
+        inferer,
+        indent:   1,
+        scopes:   vec![Scope { names: HashMap::new(), boundary: Boundary::None }],
+        captures: Vec::new(),
+        generics: HashMap::new(),
+        bounds:   HashMap::new(),
+        mutated:  HashSet::new(),
+        modules:  HashSet::new(),
+        show:     false,
+        crossed:  false,
+    };
+
+    for statement in ast {
+        mutations(statement, &mut rust.mutated)
+    }
+
+    let (items, mut lines) = rust.sequence(ast, true)?;
+
+    // the value of the program is printed, like `itu run` does
+    if let Some(Statement::Expression(expression, _)) = ast.iter().rev().find(|s| !is_eof(s)) {
+        let value = lines.pop().unwrap();
+
+        let line = match rust.type_of(expression) {
+            Ty::Unit                  => format!("{};", value),
+            ref t if rust.showable(t) => {
+                rust.show = true;
+                format!("println!(\"{{}}\", Show::show(&{}));", value)
+            },
+            _                         => format!("let _ = {};", value),
+        };
+
+        lines.push(line)
+    }
+
+    let mut out = String::from(HEADER);
+
+    if rust.show || rust.modules.contains("str") || rust.modules.contains("io") {
+        out.push('\n');
+        out.push_str(SHOW)
+    }
+
+    for &(name, source) in &[("math", MATH), ("array", ARRAY), ("str", STR), ("io", IO)] {
+        if rust.modules.contains(name) {
+            out.push('\n');
+            out.push_str(source)
+        }
+    }
+
+    for item in items {
+        out.push('\n');
+        out.push_str(&item);
+        out.push('\n')
+    }
+
+    out.push_str("\nfn main() {\n");
+
+    for line in lines {
+        out.push_str("    ");
+        out.push_str(&line);
+        out.push('\n')
+    }
+
+    out.push_str("}\n");
+
+    Ok(out)
+}
+
+// places an error raised without a position at the statement it was
+// raised in
+fn located(err: CodegenError, position: TokenPosition) -> CodegenError {
+    match err.position {
+        Some(_) => err,
+        None    => {
+            let CodegenErrorValue::Constant(ref value) = err.value;
+            CodegenError::new_pos(position, value)
+        },
+    }
+}
+
+fn is_eof(statement: &Statement) -> bool {
+    match *statement {
+        Statement::Expression(ref e, _) => **e == Expression::EOF,
+        _                            => false,
+    }
+}
+
+fn name(expression: &Expression) -> CodegenResult<&Rc<String>> {
+    match *expression {
+        Expression::Identifier(ref name) => Ok(name),
+        _                                => Err(CodegenError::new(&format!("expected name, found: {:?}", expression))),
+    }
+}
+
+// a lambda definition, and whether it may become a fn item
+fn item(statement: &Statement) -> Option<(&Rc<String>, &Expression, bool)> {
+    if let Statement::Definition(ref definition) = *statement {
+        if let (Expression::Identifier(name), Some(right)) = (&*definition.name, definition.right.as_ref()) {
+            if let Expression::Lambda(_) = **right {
+                let mutable = matches!(definition.t, Some(Type::Mut(_)));
+                return Some((name, right, !mutable))
+            }
+        }
+    }
+
+    None
+}
+
+fn fixed(t: Option<&Type>) -> bool {
+    match t {
+        Some(Type::Array(_, Some(_))) => true,
+        Some(Type::Mut(Some(t)))      => fixed(Some(t)),
+        _                             => false,
+    }
+}
+
+// names that are ever written to, or whose array is, and so need `mut`
+struct Mutations<'a>(&'a mut HashSet<Rc<String>>);
+
+impl<'a> Mutations<'a> {
+    fn place(&mut self, expression: &Expression) {
+        match *expression {
+            Expression::Identifier(ref name) => {
+                self.0.insert(name.clone());
+            },
+            Expression::Index(ref index) => self.place(&index.id),
+            _ => (),
+        }
+    }
+}
+
+impl<'a> Visitor for Mutations<'a> {
+    fn visit_assignment(&mut self, assignment: &Assignment) {
+        self.place(&assignment.left);
+        self.visit_expression(&assignment.right)
+    }
+
+    fn visit_call(&mut self, c: &Call) {
+        if let Expression::Field(ref field) = *c.callee {
+            if *field.object == Expression::Identifier(Rc::new("array".to_owned())) && (*field.name == "push" || *field.name == "pop") {
+                if let Some(first) = c.args.first() {
+                    self.place(first)
+                }
+            }
+        }
+
+        walk_call(self, c)
+    }
+}
+
+fn mutations(statement: &Statement, acc: &mut HashSet<Rc<String>>) {
+    Mutations(acc).visit_statement(statement)
+}
+
+fn mentions(expression: &Expression, name: &str) -> bool {
+    let mut acc = HashSet::new();
+    references(expression, &mut acc);
+
+    acc.iter().any(|n| **n == name)
+}
+
+struct References<'a>(&'a mut HashSet<Rc<String>>);
+
+impl<'a> Visitor for References<'a> {
+    fn visit_identifier(&mut self, name: &Rc<String>) {
+        self.0.insert(name.clone());
+    }
+}
+
+fn references(expression: &Expression, acc: &mut HashSet<Rc<String>>) {
+    References(acc).visit_expression(expression)
+}
+
+fn variables(t: &Ty, acc: &mut Vec<usize>) {
+    match *t {
+        Ty::Var(v) if !acc.contains(&v) => acc.push(v),
+        Ty::Array(ref t)                  => variables(t, acc),
+        Ty::Function(ref params, ref ret) => {
+            for p in params {
+                variables(p, acc)
+            }
+
+            variables(ret, acc)
+        },
+        _ => (),
+    }
+}
+
+fn generic_name(i: usize) -> String {
+    let letter = (b'A' + (i % 26) as u8) as char;
+
+    if i < 26 {
+        letter.to_string()
+    } else {
+        format!("{}{}", letter, i / 26)
+    }
+}
+
+fn symbol(op: Operand) -> &'static str {
+    match op {
+        Operand::Pow     => "^",
+        Operand::Mul     => "*",
+        Operand::Div     => "/",
+        Operand::Mod     => "%",
+        Operand::Add     => "+",
+        Operand::Sub     => "-",
+        Operand::Equal   => "==",
+        Operand::NEqual  => "!=",
+        Operand::Lt      => "<",
+        Operand::Gt      => ">",
+        Operand::LtEqual => "<=",
+        Operand::GtEqual => ">=",
+    }
+}
+
+pub fn mangle(name: &str) -> String {
+    let mut out = String::new();
+
+    for c in name.chars() {
+        match c {
+            '?'  => out.push_str("_p"),
+            '\'' => out.push_str("_q"),
+            c    => out.push(c),
+        }
+    }
+
+    if KEYWORDS.contains(&out.as_str()) {
+        format!("r#{}", out)
+    } else {
+        out
+    }
+}
+
+pub fn primitive(t: &Type) -> Option<&'static str> {
+    let name = match *t {
+        Type::I08  => "i8",
+        Type::I16  => "i16",
+        Type::I32  => "i32",
+        Type::I64  => "i64",
+        Type::I128 => "i128",
+        Type::F32  => "f32",
+        Type::F64  => "f64",
+        Type::U08  => "u8",
+        Type::U16  => "u16",
+        Type::U32  => "u32",
+        Type::U64  => "u64",
+        Type::U128 => "u128",
+        Type::Char => "char",
+        Type::Str  => "String",
+        Type::Bool => "bool",
+        _          => return None,
+    };
+
+    Some(name)
+}
+
+// the rust spelling of an annotation, `None` when it leaves the type open
+pub fn spelling(t: &Type) -> CodegenResult<Option<String>> {
+    let name = match *t {
+        Type::Mut(None)             => return Ok(None),
+        Type::Mut(Some(ref t))      => return spelling(t),
+        Type::Array(ref t, ref len) => {
+            let element = spelling(t)?.unwrap_or_else(|| "_".to_owned());
+
+            match *len {
+                None                        => format!("Vec<{}>", element),
+                Some(Expression::Number(n)) => format!("[{}; {}]", element, n as usize),
+                Some(_)                     => return Err(CodegenError::new("array lengths must be number literals")),
+            }
+        },
+        Type::Identifier(ref name) => mangle(name),
+        Type::Any                  => return Err(CodegenError::new("`any` has no rust equivalent")),
+        ref t                      => primitive(t).unwrap().to_owned(),
+    };
+
+    Ok(Some(name))
+}
+
+#[allow(dead_code)]
+impl<'a> Rust<'a> {
+    fn enter(&mut self, boundary: Boundary) {
+        self.scopes.push(Scope { names: HashMap::new(), boundary })
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn bind(&mut self, name: Rc<String>, kind: Kind) {
+        self.scopes.last_mut().unwrap().names.insert(name, kind);
+    }
+
+    fn pad(&self) -> String {
+        "    ".repeat(self.indent)
+    }
+
+    // finds what `name` is, noting it as a capture of every closure it is
+    // reached through
+    fn resolve(&mut self, name: &Rc<String>) -> CodegenResult<Kind> {
+        let mut closures = Vec::new();
+        let mut item     = false;
+
+        for scope in self.scopes.iter().rev() {
+            if let Some(&kind) = scope.names.get(name) {
+                if kind == Kind::Local {
+                    if item {
+                        self.crossed = true;
+                        return Err(CodegenError::new(&format!("`{}` is out of reach of a fn item", name)))
+                    }
+
+                    for &c in &closures {
+                        let captures: &mut Vec<Rc<String>> = &mut self.captures[c];
+
+                        if !captures.contains(name) {
+                            captures.push(name.clone())
+                        }
+                    }
+                }
+
+                return Ok(kind)
+            }
+
+            match scope.boundary {
+                Boundary::Item       => item = true,
+                Boundary::Closure(c) => closures.push(c),
+                Boundary::None       => (),
+            }
+        }
+
+        if LIBRARIES.contains(&name.as_str()) {
+            return Ok(Kind::Module)
+        }
+
+        Err(CodegenError::new(&format!("unknown name `{}`", name)))
+    }
+
+    // whether `name` lives outside the innermost closure
+    fn captured(&self, name: &Rc<String>) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if scope.names.contains_key(name) {
+                return false
+            }
+
+            if let Boundary::Closure(_) = scope.boundary {
+                return true
+            }
+        }
+
+        false
+    }
+
+    fn module(&mut self, expression: &Expression) -> CodegenResult<Option<&'static str>> {
+        if let Expression::Identifier(ref name) = *expression {
+            if self.resolve(name)? == Kind::Module {
+                let module = LIBRARIES.iter().find(|m| **m == name.as_str()).unwrap();
+                self.modules.insert(module);
+
+                return Ok(Some(module))
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn ty(&self, t: &Ty) -> CodegenResult<String> {
+        let name = match self.inferer.resolve(t) {
+            Ty::Var(v) => match self.generics.get(&v) {
+                Some(name)                                => name.clone(),
+                None if self.inferer.numeric(&Ty::Var(v)) => "f64".to_owned(),
+                None                                      => "_".to_owned(),
+            },
+            Ty::Any              => return Err(CodegenError::new("`any` has no rust equivalent")),
+            Ty::Unit             => "()".to_owned(),
+            Ty::Prim(ref t)      => match primitive(t) {
+                Some(name) => name.to_owned(),
+                None       => return Err(CodegenError::new("`any` has no rust equivalent")),
+            },
+            Ty::Array(ref t)     => format!("Vec<{}>", self.ty(t)?),
+            Ty::Named(ref name)  => mangle(name),
+            Ty::Module(ref name) => return Err(CodegenError::new(&format!("module {} is not a value", name))),
+            Ty::Function(ref params, ref ret) => {
+                let mut ps = Vec::new();
+
+                for p in params {
+                    ps.push(self.ty(p)?)
+                }
+
+                format!("Rc<dyn Fn({}) -> {}>", ps.join(", "), self.ty(ret)?)
+            },
+        };
+
+        Ok(name)
+    }
+
+    // the annotation where there is one, the inferred type otherwise
+    fn annotated(&self, annotation: Option<&Type>, t: &Ty) -> CodegenResult<String> {
+        match annotation.map(spelling).transpose()?.flatten() {
+            Some(t) => Ok(t),
+            None    => self.ty(t),
+        }
+    }
+
+    fn type_of(&self, expression: &Expression) -> Ty {
+        self.inferer.type_of(expression).unwrap_or(Ty::Unit)
+    }
+
+    fn signature(&self, expression: &Expression) -> CodegenResult<(Vec<Ty>, Ty)> {
+        match self.type_of(expression) {
+            Ty::Function(params, ret) => Ok((params, (*ret).clone())),
+            _                         => Err(CodegenError::new("expected a function type")),
+        }
+    }
+
+    fn copy(&self, t: &Ty) -> bool {
+        match self.inferer.resolve(t) {
+            Ty::Prim(Type::Str) => false,
+            Ty::Prim(_)         => true,
+            Ty::Unit            => true,
+            ref t @ Ty::Var(_)  => self.inferer.numeric(t),
+            _                   => false,
+        }
+    }
+
+    fn float(&self, t: &Ty) -> bool {
+        !matches!(
+            self.inferer.resolve(t),
+            Ty::Prim(Type::I08) | Ty::Prim(Type::I16) | Ty::Prim(Type::I32) | Ty::Prim(Type::I64) | Ty::Prim(Type::I128) |
+            Ty::Prim(Type::U08) | Ty::Prim(Type::U16) | Ty::Prim(Type::U32) | Ty::Prim(Type::U64) | Ty::Prim(Type::U128)
+        )
+    }
+
+    // whether values of the type can be printed by the generated `Show`
+    fn showable(&self, t: &Ty) -> bool {
+        match self.inferer.resolve(t) {
+            Ty::Prim(_) | Ty::Unit => true,
+            Ty::Array(ref t)       => self.showable(t),
+            ref t @ Ty::Var(_)     => self.inferer.numeric(t),
+            _                      => false,
+        }
+    }
+
+    // the generic parameters in `t` need `bound` for what is done with them
+    fn bound(&mut self, t: &Ty, bound: &'static str) {
+        let mut vars = Vec::new();
+        variables(&self.inferer.resolve(t), &mut vars);
+
+        for v in vars {
+            if let Some(name) = self.generics.get(&v) {
+                let bounds = self.bounds.entry(name.clone()).or_default();
+
+                if !bounds.contains(&bound) {
+                    bounds.push(bound)
+                }
+
+                if bound == "Show" {
+                    self.show = true
+                }
+            }
+        }
+    }
+
+    // a run of statements sharing one scope, lambda definitions become fn
+    // items unless they reach for a local, then they are retried as closures
+    fn sequence(&mut self, statements: &[Statement], lift: bool) -> CodegenResult<(Vec<String>, Vec<String>)> {
+        let statements = statements.iter().filter(|s| !is_eof(s)).collect::<Vec<_>>();
+        let mut closures = HashSet::new();
+
+        'retry: loop {
+            for statement in &statements {
+                if let Statement::Definition(ref definition) = **statement {
+                    let name = name(&definition.name)?;
+
+                    let kind = match item(statement) {
+                        Some((_, _, true)) if !closures.contains(name) => Kind::Item,
+                        _                                              => Kind::Local,
+                    };
+
+                    self.bind(name.clone(), kind)
+                }
+            }
+
+            let mut items = Vec::new();
+            let mut lines = Vec::new();
+
+            for (i, statement) in statements.iter().enumerate() {
+                if let Some((name, lambda, true)) = item(statement) {
+                    if !closures.contains(name) {
+                        // lifted items sit beside `main` rather than in it
+                        let indent = self.indent;
+
+                        if lift {
+                            self.indent = 0
+                        }
+
+                        self.crossed = false;
+
+                        let function = self.function(name, lambda);
+                        self.indent  = indent;
+
+                        match function {
+                            Ok(f) if lift => items.push(f),
+                            Ok(f)         => lines.push(f),
+
+                            Err(_) if self.crossed => {
+                                self.crossed = false;
+                                closures.insert(name.clone());
+
+                                continue 'retry
+                            },
+
+                            Err(err) => return Err(located(err, statement.position())),
+                        }
+
+                        continue
+                    }
+                }
+
+                let line = match **statement {
+                    Statement::Expression(ref e, _) if i + 1 == statements.len() => self.expression(e),
+                    ref statement => self.statement(statement),
+                };
+
+                let line = line.map_err(|err| located(err, statement.position()))?;
+
+                lines.push(line)
+            }
+
+            return Ok((items, lines))
+        }
+    }
+
+    // a lambda as a fn item, which may call itself and be generic
+    fn function(&mut self, name: &Rc<String>, expression: &Expression) -> CodegenResult<String> {
+        let lambda = match *expression {
+            Expression::Lambda(ref lambda) => lambda,
+            _                              => unreachable!(),
+        };
+
+        let (params, ret) = self.signature(expression)?;
+
+        let mut vars = Vec::new();
+        variables(&Ty::Function(params.clone(), Rc::new(ret.clone())), &mut vars);
+
+        // the type parameters of an enclosing fn are out of reach as well
+        if vars.iter().any(|v| self.generics.contains_key(v)) {
+            self.crossed = true;
+            return Err(CodegenError::new(&format!("`{}` shares type parameters with the function around it", name)))
+        }
+
+        let mut generics = Vec::new();
+
+        for v in vars {
+            if !self.inferer.numeric(&Ty::Var(v)) {
+                let generic = generic_name(self.generics.len());
+
+                self.generics.insert(v, generic.clone());
+                generics.push((v, generic))
+            }
+        }
+
+        let mut ps = Vec::new();
+
+        for ((annotation, param), t) in lambda.params.iter().zip(&params) {
+            let m = if self.mutated.contains(param) { "mut " } else { "" };
+            ps.push(format!("{}{}: {}", m, mangle(param), self.annotated(annotation.as_ref(), t)?))
+        }
+
+        let ret = self.annotated(lambda.t.as_deref(), &ret)?;
+
+        self.enter(Boundary::Item);
+
+        for (_, param) in &lambda.params {
+            self.bind(param.clone(), Kind::Local)
+        }
+
+        let body = self.body(&lambda.body);
+
+        self.leave();
+
+        let generics = generics.into_iter().map(|(v, generic)| {
+            self.generics.remove(&v);
+
+            let mut bounds = vec!["Clone"];
+            bounds.extend(self.bounds.remove(&generic).unwrap_or_default());
+            bounds.push("'static");
+
+            format!("{}: {}", generic, bounds.join(" + "))
+        }).collect::<Vec<_>>();
+
+        let generics = if generics.is_empty() { String::new() } else { format!("<{}>", generics.join(", ")) };
+        let ret      = if ret == "()" { String::new() } else { format!(" -> {}", ret) };
+
+        Ok(format!("fn {}{}({}){} {}", mangle(name), generics, ps.join(", "), ret, body?))
+    }
+
+    // a lambda as an `Rc<dyn Fn>`, holding clones of whatever it captures
+    fn closure(&mut self, expression: &Expression) -> CodegenResult<String> {
+        let lambda = match *expression {
+            Expression::Lambda(ref lambda) => lambda,
+            _                              => unreachable!(),
+        };
+
+        let (params, ret) = self.signature(expression)?;
+
+        let mut ps = Vec::new();
+        let mut ts = Vec::new();
+
+        for ((annotation, param), t) in lambda.params.iter().zip(&params) {
+            let t = self.annotated(annotation.as_ref(), t)?;
+            let m = if self.mutated.contains(param) { "mut " } else { "" };
+
+            ps.push(format!("{}{}: {}", m, mangle(param), t));
+            ts.push(t)
+        }
+
+        let ret = self.annotated(lambda.t.as_deref(), &ret)?;
+
+        self.captures.push(Vec::new());
+        self.enter(Boundary::Closure(self.captures.len() - 1));
+
+        for (_, param) in &lambda.params {
+            self.bind(param.clone(), Kind::Local)
+        }
+
+        let body = self.body(&lambda.body);
+
+        self.leave();
+
+        let captures = self.captures.pop().unwrap();
+        let closure  = format!("Rc::new(move |{}| -> {} {}) as Rc<dyn Fn({}) -> {}>", ps.join(", "), ret, body?, ts.join(", "), ret);
+
+        if captures.is_empty() {
+            return Ok(format!("({})", closure))
+        }
+
+        let clones = captures.iter()
+            .map(|name| format!("let {0} = {0}.clone(); ", mangle(name)))
+            .collect::<String>();
+
+        Ok(format!("{{ {}{} }}", clones, closure))
+    }
+
+    // a function item or builtin handed around as a value
+    fn function_value(&self, path: String, expression: &Expression) -> CodegenResult<String> {
+        Ok(format!("(Rc::new({}) as {})", path, self.ty(&self.type_of(expression))?))
+    }
+
+    fn statement(&mut self, statement: &Statement) -> CodegenResult<String> {
+        match *statement {
+            Statement::Expression(ref expression, _) => Ok(format!("{};", self.expression(expression)?)),
+
+            Statement::Assignment(ref assignment) => {
+                let place = self.place(&assignment.left, Some(assignment.position))?;
+                Ok(format!("{} = {};", place, self.expression(&assignment.right)?))
+            },
+
+            Statement::Definition(ref definition) => self.definition(definition),
+
+            Statement::Test(_) => unreachable!(),
+
+            Statement::Import(ref import) => Err(CodegenError::new_pos(import.position, "imports cannot be emitted as rust")),
+        }
+    }
+
+    fn definition(&mut self, definition: &Definition) -> CodegenResult<String> {
+        let name    = name(&definition.name)?;
+        let keyword = if self.mutated.contains(name) { "let mut" } else { "let" };
+
+        let right = match definition.right {
+            Some(ref right) => right,
+
+            // declared now and given its value later
+            None => return Ok(match definition.t.as_ref().map(spelling).transpose()?.flatten() {
+                Some(t) => format!("{} {}: {};", keyword, mangle(name), t),
+                None    => format!("{} {};", keyword, mangle(name)),
+            }),
+        };
+
+        let value = match **right {
+            Expression::Lambda(ref lambda) => {
+                if mentions(&lambda.body, name) {
+                    return Err(CodegenError::new_pos(definition.position, &format!("`{}` calls itself while capturing values, which rust closures cannot do", name)))
+                }
+
+                self.closure(right)?
+            },
+
+            Expression::Array(ref content) if fixed(definition.t.as_ref()) => self.array(content, "[", "]")?,
+
+            _ => self.expression(right)?,
+        };
+
+        let t = match definition.t.as_ref().map(spelling).transpose()?.flatten() {
+            Some(t) => Some(t),
+            None    => match **right {
+                // nothing in an empty array tells rust what it holds
+                Expression::Array(ref content) if content.is_empty() => Some(self.ty(&self.type_of(right))?),
+                _                                                    => None,
+            },
+        };
+
+        Ok(match t {
+            Some(t) => format!("{} {}: {} = {};", keyword, mangle(name), t, value),
+            None    => format!("{} {} = {};", keyword, mangle(name), value),
+        })
+    }
+
+    // an array or name being indexed into, borrowed rather than cloned
+    fn target(&mut self, expression: &Expression) -> CodegenResult<String> {
+        match *expression {
+            Expression::Identifier(ref name) if self.resolve(name)? == Kind::Local => Ok(mangle(name)),
+            Expression::Index(ref index) => Ok(format!("{}[({}) as usize]", self.target(&index.id)?, self.expression(&index.index)?)),
+            ref expression => Ok(format!("({})", self.expression(expression)?)),
+        }
+    }
+
+    // somewhere a value is written to, a closure only holds a clone so it
+    // cannot be one of its captures
+    fn place(&mut self, expression: &Expression, position: Option<TokenPosition>) -> CodegenResult<String> {
+        let error = |message: &str| match position {
+            Some(position) => CodegenError::new_pos(position, message),
+            None           => CodegenError::new(message),
+        };
+
+        let mut root = expression;
+
+        while let Expression::Index(ref index) = *root {
+            root = &index.id
+        }
+
+        match *root {
+            Expression::Identifier(ref name) => {
+                let target = self.target(expression)?;
+
+                if self.captured(name) {
+                    return Err(error(&format!("`{}` is changed inside a closure, which rust closures cannot share", name)))
+                }
+
+                Ok(target)
+            },
+
+            _ => Err(error("only names and their elements can be changed")),
+        }
+    }
+
+    fn body(&mut self, expression: &Expression) -> CodegenResult<String> {
+        if let Expression::Block(ref statements) = *expression {
+            return self.block(statements)
+        }
+
+        self.indent += 1;
+        let code = self.expression(expression);
+        self.indent -= 1;
+
+        Ok(format!("{{\n{}{}\n{}}}", "    ".repeat(self.indent + 1), code?, self.pad()))
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> CodegenResult<String> {
+        self.enter(Boundary::None);
+        self.indent += 1;
+
+        let sequence = self.sequence(statements, false);
+
+        self.indent -= 1;
+        self.leave();
+
+        let (_, lines) = sequence?;
+
+        if lines.is_empty() {
+            return Ok("{}".to_owned())
+        }
+
+        let mut out = String::from("{\n");
+
+        for line in lines {
+            out.push_str(&"    ".repeat(self.indent + 1));
+            out.push_str(&line);
+            out.push('\n')
+        }
+
+        out.push_str(&self.pad());
+        out.push('}');
+
+        Ok(out)
+    }
+
+    fn array(&mut self, content: &[Rc<Expression>], open: &str, close: &str) -> CodegenResult<String> {
+        let mut elements = Vec::new();
+
+        for element in content {
+            elements.push(self.expression(element)?)
+        }
+
+        Ok(format!("{}{}{}", open, elements.join(", "), close))
+    }
+
+    fn number(&self, n: f64, t: &Ty) -> String {
+        if !self.float(t) {
+            format!("{}", n as i128)
+        } else if n.fract() == 0.0 && n.abs() < 1e15 {
+            format!("{:.1}", n)
+        } else {
+            format!("{:?}", n)
+        }
+    }
+
+    // an operand, parenthesized unless it is sure to bind tightly enough
+    fn operand(&mut self, expression: &Expression) -> CodegenResult<String> {
+        let code = self.expression(expression)?;
+
+        match *expression {
+            Expression::Operation(_) | Expression::If(_) | Expression::Block(_) | Expression::Lambda(_) => Ok(format!("({})", code)),
+            Expression::Number(n) if n < 0.0 => Ok(format!("({})", code)),
+            _                                => Ok(code),
+        }
+    }
+
+    fn operation(&mut self, operation: &Operation, expression: &Expression) -> CodegenResult<String> {
+        let t     = self.type_of(&operation.left);
+        let left  = self.operand(&operation.left)?;
+        let right = self.operand(&operation.right)?;
+
+        let code = match operation.op {
+            Operand::Add if self.type_of(expression) == Ty::Prim(Type::Str) => format!("format!(\"{{}}{{}}\", {}, {})", left, right),
+
+            // the evaluator divides every number as f64
+            Operand::Div if !self.float(&t) => {
+                return Err(CodegenError::new(&format!("`/` on {} drops the fraction in rust where `itu run` keeps it, divide f64 instead", self.inferer.resolve(&t))))
+            },
+
+            Operand::Pow if self.float(&t) => format!("{}::powf({}, {})", self.ty(&t)?, left, right),
+            Operand::Pow                   => format!("{}::pow({}, ({}) as u32)", self.ty(&t)?, left, right),
+
+            op => {
+                match op {
+                    Operand::Equal | Operand::NEqual                               => self.bound(&t, "PartialEq"),
+                    Operand::Lt | Operand::Gt | Operand::LtEqual | Operand::GtEqual => self.bound(&t, "PartialOrd"),
+                    _                                                               => (),
+                }
+
+                format!("{} {} {}", left, symbol(op), right)
+            },
+        };
+
+        Ok(code)
+    }
+
+    fn call(&mut self, call: &Call) -> CodegenResult<String> {
+        if let Expression::Field(ref field) = *call.callee {
+            if let Some(module) = self.module(&field.object)? {
+                let mut args = Vec::new();
+
+                for (i, arg) in call.args.iter().enumerate() {
+                    // the array given to push and pop is changed in place
+                    if module == "array" && i == 0 && (*field.name == "push" || *field.name == "pop") {
+                        args.push(format!("&mut {}", self.place(arg, None)?));
+                        continue
+                    }
+
+                    if module == "io" || module == "str" {
+                        let t = self.type_of(arg);
+                        self.bound(&t, "Show")
+                    }
+
+                    args.push(self.expression(arg)?)
+                }
+
+                return Ok(format!("itu_{}::{}({})", module, mangle(&field.name), args.join(", ")))
+            }
+        }
+
+        let callee = match *call.callee {
+            Expression::Identifier(ref name) if self.resolve(name)? != Kind::Module => mangle(name),
+            ref callee => format!("({})", self.expression(callee)?),
+        };
+
+        let mut args = Vec::new();
+
+        for arg in &call.args {
+            args.push(self.expression(arg)?)
+        }
+
+        Ok(format!("{}({})", callee, args.join(", ")))
+    }
+
+    fn conditional(&mut self, conditional: &If) -> CodegenResult<String> {
+        let condition = self.expression(&conditional.condition)?;
+
+        match conditional.otherwise {
+            Some(ref otherwise) => {
+                let body = self.body(&conditional.body)?;
+
+                let otherwise = match **otherwise {
+                    Expression::If(ref next) => self.conditional(next)?,
+                    ref otherwise            => self.body(otherwise)?,
+                };
+
+                Ok(format!("if {} {} else {}", condition, body, otherwise))
+            },
+
+            // without an else there is no value, whatever the body gives
+            None => {
+                let body = match self.type_of(&conditional.body) {
+                    Ty::Unit => self.body(&conditional.body)?,
+                    _        => format!("{{ {}; }}", self.expression(&conditional.body)?),
+                };
+
+                Ok(format!("if {} {}", condition, body))
+            },
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> CodegenResult<String> {
+        let code = match *expression {
+            Expression::Block(ref statements) => self.block(statements)?,
+            Expression::Number(n)             => self.number(n, &self.type_of(expression)),
+            Expression::Bool(b)               => b.to_string(),
+            Expression::Str(ref s)            => format!("String::from({:?})", s),
+            Expression::Char(c)               => format!("{:?}", c),
+
+            Expression::Identifier(ref name) => match self.resolve(name)? {
+                Kind::Local if self.copy(&self.type_of(expression)) => mangle(name),
+                Kind::Local                                         => format!("{}.clone()", mangle(name)),
+                Kind::Item                                          => self.function_value(mangle(name), expression)?,
+                Kind::Module => return Err(CodegenError::new(&format!("module {} is not a value", name))),
+            },
+
+            Expression::Operation(ref operation) => self.operation(operation, expression)?,
+            Expression::Call(ref call)           => self.call(call)?,
+            Expression::Lambda(_)                => self.closure(expression)?,
+            Expression::Array(ref content)       => self.array(content, "vec![", "]")?,
+
+            Expression::Index(ref index) => {
+                let element = format!("{}[({}) as usize]", self.target(&index.id)?, self.expression(&index.index)?);
+
+                if self.copy(&self.type_of(expression)) {
+                    element
+                } else {
+                    format!("{}.clone()", element)
+                }
+            },
+
+            Expression::Field(ref field) => match self.module(&field.object)? {
+                Some(module) => {
+                    let path = format!("itu_{}::{}", module, mangle(&field.name));
+
+                    match self.type_of(expression) {
+                        Ty::Function(..) if module == "array" && (*field.name == "push" || *field.name == "pop") => {
+                            return Err(CodegenError::new(&format!("array.{} can only be called, not passed around", field.name)))
+                        },
+                        Ty::Function(..) => self.function_value(path, expression)?,
+                        _                => path,
+                    }
+                },
+
+                None => return Err(CodegenError::new("fields need struct types, which itu cannot declare yet")),
+            },
+
+            Expression::If(ref conditional) => self.conditional(conditional)?,
+            Expression::EOF                 => String::new(),
+
+            Expression::Match(ref subject) => {
+                return Err(CodegenError::new_pos(subject.position, "match expressions have no rust equivalent yet"))
+            },
+        };
+
+        Ok(code)
+    }
+}
@@ -0,0 +1,704 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use super::*;
+
+// layout of the module written, see the webassembly core spec:
+//
+//   magic     b"\0asm"
+//   version   u32 1
+//   sections  type, function, export and code, each an id byte and a
+//             length-prefixed body
+pub const MAGIC:   &[u8; 4] = b"\0asm";
+pub const VERSION: u32      = 1;
+
+const TYPE_SECTION:     u8 = 1;
+const FUNCTION_SECTION: u8 = 3;
+const EXPORT_SECTION:   u8 = 7;
+const CODE_SECTION:     u8 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Val {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl Val {
+    fn code(self) -> u8 {
+        match self {
+            Val::I32 => 0x7F,
+            Val::I64 => 0x7E,
+            Val::F32 => 0x7D,
+            Val::F64 => 0x7C,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Val::I32 => "i32",
+            Val::I64 => "i64",
+            Val::F32 => "f32",
+            Val::F64 => "f64",
+        }
+    }
+}
+
+// a wasm value type and whether, as an integer, it is unsigned
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Num {
+    val:      Val,
+    unsigned: bool,
+}
+
+struct Signature {
+    index:  u32,
+    params: Vec<Num>,
+    ret:    Option<Num>,
+}
+
+struct Body<'a> {
+    inferer:    &'a Inferer,
+    signatures: &'a HashMap<Rc<String>, Signature>,
+    scopes:     Vec<HashMap<Rc<String>, (u32, Num)>>,
+    // every local past the parameters, in index order
+    locals:     Vec<Val>,
+    arity:      u32,
+    code:       Vec<u8>,
+}
+
+// translates the top-level functions of a type-checked program whose
+// parameters and result are all numbers into a module exporting them,
+// everything else in the program is left out
+pub fn emit(ast: &[Statement], inferer: &Inferer) -> CodegenResult<Vec<u8>> {
+    let mut functions  = Vec::new();
+    let mut signatures = HashMap::new();
+
+    for statement in ast {
+        if let Statement::Definition(ref definition) = *statement {
+            if let (Expression::Identifier(name), Some(right)) = (&*definition.name, definition.right.as_ref()) {
+                if let Expression::Lambda(ref lambda) = **right {
+                    if let Some((params, ret)) = numeric_signature(inferer, right) {
+                        let index = functions.len() as u32;
+
+                        signatures.insert(name.clone(), Signature { index, params, ret });
+                        functions.push((name.clone(), lambda, definition.position))
+                    }
+                }
+            }
+        }
+    }
+
+    if functions.is_empty() {
+        return Err(CodegenError::new("nothing to export, only functions over i32, i64, f32, f64 and bool are"))
+    }
+
+    let mut types   = Vec::new();
+    let mut indices = Vec::new();
+    let mut exports = Vec::new();
+    let mut codes   = Vec::new();
+
+    for (index, &(ref name, lambda, position)) in functions.iter().enumerate() {
+        let signature = &signatures[name];
+
+        let mut t = vec![0x60];
+        vector(&mut t, signature.params.len());
+
+        for param in &signature.params {
+            t.push(param.val.code())
+        }
+
+        match signature.ret {
+            Some(ret) => {
+                vector(&mut t, 1);
+                t.push(ret.val.code())
+            },
+            None => vector(&mut t, 0),
+        }
+
+        types.push(t);
+
+        let mut index_bytes = Vec::new();
+        unsigned(&mut index_bytes, index as u64);
+        indices.push(index_bytes);
+
+        let mut export = Vec::new();
+        string(&mut export, name);
+        export.push(0x00);
+        unsigned(&mut export, index as u64);
+        exports.push(export);
+
+        let mut body = Body {
+            inferer,
+            signatures: &signatures,
+            scopes:     vec![HashMap::new()],
+            locals:     Vec::new(),
+            arity:      signature.params.len() as u32,
+            code:       Vec::new(),
+        };
+
+        for (i, (_, param)) in lambda.params.iter().enumerate() {
+            body.scopes[0].insert(param.clone(), (i as u32, signature.params[i]));
+        }
+
+        let result = body.expression(&lambda.body).and_then(|produced| match (signature.ret, produced) {
+            (Some(_), true) | (None, false) => Ok(()),
+            (None, true)                    => {
+                body.code.push(0x1A);
+                Ok(())
+            },
+            (Some(_), false) => Err(CodegenError::new("expected a value")),
+        });
+
+        if let Err(err) = result {
+            let CodegenErrorValue::Constant(ref value) = err.value;
+            return Err(CodegenError::new_pos(err.position.unwrap_or(position), &format!("in `{}`: {}", name, value)))
+        }
+
+        body.code.push(0x0B);
+
+        let mut code = Vec::new();
+        vector(&mut code, body.locals.len());
+
+        for local in &body.locals {
+            unsigned(&mut code, 1);
+            code.push(local.code())
+        }
+
+        code.extend_from_slice(&body.code);
+
+        let mut sized = Vec::new();
+        vector(&mut sized, code.len());
+        sized.extend_from_slice(&code);
+
+        codes.push(sized)
+    }
+
+    let mut out = Vec::new();
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+
+    section(&mut out, TYPE_SECTION, &types);
+    section(&mut out, FUNCTION_SECTION, &indices);
+    section(&mut out, EXPORT_SECTION, &exports);
+    section(&mut out, CODE_SECTION, &codes);
+
+    Ok(out)
+}
+
+fn numeric_signature(inferer: &Inferer, lambda: &Expression) -> Option<(Vec<Num>, Option<Num>)> {
+    match inferer.type_of(lambda)? {
+        Ty::Function(params, ret) => {
+            let mut nums = Vec::new();
+
+            for param in &params {
+                nums.push(num(inferer, param)?)
+            }
+
+            let ret = match inferer.resolve(&ret) {
+                Ty::Unit => None,
+                ref t    => Some(num(inferer, t)?),
+            };
+
+            Some((nums, ret))
+        },
+
+        _ => None,
+    }
+}
+
+// the wasm spelling of a type, numbers nobody pinned down are f64 like
+// everywhere else
+fn num(inferer: &Inferer, t: &Ty) -> Option<Num> {
+    let (val, unsigned) = match inferer.resolve(t) {
+        Ty::Prim(Type::I32)  |
+        Ty::Prim(Type::Bool) => (Val::I32, false),
+        Ty::Prim(Type::U32)  => (Val::I32, true),
+        Ty::Prim(Type::I64)  => (Val::I64, false),
+        Ty::Prim(Type::U64)  => (Val::I64, true),
+        Ty::Prim(Type::F32)  => (Val::F32, false),
+        Ty::Prim(Type::F64)  => (Val::F64, false),
+        ref t @ Ty::Var(_) if inferer.numeric(t) => (Val::F64, false),
+        _ => return None,
+    };
+
+    Some(Num { val, unsigned })
+}
+
+fn unsigned(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(byte);
+            return
+        }
+
+        out.push(byte | 0x80)
+    }
+}
+
+fn signed(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+
+        if done {
+            out.push(byte);
+            return
+        }
+
+        out.push(byte | 0x80)
+    }
+}
+
+fn vector(out: &mut Vec<u8>, len: usize) {
+    unsigned(out, len as u64)
+}
+
+fn string(out: &mut Vec<u8>, value: &str) {
+    vector(out, value.len());
+    out.extend_from_slice(value.as_bytes())
+}
+
+fn section(out: &mut Vec<u8>, id: u8, entries: &[Vec<u8>]) {
+    let mut body = Vec::new();
+    vector(&mut body, entries.len());
+
+    for entry in entries {
+        body.extend_from_slice(entry)
+    }
+
+    out.push(id);
+    vector(out, body.len());
+    out.extend_from_slice(&body)
+}
+
+#[allow(dead_code)]
+impl<'a> Body<'a> {
+    fn num(&self, expression: &Expression) -> CodegenResult<Option<Num>> {
+        match self.inferer.type_of(expression).map(|t| self.inferer.resolve(&t)) {
+            Some(Ty::Unit) => Ok(None),
+            Some(ref t)    => match num(self.inferer, t) {
+                Some(num) => Ok(Some(num)),
+                None      => Err(CodegenError::new(&format!("values of type {} have no wasm type", t))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn lookup(&self, name: &Rc<String>) -> CodegenResult<(u32, Num)> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(&local) = scope.get(name) {
+                return Ok(local)
+            }
+        }
+
+        Err(CodegenError::new(&format!("`{}` is not a local of this function", name)))
+    }
+
+    fn local(&mut self, val: Val) -> u32 {
+        self.locals.push(val);
+        self.arity + self.locals.len() as u32 - 1
+    }
+
+    fn op(&mut self, op: u8, operand: u32) {
+        self.code.push(op);
+        unsigned(&mut self.code, operand as u64)
+    }
+
+    // generates the statements of a block, returning whether it leaves a value
+    fn block(&mut self, statements: &[Statement]) -> CodegenResult<bool> {
+        self.scopes.push(HashMap::new());
+
+        let result = self.statements(statements);
+
+        self.scopes.pop();
+
+        result
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> CodegenResult<bool> {
+        let statements = statements.iter()
+            .filter(|s| !matches!(*s, Statement::Expression(e, _) if **e == Expression::EOF))
+            .collect::<Vec<_>>();
+
+        let mut produced = false;
+
+        for (i, statement) in statements.iter().enumerate() {
+            let last = i + 1 == statements.len();
+
+            produced = match **statement {
+                Statement::Expression(ref expression, _) => {
+                    let produced = self.expression(expression)?;
+
+                    if produced && !last {
+                        self.code.push(0x1A)
+                    }
+
+                    produced && last
+                },
+
+                Statement::Definition(ref definition) => {
+                    let name = match *definition.name {
+                        Expression::Identifier(ref name) => name.clone(),
+                        _ => return Err(CodegenError::new_pos(definition.position, "expected a name")),
+                    };
+
+                    let right = match definition.right {
+                        Some(ref right) => right,
+                        None => return Err(CodegenError::new_pos(definition.position, "definitions need a value in wasm")),
+                    };
+
+                    let num = match self.num(right)? {
+                        Some(num) => num,
+                        None      => return Err(CodegenError::new_pos(definition.position, "definitions need a value in wasm")),
+                    };
+
+                    self.expression(right)?;
+
+                    let index = self.local(num.val);
+                    self.op(0x21, index);
+
+                    self.scopes.last_mut().unwrap().insert(name, (index, num));
+
+                    false
+                },
+
+                Statement::Assignment(ref assignment) => {
+                    let name = match *assignment.left {
+                        Expression::Identifier(ref name) => name,
+                        _ => return Err(CodegenError::new_pos(assignment.position, "only locals can be assigned to in wasm")),
+                    };
+
+                    let (index, _) = self.lookup(name)?;
+
+                    self.expression(&assignment.right)?;
+                    self.op(0x21, index);
+
+                    false
+                },
+
+                Statement::Test(_) => unreachable!(),
+
+                Statement::Import(ref import) => return Err(CodegenError::new_pos(import.position, "imports cannot be emitted as wasm")),
+            }
+        }
+
+        Ok(produced)
+    }
+
+    // generates `expression`, returning whether it leaves a value
+    fn expression(&mut self, expression: &Expression) -> CodegenResult<bool> {
+        match *expression {
+            Expression::Block(ref statements) => self.block(statements),
+
+            Expression::Number(n) => {
+                let num = self.num(expression)?.unwrap_or(Num { val: Val::F64, unsigned: false });
+                self.constant(num.val, n);
+
+                Ok(true)
+            },
+
+            Expression::Bool(b) => {
+                self.constant(Val::I32, if b { 1.0 } else { 0.0 });
+                Ok(true)
+            },
+
+            Expression::Identifier(ref name) => {
+                let (index, _) = self.lookup(name)?;
+                self.op(0x20, index);
+
+                Ok(true)
+            },
+
+            Expression::Operation(ref operation) => self.operation(operation),
+            Expression::Call(ref call)           => self.call(expression, call),
+
+            Expression::Field(ref field) => {
+                let value = match (&*field.object, field.name.as_str()) {
+                    (Expression::Identifier(module), "pi")  if **module == "math" => ::std::f64::consts::PI,
+                    (Expression::Identifier(module), "tau") if **module == "math" => ::std::f64::consts::TAU,
+                    (Expression::Identifier(module), "e")   if **module == "math" => ::std::f64::consts::E,
+                    _ => return Err(CodegenError::new(&format!("`{}` has no wasm equivalent", field.name))),
+                };
+
+                self.constant(Val::F64, value);
+
+                Ok(true)
+            },
+
+            Expression::If(ref conditional) => {
+                self.expression(&conditional.condition)?;
+
+                let num = self.num(expression)?;
+
+                let otherwise = match conditional.otherwise {
+                    Some(ref otherwise) => otherwise,
+
+                    // with nothing to fall back on the branch gives no value
+                    None => {
+                        self.code.extend_from_slice(&[0x04, 0x40]);
+
+                        if self.expression(&conditional.body)? {
+                            self.code.push(0x1A)
+                        }
+
+                        self.code.push(0x0B);
+
+                        return Ok(false)
+                    },
+                };
+
+                self.code.push(0x04);
+                self.code.push(num.map_or(0x40, |num| num.val.code()));
+
+                let body = self.expression(&conditional.body)?;
+
+                if body && num.is_none() {
+                    self.code.push(0x1A)
+                }
+
+                self.code.push(0x05);
+
+                let other = self.expression(otherwise)?;
+
+                if other && num.is_none() {
+                    self.code.push(0x1A)
+                }
+
+                self.code.push(0x0B);
+
+                Ok(num.is_some())
+            },
+
+            Expression::EOF => Ok(false),
+
+            _ => Err(CodegenError::new("only numbers, locals, calls and ifs can be compiled to wasm")),
+        }
+    }
+
+    fn constant(&mut self, val: Val, n: f64) {
+        match val {
+            Val::I32 => {
+                self.code.push(0x41);
+                signed(&mut self.code, n as i32 as i64)
+            },
+            Val::I64 => {
+                self.code.push(0x42);
+                signed(&mut self.code, n as i64)
+            },
+            Val::F32 => {
+                self.code.push(0x43);
+                self.code.extend_from_slice(&(n as f32).to_le_bytes())
+            },
+            Val::F64 => {
+                self.code.push(0x44);
+                self.code.extend_from_slice(&n.to_le_bytes())
+            },
+        }
+    }
+
+    fn operation(&mut self, operation: &Operation) -> CodegenResult<bool> {
+        let num = match self.num(&operation.left)? {
+            Some(num) => num,
+            None      => return Err(CodegenError::new("operands need a value")),
+        };
+
+        self.expression(&operation.left)?;
+        self.expression(&operation.right)?;
+
+        let float = num.val == Val::F32 || num.val == Val::F64;
+
+        // the evaluator divides every number as f64
+        if operation.op == Operand::Div && !float {
+            return Err(CodegenError::new("`/` on integers drops the fraction in wasm where `itu run` keeps it, divide f64 instead"))
+        }
+
+        // floats have no remainder instruction, `a - b * trunc(a / b)` is
+        // what the evaluator's `%` comes to
+        if operation.op == Operand::Mod && float {
+            let a = self.local(num.val);
+            let b = self.local(num.val);
+
+            let (sub, mul, div, trunc) = match num.val {
+                Val::F32 => (0x93, 0x94, 0x95, 0x8F),
+                _        => (0xA1, 0xA2, 0xA3, 0x9D),
+            };
+
+            self.op(0x21, b);
+            self.op(0x22, a);
+            self.op(0x20, b);
+            self.op(0x20, a);
+            self.op(0x20, b);
+            self.code.push(div);
+            self.code.push(trunc);
+            self.code.push(mul);
+            self.code.push(sub);
+
+            return Ok(true)
+        }
+
+        let op = match (num.val, operation.op) {
+            (_, Operand::Pow) => return Err(CodegenError::new("`^` has no wasm instruction")),
+
+            (Val::F32, op) => match op {
+                Operand::Add => 0x92, Operand::Sub => 0x93, Operand::Mul => 0x94, Operand::Div => 0x95,
+                Operand::Equal => 0x5B, Operand::NEqual => 0x5C,
+                Operand::Lt => 0x5D, Operand::Gt => 0x5E, Operand::LtEqual => 0x5F, Operand::GtEqual => 0x60,
+                _ => unreachable!(),
+            },
+
+            (Val::F64, op) => match op {
+                Operand::Add => 0xA0, Operand::Sub => 0xA1, Operand::Mul => 0xA2, Operand::Div => 0xA3,
+                Operand::Equal => 0x61, Operand::NEqual => 0x62,
+                Operand::Lt => 0x63, Operand::Gt => 0x64, Operand::LtEqual => 0x65, Operand::GtEqual => 0x66,
+                _ => unreachable!(),
+            },
+
+            // the integer opcodes of i64 follow those of i32 at a fixed distance
+            (val, op) => {
+                let (arithmetic, comparison) = if val == Val::I64 { (0x12, 0x0B) } else { (0, 0) };
+                let u = num.unsigned as u8;
+
+                match op {
+                    Operand::Add     => 0x6A + arithmetic,
+                    Operand::Sub     => 0x6B + arithmetic,
+                    Operand::Mul     => 0x6C + arithmetic,
+                    Operand::Div     => 0x6D + arithmetic + u,
+                    Operand::Mod     => 0x6F + arithmetic + u,
+                    Operand::Equal   => 0x46 + comparison,
+                    Operand::NEqual  => 0x47 + comparison,
+                    Operand::Lt      => 0x48 + comparison + u,
+                    Operand::Gt      => 0x4A + comparison + u,
+                    Operand::LtEqual => 0x4C + comparison + u,
+                    Operand::GtEqual => 0x4E + comparison + u,
+                    Operand::Pow     => unreachable!(),
+                }
+            },
+        };
+
+        self.code.push(op);
+
+        Ok(true)
+    }
+
+    fn call(&mut self, expression: &Expression, call: &Call) -> CodegenResult<bool> {
+        if let Expression::Field(ref field) = *call.callee {
+            if *field.object == Expression::Identifier(Rc::new("math".to_owned())) {
+                let op = match field.name.as_str() {
+                    "abs"   => 0x99,
+                    "ceil"  => 0x9B,
+                    "floor" => 0x9C,
+                    "sqrt"  => 0x9F,
+                    "min"   => 0xA4,
+                    "max"   => 0xA5,
+                    name    => return Err(CodegenError::new(&format!("math.{} has no wasm instruction", name))),
+                };
+
+                for arg in &call.args {
+                    self.expression(arg)?;
+                }
+
+                self.code.push(op);
+
+                return Ok(true)
+            }
+        }
+
+        let name = match *call.callee {
+            Expression::Identifier(ref name) => name,
+            _ => return Err(CodegenError::new("only exported functions can be called in wasm")),
+        };
+
+        let signature = match self.signatures.get(name) {
+            Some(signature) => signature,
+            None => return Err(CodegenError::new(&format!("`{}` is not a function over numbers", name))),
+        };
+
+        for (arg, param) in call.args.iter().zip(&signature.params) {
+            if self.num(arg)? != Some(*param) {
+                return Err(CodegenError::new(&format!("`{}` takes {} here, polymorphic numbers are f64 in wasm", name, param.val.name())))
+            }
+        }
+
+        let (index, produced) = (signature.index, signature.ret.is_some());
+
+        for arg in &call.args {
+            self.expression(arg)?;
+        }
+
+        self.op(0x10, index);
+
+        // a call used as a value of another number type than the result
+        // would need a conversion nobody asked for
+        if produced && self.num(expression)? != signature.ret {
+            return Err(CodegenError::new(&format!("`{}` returns a different number type here", name)))
+        }
+
+        Ok(produced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasmparser::{Parser, Payload, Validator};
+
+    fn module(source: &str) -> Vec<u8> {
+        let (ast, inferer) = typed(source);
+        emit(&ast, &inferer).unwrap()
+    }
+
+    fn exports(bytes: &[u8]) -> Vec<String> {
+        let mut names = Vec::new();
+
+        for payload in Parser::new(0).parse_all(bytes) {
+            if let Payload::ExportSection(section) = payload.unwrap() {
+                for export in section {
+                    names.push(export.unwrap().name.to_string())
+                }
+            }
+        }
+
+        names
+    }
+
+    #[test]
+    fn modules_validate() {
+        let sources = [
+            "twice := (a: f32): f32 -> a * 2",
+            "add := (a: i32, b: i32): i32 -> a + b\nsub := (a: i64, b: i64): i64 -> a - b",
+            "max := (a: f64, b: f64): f64 -> if a > b then a else b",
+            "fib := (n: i32): i32 ->\n  if n < 2\n    n\n  else\n    (fib n - 1) + (fib n - 2)",
+            "even := (n: u32): bool -> n % 2 == 0",
+            "hyp := (a: f64, b: f64): f64 ->\n  aa := a * a\n  bb := b * b\n  aa + bb",
+        ];
+
+        for source in &sources {
+            let bytes = module(source);
+
+            if let Err(err) = Validator::new().validate_all(&bytes) {
+                panic!("invalid module for {:?}: {}", source, err)
+            }
+        }
+    }
+
+    #[test]
+    fn exports_every_numeric_function() {
+        let bytes = module("twice := (a: f32): f32 -> a * 2\nname := \"itu\"\nadd := (a: i32, b: i32): i32 -> a + b");
+        assert_eq!(exports(&bytes), vec!["twice", "add"]);
+    }
+
+    #[test]
+    fn refuses_programs_without_numeric_functions() {
+        let (ast, inferer) = typed("greet := (name: str): str -> name");
+        assert!(emit(&ast, &inferer).is_err());
+    }
+}
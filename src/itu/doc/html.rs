@@ -0,0 +1,112 @@
+use super::*;
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 50em; margin: 2em auto; padding: 0 1em; line-height: 1.5; color: #222; }
+nav { margin-bottom: 2em; }
+a { color: #2a6db0; text-decoration: none; }
+a:hover { text-decoration: underline; }
+code { font-family: monospace; background: #f4f4f4; padding: 0 .2em; }
+section { border-top: 1px solid #ddd; margin-top: 1.5em; }
+h2 code { background: none; font-size: 1rem; }
+.function { color: #8e44ad; }
+";
+
+// a page per file beside an index of them all and the stylesheet they share
+pub fn site(pages: &[Page]) -> Vec<(String, String)> {
+    let links = Links::new(pages);
+
+    let mut files = vec![
+        ("index.html".to_string(), index(pages)),
+        ("style.css".to_string(), STYLE.to_string()),
+    ];
+
+    for page in pages {
+        files.push((format!("{}.html", page.name), render(page, &links)))
+    }
+
+    files
+}
+
+fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        body,
+    )
+}
+
+fn index(pages: &[Page]) -> String {
+    let mut body = String::from("<h1>modules</h1>\n<ul>\n");
+
+    for page in pages {
+        let summary = paragraphs(&page.docs).into_iter().next().map_or(String::new(), |summary| {
+            format!(" &mdash; {}", inline(&summary, None, &page.name))
+        });
+
+        body.push_str(&format!("<li><a href=\"{}.html\">{}</a>{}</li>\n", anchor(&page.name), escape(&page.name), summary))
+    }
+
+    body.push_str("</ul>\n");
+
+    document("itu documentation", &body)
+}
+
+fn render(page: &Page, links: &Links) -> String {
+    let mut body = format!("<nav><a href=\"index.html\">index</a></nav>\n<h1>{}</h1>\n", escape(&page.name));
+
+    body.push_str(&text(&page.docs, links, &page.name));
+
+    if !page.items.is_empty() {
+        body.push_str("<ul>\n");
+
+        for item in &page.items {
+            body.push_str(&format!("<li><a href=\"#{}\">{}</a></li>\n", anchor(&item.name), escape(&item.name)))
+        }
+
+        body.push_str("</ul>\n");
+    }
+
+    for item in &page.items {
+        let class = if item.function { " class=\"function\"" } else { "" };
+
+        body.push_str(&format!(
+            "<section id=\"{}\">\n<h2><code{}>{}</code></h2>\n{}</section>\n",
+            escape(&item.name),
+            class,
+            signature(&item.signature, links, &page.name),
+            text(&item.docs, links, &page.name),
+        ))
+    }
+
+    document(&page.name, &body)
+}
+
+fn signature(pieces: &[Piece], links: &Links, from: &str) -> String {
+    pieces.iter().map(|piece| match *piece {
+        Piece::Text(ref text) => escape(text),
+        Piece::Type(ref name) => match links.href(name, from, "html") {
+            Some(href) => format!("<a href=\"{}\">{}</a>", href, escape(name)),
+            None       => escape(name),
+        },
+    }).collect()
+}
+
+fn text(docs: &[String], links: &Links, from: &str) -> String {
+    paragraphs(docs).iter().map(|paragraph| format!("<p>{}</p>\n", inline(paragraph, Some(links), from))).collect()
+}
+
+fn inline(text: &str, links: Option<&Links>, from: &str) -> String {
+    spans(text).iter().enumerate().map(|(i, span)| {
+        if i % 2 == 0 {
+            return escape(span)
+        }
+
+        match links.and_then(|links| links.href(span, from, "html")) {
+            Some(href) => format!("<a href=\"{}\"><code>{}</code></a>", href, escape(span)),
+            None       => format!("<code>{}</code>", escape(span)),
+        }
+    }).collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
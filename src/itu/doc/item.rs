@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::*;
+
+// a signature as pieces, type names kept apart so pages can link them to
+// where they are documented
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Text(String),
+    Type(Rc<String>),
+}
+
+// a top-level definition and the `---` lines right above it
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub name:      Rc<String>,
+    pub docs:      Vec<String>,
+    pub signature: Vec<Piece>,
+    pub function:  bool,
+}
+
+// one source file, its docs being the `---` lines heading the file that
+// no definition takes
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub name:  String,
+    pub docs:  Vec<String>,
+    pub items: Vec<Item>,
+}
+
+impl Page {
+    pub fn new(name: &str, source: &str) -> ParserResult<Page> {
+        let mut lexer  = lexer(&mut source.chars());
+        let mut parser = Parser::new(Traveler::new(lexer.by_ref().collect()));
+
+        let mut tops = Vec::new();
+
+        loop {
+            let count = parser.starts().len();
+
+            match parser.next_statement()? {
+                Some(statement) => tops.push((parser.starts()[count], statement)),
+                None            => break,
+            }
+        }
+
+        // lines with code on them, a doc comment sharing one documents nothing
+        let code = parser.starts().iter().zip(parser.ends().iter())
+            .flat_map(|(start, end)| start.line ..= end.line)
+            .collect::<HashSet<_>>();
+
+        let docs = lexer.comments().iter()
+            .filter(|comment| comment.token_type == TokenType::DocComment && !code.contains(&comment.position.line))
+            .map(|comment| (comment.position.line, text(comment.content())))
+            .collect::<HashMap<_, _>>();
+
+        let mut taken = HashSet::new();
+        let mut items = Vec::new();
+
+        for (start, statement) in &tops {
+            let definition = match *statement {
+                Statement::Definition(ref definition) => definition,
+                _                                     => continue,
+            };
+
+            let name = match *definition.name {
+                Expression::Identifier(ref name) => name.clone(),
+                _                                => continue,
+            };
+
+            let mut line = start.line;
+
+            while line > 1 && docs.contains_key(&(line - 1)) {
+                line -= 1;
+                taken.insert(line);
+            }
+
+            let (signature, function) = signature(&name, definition);
+
+            items.push(Item {
+                name,
+                docs: (line .. start.line).map(|line| docs[&line].clone()).collect(),
+                signature,
+                function,
+            })
+        }
+
+        let mut heading = Vec::new();
+        let mut line    = 1;
+
+        while let Some(doc) = docs.get(&line).filter(|_| !taken.contains(&line)) {
+            heading.push(doc.clone());
+            line += 1
+        }
+
+        Ok(Page {
+            name: name.to_string(),
+            docs: heading,
+            items,
+        })
+    }
+}
+
+// a doc comment without its dashes and the space after them
+fn text(comment: &str) -> String {
+    let text = comment.trim_start_matches('-');
+    text.strip_prefix(' ').unwrap_or(text).to_string()
+}
+
+// what a definition looks like from outside, a function by its parameters
+// and return type, a value by its type or a literal it is bound to
+fn signature(name: &Rc<String>, definition: &Definition) -> (Vec<Piece>, bool) {
+    let mut pieces = vec![Piece::Text(name.to_string())];
+
+    match (&definition.t, definition.right.as_deref()) {
+        (None, Some(Expression::Lambda(lambda))) => {
+            pieces.push(Piece::Text(" := ".to_string()));
+
+            if !lambda.params.is_empty() {
+                pieces.push(Piece::Text("(".to_string()));
+
+                for (i, (t, param)) in lambda.params.iter().enumerate() {
+                    if i > 0 {
+                        pieces.push(Piece::Text(", ".to_string()))
+                    }
+
+                    pieces.push(Piece::Text(param.to_string()));
+
+                    if let Some(t) = t {
+                        pieces.push(Piece::Text(": ".to_string()));
+                        kind(t, &mut pieces)
+                    }
+                }
+
+                pieces.push(Piece::Text(")".to_string()));
+
+                if let Some(ref t) = lambda.t {
+                    pieces.push(Piece::Text(": ".to_string()));
+                    kind(t, &mut pieces)
+                }
+
+                pieces.push(Piece::Text(" ".to_string()))
+            }
+
+            pieces.push(Piece::Text("->".to_string()));
+
+            return (join(pieces), true)
+        },
+
+        (Some(t), _) => {
+            pieces.push(Piece::Text(": ".to_string()));
+            kind(t, &mut pieces)
+        },
+
+        (None, Some(right @ Expression::Number(_))) |
+        (None, Some(right @ Expression::Str(_)))    |
+        (None, Some(right @ Expression::Char(_)))   |
+        (None, Some(right @ Expression::Bool(_)))   => pieces.push(Piece::Text(format!(" := {}", right))),
+
+        _ => (),
+    }
+
+    (join(pieces), false)
+}
+
+fn kind(t: &Type, pieces: &mut Vec<Piece>) {
+    match *t {
+        Type::Identifier(ref name) => pieces.push(Piece::Type(name.clone())),
+        Type::Mut(Some(ref t))     => {
+            pieces.push(Piece::Text("mut ".to_string()));
+            kind(t, pieces)
+        },
+        Type::Array(ref t, ref len) => {
+            pieces.push(Piece::Text("[".to_string()));
+            kind(t, pieces);
+
+            if let Some(ref len) = *len {
+                pieces.push(Piece::Text(format!("; {}", len)))
+            }
+
+            pieces.push(Piece::Text("]".to_string()))
+        },
+        ref t => pieces.push(Piece::Text(t.to_string())),
+    }
+}
+
+// neighbouring text pieces run together
+fn join(pieces: Vec<Piece>) -> Vec<Piece> {
+    let mut joined: Vec<Piece> = Vec::new();
+
+    for piece in pieces {
+        match (joined.last_mut(), piece) {
+            (Some(&mut Piece::Text(ref mut last)), Piece::Text(text)) => last.push_str(&text),
+            (_, piece)                                                 => joined.push(piece),
+        }
+    }
+
+    joined
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use super::*;
+
+// where every documented name lives, so signatures and `names` in docs can
+// point there, a name the page itself defines winning over the others
+pub struct Links<'a> {
+    names: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> Links<'a> {
+    pub fn new(pages: &'a [Page]) -> Links<'a> {
+        let mut names = HashMap::new();
+
+        for page in pages {
+            for item in &page.items {
+                names.entry(item.name.as_str()).or_insert_with(Vec::new).push(page.name.as_str())
+            }
+        }
+
+        Links {
+            names,
+        }
+    }
+
+    pub fn href(&self, name: &str, from: &str, extension: &str) -> Option<String> {
+        let pages = self.names.get(name)?;
+
+        if pages.contains(&from) {
+            Some(format!("#{}", anchor(name)))
+        } else {
+            Some(format!("{}.{}#{}", pages[0], extension, anchor(name)))
+        }
+    }
+}
+
+// names may hold `?` and `'`, which mean something else in a url
+pub fn anchor(name: &str) -> String {
+    let mut encoded = String::new();
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+            encoded.push(c)
+        } else {
+            let mut bytes = [0; 4];
+
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte))
+            }
+        }
+    }
+
+    encoded
+}
+
+// doc lines as paragraphs, a blank line between each
+pub fn paragraphs(docs: &[String]) -> Vec<String> {
+    docs.split(|line| line.trim().is_empty())
+        .filter(|lines| !lines.is_empty())
+        .map(|lines| lines.iter().map(|line| line.trim()).collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+// text and `code` taking turns, starting with text
+pub fn spans(text: &str) -> Vec<&str> {
+    text.split('`').collect()
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+// the same site as markdown, for a repository's own docs to carry
+pub fn site(pages: &[Page]) -> Vec<(String, String)> {
+    let links = Links::new(pages);
+
+    let mut files = vec![("index.md".to_string(), index(pages))];
+
+    for page in pages {
+        files.push((format!("{}.md", page.name), render(page, &links)))
+    }
+
+    files
+}
+
+fn index(pages: &[Page]) -> String {
+    let mut out = String::from("# modules\n\n");
+
+    for page in pages {
+        let summary = paragraphs(&page.docs).into_iter().next().map_or(String::new(), |summary| format!(" — {}", summary));
+
+        out.push_str(&format!("- [{}]({}.md){}\n", page.name, anchor(&page.name), summary))
+    }
+
+    out
+}
+
+fn render(page: &Page, links: &Links) -> String {
+    let mut out = format!("[index](index.md)\n\n# {}\n\n", page.name);
+
+    out.push_str(&text(&page.docs, links, &page.name));
+
+    for item in &page.items {
+        out.push_str(&format!("- [{}](#{})\n", item.name, anchor(&item.name)))
+    }
+
+    if !page.items.is_empty() {
+        out.push('\n')
+    }
+
+    for item in &page.items {
+        // headings get anchors of their own making, this one is ours
+        out.push_str(&format!(
+            "<a id=\"{}\"></a>\n\n### `{}`\n\n{}",
+            item.name,
+            plain(&item.signature),
+            text(&item.docs, links, &page.name),
+        ));
+
+        let types = item.signature.iter().filter_map(|piece| match *piece {
+            Piece::Type(ref name) => links.href(name, &page.name, "md").map(|href| format!("[{}]({})", name, href)),
+            Piece::Text(_)        => None,
+        }).collect::<Vec<_>>();
+
+        if !types.is_empty() {
+            out.push_str(&format!("see {}\n\n", types.join(", ")))
+        }
+    }
+
+    out
+}
+
+// markdown has no links inside code, the types are linked below instead
+fn plain(pieces: &[Piece]) -> String {
+    pieces.iter().map(|piece| match *piece {
+        Piece::Text(ref text) => text.as_str(),
+        Piece::Type(ref name) => name.as_str(),
+    }).collect()
+}
+
+fn text(docs: &[String], links: &Links, from: &str) -> String {
+    paragraphs(docs).iter().map(|paragraph| {
+        let text = spans(paragraph).iter().enumerate().map(|(i, span)| {
+            if i % 2 == 0 {
+                return span.to_string()
+            }
+
+            match links.href(span, from, "md") {
+                Some(href) => format!("[`{}`]({})", span, href),
+                None       => format!("`{}`", span),
+            }
+        }).collect::<String>();
+
+        format!("{}\n\n", text)
+    }).collect()
+}
@@ -0,0 +1,9 @@
+pub mod item;
+pub mod links;
+pub mod html;
+pub mod markdown;
+
+pub use super::*;
+
+pub use self::item::*;
+pub use self::links::*;
@@ -0,0 +1,584 @@
+use super::*;
+
+// prints a program back out as canonical itu, comments are trivia the parser
+// never sees so they are put back by the line they sat on
+pub fn format(source: &str, align: bool) -> ParserResult<String> {
+    let mut lexer = lexer(&mut source.chars());
+    let tokens    = lexer.by_ref().collect();
+
+    let mut parser = Parser::new(Traveler::new(tokens));
+    let ast        = parser.parse()?;
+
+    let mut formatter = Formatter::new(source, lexer.comments().clone(), parser.starts().clone(), align);
+
+    Ok(formatter.format(&ast))
+}
+
+// the left of a definition, kept apart so runs of them can share the column of `=`
+struct Head {
+    name: String,
+    t:    Option<String>,
+}
+
+impl Head {
+    fn column(&self) -> usize {
+        match self.t {
+            Some(ref t) => self.name.chars().count() + t.chars().count() + 3,
+            None        => self.name.chars().count() + 2,
+        }
+    }
+
+    fn text(&self, column: usize) -> String {
+        let pad = " ".repeat(column.max(self.column()) - self.column());
+
+        match self.t {
+            Some(ref t) => format!("{}: {} {}=", self.name, t, pad),
+            None        => format!("{} {}:=", self.name, pad),
+        }
+    }
+}
+
+enum Item {
+    Blank,
+    Comment(String),
+    Statement(String, Option<String>),
+    Definition(Head, String, Option<String>),
+}
+
+pub struct Formatter<'a> {
+    lines:    Vec<&'a str>,
+    comments: Vec<Token>,
+    comment:  usize,
+    starts:   Vec<TokenPosition>,
+    start:    usize,
+    depth:    usize,
+    align:    bool,
+}
+
+#[allow(dead_code)]
+impl<'a> Formatter<'a> {
+    pub fn new(source: &'a str, comments: Vec<Token>, starts: Vec<TokenPosition>, align: bool) -> Self {
+        Formatter {
+            lines:   source.lines().collect(),
+            comments,
+            comment: 0,
+            starts,
+            start:   0,
+            depth:   0,
+            align,
+        }
+    }
+
+    // a formatter with no source behind it, for printing trees built by hand
+    pub fn plain() -> Formatter<'static> {
+        Formatter::new("", Vec::new(), Vec::new(), false)
+    }
+
+    pub fn format(&mut self, ast: &[Statement]) -> String {
+        let lines = self.sequence(ast);
+
+        if lines.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", lines.join("\n"))
+        }
+    }
+
+    fn indent(&self) -> String {
+        "  ".repeat(self.depth)
+    }
+
+    fn blank_before(&self, line: usize) -> bool {
+        line > 1 && self.lines.get(line - 2).is_some_and(|l| l.trim().is_empty())
+    }
+
+    fn separate(&self, line: usize, items: &mut Vec<Item>) {
+        match items.last() {
+            None | Some(&Item::Blank) => (),
+            _ if self.blank_before(line) => items.push(Item::Blank),
+            _ => (),
+        }
+    }
+
+    // comments ahead of `line`, stopping at the first one indented less than `column`
+    fn trivia(&mut self, line: usize, column: usize, items: &mut Vec<Item>) {
+        while let Some(comment) = self.comments.get(self.comment).cloned() {
+            if comment.position.line >= line || comment.position.col < column {
+                break
+            }
+
+            self.comment += 1;
+
+            self.separate(comment.position.line, items);
+            items.push(Item::Comment(comment.content().clone()))
+        }
+    }
+
+    // a comment after code on the line a statement starts on
+    fn trailing(&mut self, line: usize) -> Option<String> {
+        let comment = self.comments.get(self.comment)?;
+
+        if comment.position.line != line {
+            return None
+        }
+
+        let code = self.lines.get(line - 1)?.chars().take(comment.position.col).any(|c| !c.is_whitespace());
+
+        if code {
+            self.comment += 1;
+            Some(comment.content().clone())
+        } else {
+            None
+        }
+    }
+
+    fn sequence(&mut self, body: &[Statement]) -> Vec<String> {
+        let mut items  = Vec::new();
+        let mut column = 0;
+
+        for (i, statement) in body.iter().enumerate() {
+            let start = self.starts.get(self.start).cloned().unwrap_or_default();
+            self.start += 1;
+
+            // comments closing a block are told apart from the ones after it by indentation
+            if i == 0 && self.depth > 0 {
+                column = start.col
+            }
+
+            self.trivia(start.line, 0, &mut items);
+
+            if let Statement::Expression(ref e, _) = *statement {
+                if **e == Expression::EOF {
+                    continue
+                }
+            }
+
+            self.separate(start.line, &mut items);
+
+            let trailing = self.trailing(start.line);
+            let item     = self.statement(statement, trailing);
+
+            items.push(item)
+        }
+
+        let next = self.starts.get(self.start).map_or(usize::MAX, |p| p.line);
+        self.trivia(next, column, &mut items);
+
+        self.layout(items)
+    }
+
+    fn layout(&self, items: Vec<Item>) -> Vec<String> {
+        let indent = self.indent();
+
+        let mut columns = vec![0; items.len()];
+
+        if self.align {
+            let mut i = 0;
+
+            while i < items.len() {
+                let mut j      = i;
+                let mut column = 0;
+
+                while let Some(Item::Definition(head, _, _)) = items.get(j) {
+                    column = column.max(head.column());
+                    j += 1
+                }
+
+                for c in &mut columns[i .. j] {
+                    *c = column
+                }
+
+                i = j.max(i + 1)
+            }
+        }
+
+        items.into_iter().zip(columns).map(|(item, column)| match item {
+            Item::Blank                             => String::new(),
+            Item::Comment(text)                     => format!("{}{}", indent, text),
+            Item::Statement(text, trailing)         => format!("{}{}", indent, with_comment(text, trailing)),
+            Item::Definition(head, right, trailing) => {
+                let text = format!("{} {}", head.text(column), right);
+                format!("{}{}", indent, with_comment(text, trailing))
+            },
+        }).collect()
+    }
+
+    fn statement(&mut self, statement: &Statement, trailing: Option<String>) -> Item {
+        match *statement {
+            Statement::Expression(ref e, _) => Item::Statement(self.expression(e, true), trailing),
+
+            Statement::Assignment(ref assignment) => {
+                let left  = self.expression(&assignment.left, false);
+                let right = self.expression(&assignment.right, true);
+
+                Item::Statement(format!("{} = {}", left, right), trailing)
+            },
+
+            Statement::Definition(ref definition) => {
+                let name = self.expression(&definition.name, false);
+                let name = if definition.public { format!("pub {}", name) } else { name };
+                let t    = definition.t.as_ref().map(|t| self.kind(t));
+                let head = Head { name, t };
+
+                match definition.right {
+                    Some(ref right) => {
+                        let right = self.expression(right, true);
+
+                        // only one-line definitions are lined up
+                        if right.contains('\n') {
+                            Item::Statement(format!("{} {}", head.text(0), right), trailing)
+                        } else {
+                            Item::Definition(head, right, trailing)
+                        }
+                    },
+
+                    None => match head.t {
+                        Some(t) => Item::Statement(format!("{}: {}", head.name, t), trailing),
+                        None    => Item::Statement(format!("{}:", head.name), trailing),
+                    },
+                }
+            },
+
+            Statement::Test(ref test) => {
+                let name = self.expression(&Expression::Str(test.name.clone()), true);
+
+                let text = match *test.body {
+                    Expression::Block(ref body) => format!("test {} ->{}", name, self.block(body)),
+                    ref body                    => format!("test {} -> {}", name, self.expression(body, true)),
+                };
+
+                Item::Statement(text, trailing)
+            },
+
+            Statement::Import(ref import) => {
+                let path = import.path.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+                Item::Statement(format!("import {}", path.join("/")), trailing)
+            },
+        }
+    }
+
+    pub fn kind(&mut self, t: &Type) -> String {
+        match *t {
+            Type::Mut(None)            => "mut".to_string(),
+            Type::Mut(Some(ref t))     => format!("mut {}", self.kind(t)),
+            Type::Array(ref t, None)   => format!("[{}]", self.kind(t)),
+            Type::Array(ref t, Some(ref len)) => {
+                let t = self.kind(t);
+                format!("[{}; {}]", t, self.expression(len, true))
+            },
+            Type::Identifier(ref name) => name.to_string(),
+            Type::I08                  => "i08".to_string(),
+            Type::I16                  => "i16".to_string(),
+            Type::I32                  => "i32".to_string(),
+            Type::I64                  => "i64".to_string(),
+            Type::I128                 => "i128".to_string(),
+            Type::F32                  => "f32".to_string(),
+            Type::F64                  => "f64".to_string(),
+            Type::U08                  => "u08".to_string(),
+            Type::U16                  => "u16".to_string(),
+            Type::U32                  => "u32".to_string(),
+            Type::U64                  => "u64".to_string(),
+            Type::U128                 => "u128".to_string(),
+            Type::Char                 => "char".to_string(),
+            Type::Str                  => "str".to_string(),
+            Type::Bool                 => "bool".to_string(),
+            Type::Any                  => "any".to_string(),
+        }
+    }
+
+    // parentheses around an expression, a closing one after a block goes on a line of its own
+    fn wrap(&self, text: String) -> String {
+        if text.contains('\n') {
+            format!("({}\n{})", text, self.indent())
+        } else {
+            format!("({})", text)
+        }
+    }
+
+    fn block(&mut self, body: &[Statement]) -> String {
+        self.depth += 1;
+        let lines = self.sequence(body);
+        self.depth -= 1;
+
+        lines.iter().map(|line| format!("\n{}", line)).collect()
+    }
+
+    // `tail` tells whether the expression runs to the end of whatever holds
+    // it, calls, lambdas and ifs swallow what follows them otherwise
+    pub fn expression(&mut self, expression: &Expression, tail: bool) -> String {
+        match *expression {
+            Expression::Block(ref body)      => self.block(body),
+            Expression::Number(n)            => n.to_string(),
+            Expression::Bool(b)              => b.to_string(),
+            Expression::Str(ref s)           => format!("\"{}\"", escape(s, '"')),
+            Expression::Char(c)              => format!("'{}'", escape(&c.to_string(), '\'')),
+            Expression::Identifier(ref name) => name.to_string(),
+            Expression::Operation(ref op)    => self.operation(op),
+
+            Expression::Call(ref call) => {
+                let callee = self.target(&call.callee);
+
+                if call.args.is_empty() {
+                    return format!("{}!", callee)
+                }
+
+                let mut args = Vec::new();
+
+                for (i, arg) in call.args.iter().enumerate() {
+                    // a keyword can't open the argument list
+                    let keyword = i == 0 && match **arg {
+                        Expression::If(_)          => true,
+                        Expression::Match(_)       => true,
+                        Expression::Lambda(ref l)  => l.params.is_empty(),
+                        _                          => false,
+                    };
+
+                    args.push(self.expression(arg, i + 1 == call.args.len() && !keyword))
+                }
+
+                let text = format!("{} {}", callee, args.join(", "));
+
+                if tail { text } else { self.wrap(text) }
+            },
+
+            Expression::Lambda(ref lambda) => {
+                let mut params = Vec::new();
+
+                for (t, name) in &lambda.params {
+                    match *t {
+                        Some(ref t) => {
+                            let t = self.kind(t);
+                            params.push(format!("{}: {}", name, t))
+                        },
+                        None => params.push(name.to_string()),
+                    }
+                }
+
+                let head = match lambda.t {
+                    _ if params.is_empty() => "->".to_string(),
+                    Some(ref t)            => {
+                        let t = self.kind(t);
+                        format!("({}): {} ->", params.join(", "), t)
+                    },
+                    None                   => format!("({}) ->", params.join(", ")),
+                };
+
+                let text = match *lambda.body {
+                    Expression::Block(ref body) => format!("{}{}", head, self.block(body)),
+                    ref body                    => format!("{} {}", head, self.expression(body, true)),
+                };
+
+                if tail { text } else { self.wrap(text) }
+            },
+
+            Expression::Array(ref content) => {
+                let mut items = Vec::new();
+
+                for (i, item) in content.iter().enumerate() {
+                    items.push(self.expression(item, i + 1 == content.len()))
+                }
+
+                format!("{{{}}}", items.join(", "))
+            },
+
+            Expression::Index(ref index) => {
+                let id = match *index.id {
+                    Expression::Array(_) => self.expression(&index.id, false),
+                    _                    => self.target(&index.id),
+                };
+
+                format!("{}[{}]", id, self.expression(&index.index, true))
+            },
+
+            Expression::Field(ref field) => format!("{}.{}", self.target(&field.object), field.name),
+
+            Expression::If(ref branch) => {
+                let text = self.branch(branch);
+
+                if tail { text } else { self.wrap(text) }
+            },
+
+            Expression::Match(ref subject) => {
+                let text = self.match_expression(subject);
+
+                if tail { text } else { self.wrap(text) }
+            },
+
+            Expression::EOF => String::new(),
+        }
+    }
+
+    // names and fields stand bare in front of calls, indices and fields, anything else is wrapped
+    fn target(&mut self, expression: &Expression) -> String {
+        match *expression {
+            Expression::Identifier(_) | Expression::Field(_) => self.expression(expression, false),
+            _ => {
+                let text = self.expression(expression, true);
+                self.wrap(text)
+            },
+        }
+    }
+
+    // operands never run to the end, so calls in them are always wrapped
+    fn operation(&mut self, operation: &Operation) -> String {
+        let precedence = operation.op.precedence();
+
+        // operands bound looser than the operator need parentheses, on the right
+        // equal precedence does too since operators group to the left
+        let left = match *operation.left {
+            Expression::Operation(ref left) if left.op.precedence() > precedence => {
+                let text = self.operation(left);
+                self.wrap(text)
+            },
+            ref left => self.expression(left, false),
+        };
+
+        let right = match *operation.right {
+            Expression::Operation(ref right) if right.op.precedence() >= precedence => {
+                let text = self.operation(right);
+                self.wrap(text)
+            },
+            ref right => self.expression(right, false),
+        };
+
+        format!("{} {} {}", left, operation.op.as_str(), right)
+    }
+
+    fn branch(&mut self, branch: &If) -> String {
+        let condition = match *branch.condition {
+            Expression::If(_) | Expression::Lambda(_) => self.expression(&branch.condition, false),
+            ref condition                             => self.expression(condition, true),
+        };
+
+        let mut text = format!("if {}", condition);
+
+        match *branch.body {
+            Expression::Block(ref body) => text.push_str(&self.block(body)),
+
+            ref body => {
+                // an if left open at the end would take the `else` meant for this one
+                let open = branch.otherwise.is_some() && dangles(body);
+
+                text.push_str(&format!(" then {}", self.expression(body, !open)))
+            },
+        }
+
+        // after a block the branch goes on a line of its own, so does an `else` opening one
+        let separator = match branch.otherwise {
+            _ if text.contains('\n')                                           => format!("\n{}", self.indent()),
+            Some(ref otherwise) if matches!(**otherwise, Expression::Block(_)) => format!("\n{}", self.indent()),
+            _                                                                  => " ".to_string(),
+        };
+
+        if let Some(ref otherwise) = branch.otherwise {
+            text.push_str(&separator);
+
+            match **otherwise {
+                Expression::If(ref elif)    => text.push_str(&format!("el{}", self.branch(elif))),
+                Expression::Block(ref body) => text.push_str(&format!("else{}", self.block(body))),
+                ref body                    => text.push_str(&format!("else {}", self.expression(body, true))),
+            }
+        }
+
+        text
+    }
+
+    // the arms a line each, indented one step past the `match`
+    fn match_expression(&mut self, subject: &Match) -> String {
+        let mut text = match *subject.subject {
+            Expression::If(_) | Expression::Lambda(_) => format!("match {}", self.expression(&subject.subject, false)),
+            ref value                                 => format!("match {}", self.expression(value, true)),
+        };
+
+        self.depth += 1;
+
+        for arm in &subject.arms {
+            let line = self.arm(arm);
+            text.push_str(&format!("\n{}{}", self.indent(), line))
+        }
+
+        self.depth -= 1;
+
+        text
+    }
+
+    pub fn arm(&mut self, arm: &Arm) -> String {
+        let mut head = print_pattern(&arm.pattern);
+
+        // the guard runs up to the arrow, so calls in it are wrapped
+        if let Some(ref guard) = arm.guard {
+            head.push_str(&format!(" if {}", self.expression(guard, false)))
+        }
+
+        match *arm.body {
+            Expression::Block(ref body) => format!("{} ->{}", head, self.block(body)),
+            ref body                    => format!("{} -> {}", head, self.expression(body, true)),
+        }
+    }
+}
+
+pub fn print_pattern(pattern: &Pattern) -> String {
+    match *pattern {
+        Pattern::Wildcard          => "_".to_string(),
+        Pattern::Binding(ref name) => name.to_string(),
+        Pattern::Number(n)         => n.to_string(),
+        Pattern::Bool(b)           => b.to_string(),
+        Pattern::Str(ref s)        => format!("\"{}\"", escape(s, '"')),
+        Pattern::Char(c)           => format!("'{}'", escape(&c.to_string(), '\'')),
+
+        Pattern::Array(ref elements, ref rest) => {
+            let mut items = elements.iter().map(print_pattern).collect::<Vec<_>>();
+
+            match rest.as_deref() {
+                Some(&Pattern::Wildcard) => items.push("...".to_string()),
+                Some(rest)               => items.push(format!("...{}", print_pattern(rest))),
+                None                     => (),
+            }
+
+            format!("{{{}}}", items.join(", "))
+        },
+    }
+}
+
+// whether an expression printed in tail position ends in an if without `else`
+fn dangles(expression: &Expression) -> bool {
+    match *expression {
+        Expression::If(ref branch) => match branch.otherwise {
+            Some(ref otherwise) => dangles(otherwise),
+            None                => true,
+        },
+        Expression::Lambda(ref lambda) => dangles(&lambda.body),
+        Expression::Call(ref call)     => call.args.last().is_some_and(|arg| dangles(arg)),
+        _                              => false,
+    }
+}
+
+fn with_comment(text: String, comment: Option<String>) -> String {
+    match comment {
+        Some(comment) => match text.find('\n') {
+            Some(i) => format!("{}  {}{}", &text[.. i], comment, &text[i ..]),
+            None    => format!("{}  {}", text, comment),
+        },
+        None => text,
+    }
+}
+
+fn escape(s: &str, quote: char) -> String {
+    let mut escaped = String::new();
+
+    for c in s.chars() {
+        match c {
+            '\\'            => escaped.push_str("\\\\"),
+            '\n'            => escaped.push_str("\\n"),
+            '\r'            => escaped.push_str("\\r"),
+            '\t'            => escaped.push_str("\\t"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c)
+            },
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+// characters that make up a name, a keyword, type or number running into
+// them is part of that name instead
+const NAME: &str = r"[\p{Alnum}_?']";
+
+// a textmate grammar, as editors take them for highlighting, written out
+// from the same lists the lexer matches against so the two never disagree
+pub fn grammar() -> String {
+    let words = |words: &[&str]| format!(r"(?<!{}){}(?![\p{{Alnum}}_@?])", NAME, alternatives(words));
+
+    let keywords = KEYWORDS.iter().cloned().filter(|keyword| keyword.chars().all(char::is_alphanumeric)).collect::<Vec<_>>();
+    let arrows   = KEYWORDS.iter().cloned().filter(|keyword| !keyword.chars().all(char::is_alphanumeric)).collect::<Vec<_>>();
+
+    let rules = vec![
+        ("doc-comment", rule("comment.line.documentation.itu", "---(?!-).*$")),
+        ("comment", rule("comment.line.double-dash.itu", "--.*$")),
+
+        ("raw-string", format!(
+            "{{ \"name\": {}, \"begin\": {}, \"end\": {} }}",
+            quote("string.quoted.double.raw.itu"), quote(&format!("(?<!{})r\"", NAME)), quote("\""),
+        )),
+
+        ("string", format!(
+            "{{ \"name\": {}, \"begin\": {}, \"end\": {}, \"patterns\": [ {}, {} ] }}",
+            quote("string.quoted.double.itu"), quote("\""), quote("\""),
+            rule("constant.character.escape.itu", r#"\\[\\'"nrt]"#),
+            rule("invalid.illegal.escape.itu", r"\\."),
+        )),
+
+        ("char", rule("string.quoted.single.itu", &format!(r#"(?<!{})'(\\[\\'"nrt]|[^'\\])'"#, NAME))),
+
+        ("keyword", rule("keyword.control.itu", &words(&keywords))),
+        ("arrow", rule("keyword.operator.arrow.itu", &alternatives(&arrows))),
+        ("type", rule("storage.type.itu", &words(TYPES))),
+        ("boolean", rule("constant.language.boolean.itu", &words(BOOLEANS))),
+        ("number", rule("constant.numeric.itu", &format!(r"(?<!{})(\d+\.\d*|\.\d+|\d+)", NAME))),
+        ("operator", rule("keyword.operator.itu", &alternatives(OPERATORS))),
+        ("punctuation", rule("punctuation.itu", &alternatives(SYMBOLS))),
+    ];
+
+    let patterns = rules.iter()
+        .map(|&(name, _)| format!("    {{ \"include\": {} }}", quote(&format!("#{}", name))))
+        .collect::<Vec<_>>();
+
+    let repository = rules.iter()
+        .map(|&(name, ref rule)| format!("    {}: {}", quote(name), rule))
+        .collect::<Vec<_>>();
+
+    format!(
+        "{{\n  \"name\": \"itu\",\n  \"scopeName\": \"source.itu\",\n  \"fileTypes\": [\"itu\"],\n  \"patterns\": [\n{}\n  ],\n  \"repository\": {{\n{}\n  }}\n}}\n",
+        patterns.join(",\n"),
+        repository.join(",\n"),
+    )
+}
+
+fn rule(name: &str, pattern: &str) -> String {
+    format!("{{ \"name\": {}, \"match\": {} }}", quote(name), quote(pattern))
+}
+
+// a regex taking any of the constants as written, in the order given
+fn alternatives(constants: &[&str]) -> String {
+    let escaped = constants.iter().map(|constant| {
+        constant.chars().map(|c| match c {
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => format!("\\{}", c),
+            c                                                                                => c.to_string(),
+        }).collect::<String>()
+    }).collect::<Vec<_>>();
+
+    format!("({})", escaped.join("|"))
+}
+
+// a json string
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in text.chars() {
+        match c {
+            '"'  => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c    => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,66 @@
+use colored::*;
+
+use super::*;
+
+// the source as html, each token in a span classed after its token type,
+// `<span class="Keyword">if</span>`, the stylesheet is left to the page
+pub fn html(source: &str) -> String {
+    let mut out = String::from("<pre class=\"itu\"><code>");
+
+    for token in cst(source).tokens() {
+        let text = escape(token.text());
+
+        match token.kind() {
+            TokenType::Whitespace | TokenType::Indent | TokenType::EOL => out.push_str(&text),
+            kind                                                       => {
+                out.push_str(&format!("<span class=\"{:?}\">{}</span>", kind, text))
+            },
+        }
+    }
+
+    out.push_str("</code></pre>\n");
+    out
+}
+
+// the source colored for a terminal
+pub fn ansi(source: &str) -> String {
+    let mut out = String::new();
+
+    for token in cst(source).tokens() {
+        let color = match token.kind() {
+            TokenType::Keyword                                => Some(Color::Magenta),
+            TokenType::Type                                   => Some(Color::Cyan),
+            TokenType::StringLiteral | TokenType::CharLiteral => Some(Color::Green),
+            TokenType::IntLiteral | TokenType::FloatLiteral   => Some(Color::Yellow),
+            TokenType::BoolLiteral                            => Some(Color::Yellow),
+            TokenType::Operator                               => Some(Color::Blue),
+            TokenType::Comment                                => Some(Color::BrightBlack),
+            TokenType::DocComment                             => Some(Color::BrightGreen),
+            TokenType::Unknown                                => Some(Color::Red),
+            _                                                 => None,
+        };
+
+        match color {
+            Some(color) => out.push_str(&token.text().color(color).to_string()),
+            None        => out.push_str(token.text()),
+        }
+    }
+
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c   => escaped.push(c),
+        }
+    }
+
+    escaped
+}
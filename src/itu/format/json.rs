@@ -0,0 +1,34 @@
+use serde_json;
+
+use super::*;
+
+// bumped whenever the shape of the serialized tree changes, readers refuse
+// documents written under any other version
+
+pub const SCHEMA: u64 = 4;
+
+pub fn to_json(ast: &[Statement]) -> String {
+    let document = json!({
+        "schema":     SCHEMA,
+        "statements": ast,
+    });
+
+    serde_json::to_string_pretty(&document).unwrap()
+}
+
+pub fn from_json(source: &str) -> Result<Vec<Statement>, String> {
+    let mut document: serde_json::Value = serde_json::from_str(source).map_err(|why| why.to_string())?;
+
+    match document.get("schema").and_then(|schema| schema.as_u64()) {
+        Some(SCHEMA)  => (),
+        Some(version) => return Err(format!("unsupported schema version {}, expected {}", version, SCHEMA)),
+        None          => return Err("missing schema version".to_string()),
+    }
+
+    let statements = document.get_mut("statements").map(|statements| statements.take());
+
+    match statements {
+        Some(statements) => serde_json::from_value(statements).map_err(|why| why.to_string()),
+        None             => Err("missing statements".to_string()),
+    }
+}
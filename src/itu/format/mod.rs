@@ -0,0 +1,15 @@
+pub mod formatter;
+pub mod printer;
+pub mod highlight;
+pub mod grammar;
+#[cfg(feature = "serde")]
+pub mod json;
+
+pub use super::*;
+
+pub use self::formatter::*;
+pub use self::printer::*;
+pub use self::highlight::*;
+pub use self::grammar::*;
+#[cfg(feature = "serde")]
+pub use self::json::*;
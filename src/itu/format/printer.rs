@@ -0,0 +1,391 @@
+use std::fmt;
+use std::slice;
+
+use super::*;
+
+// the syntax tree prints as the itu it was parsed from, give or take layout,
+// parentheses only where precedence or a greedy call would read it otherwise
+
+pub fn print(ast: &[Statement]) -> String {
+    Formatter::plain().format(ast)
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", print(slice::from_ref(self)).trim_end())
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Formatter::plain().expression(self, true))
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Formatter::plain().kind(self))
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+macro_rules! display {
+    ($($node:ident),*) => {
+        $(
+            impl fmt::Display for $node {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{}", Expression::$node(self.clone()))
+                }
+            }
+        )*
+    };
+}
+
+display!(Operation, Call, Lambda, Index, Field, If, Match);
+
+impl fmt::Display for Arm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Formatter::plain().arm(self))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", print_pattern(self))
+    }
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Statement::Assignment(self.clone()))
+    }
+}
+
+impl fmt::Display for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Statement::Definition(self.clone()))
+    }
+}
+
+impl fmt::Display for Test {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Statement::Test(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::rc::Rc;
+
+    // xorshift, so that every run prints the same trees
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            (self.0 % n as u64) as usize
+        }
+
+        fn chance(&mut self) -> bool {
+            self.below(2) == 0
+        }
+
+        fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+            items[self.below(items.len())].clone()
+        }
+    }
+
+    const NAMES:    &[&str]    = &["a", "b", "xs", "total", "io"];
+    const STRINGS:  &[&str]    = &["", "itu", "say \"hi\"", "tab\tand\nline", "back\\slash"];
+    const CHARS:    &[char]    = &['x', '\'', '\n', '\\'];
+    const OPERANDS: &[Operand] = &[
+        Operand::Pow, Operand::Mul, Operand::Div, Operand::Mod, Operand::Add, Operand::Sub,
+        Operand::Equal, Operand::NEqual, Operand::Lt, Operand::Gt, Operand::LtEqual, Operand::GtEqual,
+    ];
+
+    fn name(random: &mut Random) -> Rc<String> {
+        Rc::new(random.pick(NAMES).to_string())
+    }
+
+    fn kind(random: &mut Random) -> Type {
+        random.pick(&[Type::I32, Type::F64, Type::Str, Type::Bool, Type::Identifier(Rc::new("vector".to_string()))])
+    }
+
+    fn leaf(random: &mut Random) -> Expression {
+        match random.below(6) {
+            0 => Expression::Number(random.below(100) as f64),
+            1 => Expression::Number(random.below(100) as f64 + 0.5),
+            2 => Expression::Bool(random.chance()),
+            3 => Expression::Str(Rc::new(random.pick(STRINGS).to_string())),
+            4 => Expression::Char(random.pick(CHARS)),
+            _ => Expression::Identifier(name(random)),
+        }
+    }
+
+    // `open` tells whether the expression runs to the end of its line, the
+    // parser only finds blocks and matches where it does
+    fn body(random: &mut Random, depth: usize, open: bool) -> Rc<Expression> {
+        if open && random.below(3) == 0 {
+            Rc::new(block(random, depth))
+        } else {
+            Rc::new(expression(random, depth, open))
+        }
+    }
+
+    fn block(random: &mut Random, depth: usize) -> Expression {
+        let count = 1 + random.below(3);
+        Expression::Block((0 .. count).map(|_| statement(random, depth)).collect())
+    }
+
+    fn pattern(random: &mut Random, depth: usize) -> Pattern {
+        match random.below(if depth == 0 { 6 } else { 7 }) {
+            0 => Pattern::Wildcard,
+            1 => Pattern::Binding(name(random)),
+            2 => Pattern::Number(random.below(10) as f64),
+            3 => Pattern::Bool(random.chance()),
+            4 => Pattern::Str(Rc::new(random.pick(STRINGS).to_string())),
+            5 => Pattern::Char(random.pick(CHARS)),
+            _ => {
+                let elements = (0 .. random.below(3)).map(|_| pattern(random, depth - 1)).collect();
+
+                let rest = match random.below(3) {
+                    0 => None,
+                    1 => Some(Rc::new(Pattern::Wildcard)),
+                    _ => Some(Rc::new(Pattern::Binding(name(random)))),
+                };
+
+                Pattern::Array(elements, rest)
+            },
+        }
+    }
+
+    fn expression(random: &mut Random, depth: usize, open: bool) -> Expression {
+        if depth == 0 {
+            return leaf(random)
+        }
+
+        let depth = depth - 1;
+
+        match random.below(if open { 10 } else { 9 }) {
+            0 => leaf(random),
+
+            1 => Expression::Operation(Operation {
+                left:  Rc::new(expression(random, depth, false)),
+                op:    random.pick(OPERANDS),
+                right: Rc::new(expression(random, depth, false)),
+            }),
+
+            2 => {
+                let callee = if random.chance() {
+                    Expression::Identifier(name(random))
+                } else {
+                    Expression::Field(Field { object: Rc::new(Expression::Identifier(name(random))), name: name(random) })
+                };
+
+                let count = random.below(3);
+                let args  = (0 .. count).map(|i| Rc::new(expression(random, depth, open && i + 1 == count))).collect();
+
+                Expression::Call(Call { callee: Rc::new(callee), args })
+            },
+
+            3 => {
+                let params = (0 .. random.below(3))
+                    .map(|_| (if random.chance() { Some(kind(random)) } else { None }, name(random)))
+                    .collect::<Vec<_>>();
+
+                // a lambda without parameters has nowhere to write its type
+                let t = if !params.is_empty() && random.chance() { Some(Rc::new(kind(random))) } else { None };
+
+                Expression::Lambda(Lambda { t, params, body: body(random, depth, open) })
+            },
+
+            4 => Expression::Array((0 .. random.below(4)).map(|_| Rc::new(expression(random, depth, false))).collect()),
+
+            5 => {
+                let id = if random.chance() {
+                    Expression::Identifier(name(random))
+                } else {
+                    Expression::Array((0 .. 1 + random.below(2)).map(|_| Rc::new(leaf(random))).collect())
+                };
+
+                Expression::Index(Index { id: Rc::new(id), index: Rc::new(expression(random, depth, false)) })
+            },
+
+            6 => Expression::Field(Field { object: Rc::new(Expression::Identifier(name(random))), name: name(random) }),
+
+            7 | 8 => {
+                let otherwise = random.chance();
+
+                Expression::If(If {
+                    condition: Rc::new(expression(random, depth, false)),
+                    body:      body(random, depth, open && !otherwise),
+                    otherwise: if otherwise { Some(body(random, depth, open)) } else { None },
+                })
+            },
+
+            _ => {
+                let arms = (0 .. 1 + random.below(3))
+                    .map(|_| Arm {
+                        pattern:  pattern(random, 2),
+                        guard:    if random.below(3) == 0 { Some(Rc::new(expression(random, depth, false))) } else { None },
+                        body:     body(random, depth, true),
+                        position: TokenPosition::new(0, 0),
+                    })
+                    .collect();
+
+                Expression::Match(Match { subject: Rc::new(expression(random, depth, false)), arms, position: TokenPosition::new(0, 0) })
+            },
+        }
+    }
+
+    fn statement(random: &mut Random, depth: usize) -> Statement {
+        let position = TokenPosition::new(0, 0);
+
+        match random.below(4) {
+            0 => Statement::Definition(Definition {
+                t:      if random.chance() { Some(kind(random)) } else { None },
+                name:   Rc::new(Expression::Identifier(name(random))),
+                right:  Some(Rc::new(expression(random, depth, true))),
+                public: false,
+                position,
+            }),
+
+            1 => Statement::Assignment(Assignment {
+                left:  Rc::new(Expression::Identifier(name(random))),
+                right: Rc::new(expression(random, depth, true)),
+                position,
+            }),
+
+            _ => Statement::Expression(Rc::new(expression(random, depth, true)), TokenPosition::new(0, 0)),
+        }
+    }
+
+    // positions say where the source was, which printing does not keep
+    struct Forget;
+
+    impl VisitorMut for Forget {
+        fn visit_statement_mut(&mut self, statement: &mut Statement) {
+            if let Statement::Expression(_, ref mut position) = *statement {
+                *position = TokenPosition::new(0, 0)
+            }
+
+            walk_statement_mut(self, statement)
+        }
+
+        fn visit_assignment_mut(&mut self, assignment: &mut Assignment) {
+            assignment.position = TokenPosition::new(0, 0);
+            walk_assignment_mut(self, assignment)
+        }
+
+        fn visit_definition_mut(&mut self, definition: &mut Definition) {
+            definition.position = TokenPosition::new(0, 0);
+            walk_definition_mut(self, definition)
+        }
+
+        fn visit_match_mut(&mut self, subject: &mut Match) {
+            subject.position = TokenPosition::new(0, 0);
+            walk_match_mut(self, subject)
+        }
+
+        fn visit_arm_mut(&mut self, arm: &mut Arm) {
+            arm.position = TokenPosition::new(0, 0);
+            walk_arm_mut(self, arm)
+        }
+    }
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let lexer      = lexer(&mut source.chars());
+        let mut parser = Parser::new(Traveler::new(lexer.collect()));
+
+        let mut ast = match parser.parse() {
+            Ok(ast)  => ast,
+            Err(err) => panic!("{} does not parse: {:?}", source, err),
+        };
+
+        ast.retain(|statement| match *statement {
+            Statement::Expression(ref expression, _) => **expression != Expression::EOF,
+            _                                        => true,
+        });
+
+        for statement in &mut ast {
+            Forget.visit_statement_mut(statement)
+        }
+
+        ast
+    }
+
+    #[test]
+    fn printed_expressions_parse_back() {
+        let mut random = Random(0x2545_F491_4F6C_DD1D);
+
+        for _ in 0 .. 2000 {
+            let expression = expression(&mut random, 4, true);
+            let source     = expression.to_string();
+
+            assert_eq!(parse(&source), vec![Statement::Expression(Rc::new(expression), TokenPosition::new(0, 0))], "printed as:\n{}", source);
+        }
+    }
+
+    #[test]
+    fn printed_programs_parse_back() {
+        let mut random = Random(0x9E37_79B9_7F4A_7C15);
+
+        for _ in 0 .. 500 {
+            let ast    = (0 .. 1 + random.below(4)).map(|_| statement(&mut random, 3)).collect::<Vec<_>>();
+            let source = print(&ast);
+
+            assert_eq!(parse(&source), ast, "printed as:\n{}", source);
+        }
+    }
+
+    #[test]
+    fn nodes_print_like_their_expressions() {
+        let mut random = Random(0xD1B5_4A32_D192_ED03);
+
+        for _ in 0 .. 500 {
+            let expression = expression(&mut random, 3, true);
+
+            let node = match expression {
+                Expression::Operation(ref node) => node.to_string(),
+                Expression::Call(ref node)      => node.to_string(),
+                Expression::Lambda(ref node)    => node.to_string(),
+                Expression::Index(ref node)     => node.to_string(),
+                Expression::Field(ref node)     => node.to_string(),
+                Expression::If(ref node)        => node.to_string(),
+                Expression::Match(ref node)     => node.to_string(),
+                _                               => continue,
+            };
+
+            assert_eq!(node, expression.to_string())
+        }
+    }
+
+    #[test]
+    fn arms_and_patterns_print_as_written() {
+        let source = "match xs\n  {} -> 0\n  {a, ...rest} if a > 1 -> a\n  {_, ...} -> \"it\\\"s\"\n  _ -> 'x'\n";
+
+        let subject = match parse(source).remove(0) {
+            Statement::Expression(ref e, _) => match **e {
+                Expression::Match(ref subject) => subject.clone(),
+                _                              => panic!("expected a match"),
+            },
+            _ => panic!("expected a match"),
+        };
+
+        let arms = subject.arms.iter().map(|arm| arm.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(arms, vec!["{} -> 0", "{a, ...rest} if a > 1 -> a", "{_, ...} -> \"it\\\"s\"", "_ -> 'x'"]);
+        assert_eq!(subject.arms[1].pattern.to_string(), "{a, ...rest}");
+        assert_eq!(format!("{}\n", subject), source);
+    }
+}
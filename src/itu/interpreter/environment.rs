@@ -0,0 +1,58 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::*;
+
+pub struct Environment {
+    values: RefCell<HashMap<Rc<String>, Value>>,
+    parent: Option<Rc<Environment>>,
+}
+
+#[allow(dead_code)]
+impl Environment {
+    pub fn new() -> Rc<Environment> {
+        Rc::new(
+            Environment {
+                values: RefCell::new(HashMap::new()),
+                parent: None,
+            }
+        )
+    }
+
+    pub fn child(parent: &Rc<Environment>) -> Rc<Environment> {
+        Rc::new(
+            Environment {
+                values: RefCell::new(HashMap::new()),
+                parent: Some(parent.clone()),
+            }
+        )
+    }
+
+    pub fn define(&self, name: Rc<String>, value: Value) {
+        self.values.borrow_mut().insert(name, value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.values.borrow().get(&name.to_owned()) {
+            Some(value) => Some(value.clone()),
+            None        => match self.parent {
+                Some(ref parent) => parent.get(name),
+                None             => None,
+            },
+        }
+    }
+
+    // rebinds `name` in the scope that defined it
+    pub fn assign(&self, name: &str, value: Value) -> bool {
+        if let Some(slot) = self.values.borrow_mut().get_mut(&name.to_owned()) {
+            *slot = value;
+            return true
+        }
+
+        match self.parent {
+            Some(ref parent) => parent.assign(name, value),
+            None             => false,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use super::*;
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum RuntimeErrorValue {
+    Constant(String),
+}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub value:    RuntimeErrorValue,
+    pub position: Option<TokenPosition>,
+}
+
+#[allow(dead_code)]
+impl RuntimeError {
+    pub fn new(value: &str) -> RuntimeError {
+        RuntimeError {
+            value:    RuntimeErrorValue::Constant(value.to_owned()),
+            position: None,
+        }
+    }
+
+    pub fn new_pos(position: TokenPosition, value: &str) -> RuntimeError {
+        RuntimeError {
+            value:    RuntimeErrorValue::Constant(value.to_owned()),
+            position: Some(position),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            RuntimeErrorValue::Constant(ref s) => match self.position {
+                Some(p) => write!(f, "{}: {}", p, s),
+                None    => write!(f, "{}", s),
+            }
+        }
+    }
+}
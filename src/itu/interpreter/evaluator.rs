@@ -0,0 +1,341 @@
+use std::rc::Rc;
+
+use super::*;
+
+// calls nested deeper than this are taken for runaway recursion
+pub const DEPTH: usize = 10_000;
+
+pub struct Evaluator {
+    pub globals: Rc<Environment>,
+    position:    Option<TokenPosition>,
+    depth:       usize,
+}
+
+#[allow(dead_code)]
+impl Evaluator {
+    pub fn new() -> Evaluator {
+        Evaluator {
+            globals:  Environment::new(),
+            position: None,
+            depth:    0,
+        }
+    }
+
+    // evaluates the program in the global scope, giving back the value of
+    // the last statement
+    pub fn run(&mut self, ast: &[Statement]) -> RuntimeResult<Value> {
+        let globals = self.globals.clone();
+
+        let mut last = Value::Unit;
+
+        for statement in ast {
+            last = self.statement(statement, &globals)?
+        }
+
+        Ok(last)
+    }
+
+    // runs the body of a test in a scope of its own below the globals, a
+    // failure outside any statement of the body being the test's
+    pub fn test(&mut self, test: &Test) -> RuntimeResult<()> {
+        let env = Environment::child(&self.globals);
+
+        self.position = Some(test.position);
+
+        match *test.body {
+            Expression::Block(ref statements) => self.statements(statements, &env).map(|_| ()),
+            ref body                          => self.expression(body, &env).map(|_| ()),
+        }
+    }
+
+    fn error(&self, message: &str) -> RuntimeError {
+        match self.position {
+            Some(p) => RuntimeError::new_pos(p, message),
+            None    => RuntimeError::new(message),
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement], env: &Rc<Environment>) -> RuntimeResult<Value> {
+        let mut last = Value::Unit;
+
+        for statement in statements {
+            last = self.statement(statement, env)?
+        }
+
+        Ok(last)
+    }
+
+    fn statement(&mut self, statement: &Statement, env: &Rc<Environment>) -> RuntimeResult<Value> {
+        self.position = Some(statement.position());
+
+        match *statement {
+            Statement::Expression(ref expression, _) => self.expression(expression, env),
+
+            Statement::Assignment(ref assignment) => {
+                let value = self.expression(&assignment.right, env)?;
+                self.assign(&assignment.left, value, env)?;
+
+                Ok(Value::Unit)
+            },
+
+            Statement::Definition(ref definition) => {
+                let name = match *definition.name {
+                    Expression::Identifier(ref name) => name.clone(),
+                    _ => return Err(self.error("expected name")),
+                };
+
+                let value = match definition.right {
+                    Some(ref right) => match **right {
+                        // the closure captures `env` itself, so it can see its own name once bound
+                        Expression::Lambda(ref lambda) => Value::Closure(
+                            Rc::new(
+                                Closure {
+                                    name:   Some(name.clone()),
+                                    lambda: lambda.clone(),
+                                    env:    env.clone(),
+                                }
+                            )
+                        ),
+
+                        ref right => self.expression(right, env)?,
+                    },
+
+                    None => Value::Unit,
+                };
+
+                env.define(name, value);
+
+                Ok(Value::Unit)
+            },
+
+            // tests only run through `test`
+            Statement::Test(_) => Ok(Value::Unit),
+
+            // the loader bound the module before the program started
+            Statement::Import(_) => Ok(Value::Unit),
+        }
+    }
+
+    fn assign(&mut self, left: &Expression, value: Value, env: &Rc<Environment>) -> RuntimeResult<()> {
+        match *left {
+            Expression::Identifier(ref name) => if env.assign(name, value) {
+                Ok(())
+            } else {
+                Err(self.error(&format!("undefined name: {}", name)))
+            },
+
+            Expression::Index(ref index) => {
+                let array = self.expression(&index.id, env)?;
+                let at    = self.expression(&index.index, env)?;
+
+                array.set_index(&at, value).map_err(|err| self.error(&err))
+            },
+
+            _ => Err(self.error("invalid left-hand side of assignment")),
+        }
+    }
+
+    pub fn call(&mut self, callee: &Value, args: Vec<Value>) -> RuntimeResult<Value> {
+        match *callee {
+            Value::Closure(ref closure) => {
+                let params = &closure.lambda.params;
+
+                if params.len() != args.len() {
+                    return Err(self.error(&format!("{} expected {} argument(s), found {}", callee, params.len(), args.len())))
+                }
+
+                if self.depth == DEPTH {
+                    return Err(self.error(&format!("stack overflow, calls nested deeper than {}", DEPTH)))
+                }
+
+                let env = Environment::child(&closure.env);
+
+                for ((_, name), arg) in params.iter().zip(args) {
+                    env.define(name.clone(), arg)
+                }
+
+                let outer = self.position;
+
+                self.depth += 1;
+                let value = self.expression(&closure.lambda.body, &env);
+                self.depth -= 1;
+
+                self.position = outer;
+
+                value
+            },
+
+            Value::Builtin(ref builtin) => {
+                if builtin.arity != args.len() {
+                    return Err(self.error(&format!("{} expected {} argument(s), found {}", callee, builtin.arity, args.len())))
+                }
+
+                (builtin.function)(self, args).map_err(|err| match err.position {
+                    Some(_) => err,
+                    None    => self.error(&format!("{}", err)),
+                })
+            },
+
+            ref v => Err(self.error(&format!("cannot call {}", v.type_name()))),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, env: &Rc<Environment>) -> RuntimeResult<Value> {
+        match *expression {
+            Expression::Block(ref statements) => {
+                let env = Environment::child(env);
+                self.statements(statements, &env)
+            },
+
+            Expression::Number(n)     => Ok(Value::Number(n)),
+            Expression::Bool(b)       => Ok(Value::Bool(b)),
+            Expression::Str(ref s)    => Ok(Value::Str(s.clone())),
+            Expression::Char(c)       => Ok(Value::Char(c)),
+
+            Expression::Identifier(ref name) => match env.get(name) {
+                Some(value) => Ok(value),
+                None        => Err(self.error(&format!("undefined name: {}", name))),
+            },
+
+            Expression::Operation(ref operation) => {
+                let left  = self.expression(&operation.left, env)?;
+                let right = self.expression(&operation.right, env)?;
+
+                Value::operate(&operation.op, left, right).map_err(|err| self.error(&err))
+            },
+
+            Expression::Call(ref call) => {
+                let callee = self.expression(&call.callee, env)?;
+
+                let mut args = Vec::new();
+
+                for arg in &call.args {
+                    args.push(self.expression(arg, env)?)
+                }
+
+                self.call(&callee, args)
+            },
+
+            Expression::Lambda(ref lambda) => Ok(
+                Value::Closure(
+                    Rc::new(
+                        Closure {
+                            name:   None,
+                            lambda: lambda.clone(),
+                            env:    env.clone(),
+                        }
+                    )
+                )
+            ),
+
+            Expression::Array(ref content) => {
+                let mut values = Vec::new();
+
+                for element in content {
+                    values.push(self.expression(element, env)?)
+                }
+
+                Ok(Value::array(values))
+            },
+
+            Expression::Index(ref index) => {
+                let id = self.expression(&index.id, env)?;
+                let at = self.expression(&index.index, env)?;
+
+                id.index(&at).map_err(|err| self.error(&err))
+            },
+
+            Expression::Field(ref field) => {
+                let object = self.expression(&field.object, env)?;
+                object.field(&field.name).map_err(|err| self.error(&err))
+            },
+
+            Expression::If(ref branch) => match self.expression(&branch.condition, env)? {
+                Value::Bool(true)  => self.expression(&branch.body, env),
+                Value::Bool(false) => match branch.otherwise {
+                    Some(ref otherwise) => self.expression(otherwise, env),
+                    None                => Ok(Value::Unit),
+                },
+                ref v => Err(self.error(&format!("expected bool condition, found {}", v.type_name()))),
+            },
+
+            Expression::Match(ref subject) => {
+                let value = self.expression(&subject.subject, env)?;
+                let outer = self.position;
+
+                for arm in &subject.arms {
+                    let env = Environment::child(env);
+
+                    if !Self::matches(&arm.pattern, &value, &env) {
+                        continue
+                    }
+
+                    self.position = Some(arm.position);
+
+                    if let Some(ref guard) = arm.guard {
+                        match self.expression(guard, &env)? {
+                            Value::Bool(true)  => (),
+                            Value::Bool(false) => {
+                                self.position = outer;
+                                continue
+                            },
+                            ref v => return Err(self.error(&format!("expected bool guard, found {}", v.type_name()))),
+                        }
+                    }
+
+                    let body = self.expression(&arm.body, &env)?;
+                    self.position = outer;
+
+                    return Ok(body)
+                }
+
+                Err(RuntimeError::new_pos(subject.position, &format!("no arm matched {}", value)))
+            },
+
+            Expression::EOF => Ok(Value::Unit),
+        }
+    }
+
+    // whether `value` fits `pattern`, defining what it binds in `env`
+    fn matches(pattern: &Pattern, value: &Value, env: &Rc<Environment>) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => true,
+
+            (Pattern::Binding(name), _) => {
+                env.define(name.clone(), value.clone());
+                true
+            },
+
+            (Pattern::Number(a), Value::Number(b)) => a == b,
+            (Pattern::Bool(a), Value::Bool(b))     => a == b,
+            (Pattern::Str(a), Value::Str(b))       => a == b,
+            (Pattern::Char(a), Value::Char(b))     => a == b,
+
+            (Pattern::Array(elements, rest), Value::Array(content)) => {
+                let content = content.borrow();
+
+                let fits = match rest {
+                    Some(_) => content.len() >= elements.len(),
+                    None    => content.len() == elements.len(),
+                };
+
+                if !fits || !elements.iter().zip(content.iter()).all(|(pattern, value)| Self::matches(pattern, value, env)) {
+                    return false
+                }
+
+                match rest {
+                    Some(rest) => Self::matches(rest, &Value::array(content[elements.len() ..].to_vec()), env),
+                    None       => true,
+                }
+            },
+
+            _ => false,
+        }
+    }
+}
+
+impl Caller for Evaluator {
+    fn call(&mut self, callee: &Value, args: Vec<Value>) -> RuntimeResult<Value> {
+        Evaluator::call(self, callee, args)
+    }
+}
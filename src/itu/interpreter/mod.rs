@@ -0,0 +1,13 @@
+pub mod error;
+pub mod value;
+pub mod environment;
+pub mod evaluator;
+
+pub use super::*;
+
+pub use self::error::*;
+pub use self::value::*;
+pub use self::environment::*;
+pub use self::evaluator::*;
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
@@ -0,0 +1,211 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::*;
+
+#[derive(Clone)]
+pub enum Value {
+    Unit,
+    Number(f64),
+    Bool(bool),
+    Str(Rc<String>),
+    Char(char),
+    Array(Rc<RefCell<Vec<Value>>>),
+    Closure(Rc<Closure>),
+    Compiled(Rc<vm::Closure>),
+    Builtin(Rc<Builtin>),
+    Module(Rc<Module>),
+}
+
+// a lambda together with the scope it was created in, the scope is shared
+// rather than copied so later changes to captured names are visible
+pub struct Closure {
+    pub name:   Option<Rc<String>>,
+    pub lambda: Lambda,
+    pub env:    Rc<Environment>,
+}
+
+// whatever is running the program, builtins taking lambdas call back through it
+pub trait Caller {
+    fn call(&mut self, callee: &Value, args: Vec<Value>) -> RuntimeResult<Value>;
+}
+
+pub type BuiltinFunction = fn(&mut dyn Caller, Vec<Value>) -> RuntimeResult<Value>;
+
+pub struct Builtin {
+    pub name:     Rc<String>,
+    pub arity:    usize,
+    pub function: BuiltinFunction,
+}
+
+pub struct Module {
+    pub name:    Rc<String>,
+    pub members: HashMap<Rc<String>, Value>,
+}
+
+#[allow(dead_code)]
+impl Value {
+    pub fn array(content: Vec<Value>) -> Value {
+        Value::Array(Rc::new(RefCell::new(content)))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Unit       => "()",
+            Value::Number(_)  => "number",
+            Value::Bool(_)    => "bool",
+            Value::Str(_)     => "str",
+            Value::Char(_)    => "char",
+            Value::Array(_)   => "array",
+            Value::Closure(_) => "lambda",
+            Value::Compiled(_) => "lambda",
+            Value::Builtin(_) => "builtin",
+            Value::Module(_)  => "module",
+        }
+    }
+
+    // how the value looks when nested inside another, strings keep their quotes
+    pub fn repr(&self) -> String {
+        match *self {
+            Value::Str(ref s)  => format!("{:?}", s),
+            Value::Char(ref c) => format!("{:?}", c),
+            ref v              => format!("{}", v),
+        }
+    }
+
+    // the semantics of binary operators, shared by the evaluator and the vm
+    pub fn operate(op: &Operand, left: Value, right: Value) -> Result<Value, String> {
+        match (op, &left, &right) {
+            (&Operand::Equal, _, _)  => Ok(Value::Bool(left == right)),
+            (&Operand::NEqual, _, _) => Ok(Value::Bool(left != right)),
+
+            (&Operand::Add, Value::Str(a), b) => Ok(Value::Str(Rc::new(format!("{}{}", a, b)))),
+
+            (op, &Value::Number(a), &Value::Number(b)) => Ok(
+                match *op {
+                    Operand::Pow     => Value::Number(a.powf(b)),
+                    Operand::Mul     => Value::Number(a * b),
+                    Operand::Div     => Value::Number(a / b),
+                    Operand::Mod     => Value::Number(a % b),
+                    Operand::Add     => Value::Number(a + b),
+                    Operand::Sub     => Value::Number(a - b),
+                    Operand::Lt      => Value::Bool(a < b),
+                    Operand::Gt      => Value::Bool(a > b),
+                    Operand::LtEqual => Value::Bool(a <= b),
+                    Operand::GtEqual => Value::Bool(a >= b),
+                    Operand::Equal | Operand::NEqual => unreachable!(),
+                }
+            ),
+
+            (op, a, b) => Err(format!("cannot apply {:?} to {} and {}", op, a.type_name(), b.type_name())),
+        }
+    }
+
+    pub fn index(&self, at: &Value) -> Result<Value, String> {
+        match *self {
+            Value::Array(ref content) => {
+                let content = content.borrow();
+                let i       = offset(at, content.len())?;
+
+                Ok(content[i].clone())
+            },
+
+            Value::Str(ref s) => {
+                let i = offset(at, s.chars().count())?;
+                Ok(Value::Char(s.chars().nth(i).unwrap()))
+            },
+
+            ref v => Err(format!("cannot index into {}", v.type_name())),
+        }
+    }
+
+    pub fn set_index(&self, at: &Value, value: Value) -> Result<(), String> {
+        match *self {
+            Value::Array(ref content) => {
+                let i = offset(at, content.borrow().len())?;
+                content.borrow_mut()[i] = value;
+
+                Ok(())
+            },
+
+            ref v => Err(format!("cannot index into {}", v.type_name())),
+        }
+    }
+
+    pub fn field(&self, name: &Rc<String>) -> Result<Value, String> {
+        match *self {
+            Value::Module(ref module) => match module.members.get(name) {
+                Some(value) => Ok(value.clone()),
+                None        => Err(format!("module {} has no member {}", module.name, name)),
+            },
+
+            ref v => Err(format!("no field {} on {}", name, v.type_name())),
+        }
+    }
+}
+
+fn offset(index: &Value, len: usize) -> Result<usize, String> {
+    match *index {
+        Value::Number(n) if n >= 0.0 && n.fract() == 0.0 && (n as usize) < len => Ok(n as usize),
+        Value::Number(n) => Err(format!("index {} out of bounds for length {}", n, len)),
+        ref v            => Err(format!("expected number index, found {}", v.type_name())),
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Unit, Value::Unit)               => true,
+            (Value::Number(a), Value::Number(b))     => a == b,
+            (Value::Bool(a), Value::Bool(b))         => a == b,
+            (Value::Str(a), Value::Str(b))           => a == b,
+            (Value::Char(a), Value::Char(b))         => a == b,
+            (Value::Array(a), Value::Array(b))       => *a.borrow() == *b.borrow(),
+            (Value::Closure(a), Value::Closure(b))   => Rc::ptr_eq(a, b),
+            (Value::Compiled(a), Value::Compiled(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b))   => Rc::ptr_eq(a, b),
+            (Value::Module(a), Value::Module(b))     => Rc::ptr_eq(a, b),
+            _                                        => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Unit          => write!(f, "()"),
+            Value::Number(n)     => write!(f, "{}", n),
+            Value::Bool(b)       => write!(f, "{}", b),
+            Value::Str(ref s)    => write!(f, "{}", s),
+            Value::Char(c)       => write!(f, "{}", c),
+            Value::Array(ref a)  => {
+                write!(f, "{{")?;
+                for (i, v) in a.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?
+                    }
+                    write!(f, "{}", v.repr())?
+                }
+                write!(f, "}}")
+            },
+            Value::Closure(ref c) => match c.name {
+                Some(ref name) => write!(f, "<lambda {}>", name),
+                None           => write!(f, "<lambda>"),
+            },
+            Value::Compiled(ref c) => match c.function.name {
+                Some(ref name) => write!(f, "<lambda {}>", name),
+                None           => write!(f, "<lambda>"),
+            },
+            Value::Builtin(ref b) => write!(f, "<builtin {}>", b.name),
+            Value::Module(ref m)  => write!(f, "<module {}>", m.name),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.repr())
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::*;
+
+// the name of the file that overrides rule levels, looked for beside the
+// linted file and in the directories above it
+pub const CONFIG: &str = ".itu-lint";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    pub fn from_str(v: &str) -> Option<Level> {
+        match v {
+            "allow" => Some(Level::Allow),
+            "warn"  => Some(Level::Warn),
+            "deny"  => Some(Level::Deny),
+            _       => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Level::Allow => write!(f, "allow"),
+            Level::Warn  => write!(f, "warn"),
+            Level::Deny  => write!(f, "deny"),
+        }
+    }
+}
+
+// rule levels set apart from their defaults, a line each,
+//
+//     shadowed-name = allow
+//     redundant-mut = deny
+//
+// with `--` comments as in itu itself
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    levels: HashMap<String, Level>,
+}
+
+#[allow(dead_code)]
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut levels = HashMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split("--").next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue
+            }
+
+            let (rule, level) = match line.split_once('=') {
+                Some((rule, level)) => (rule.trim(), level.trim()),
+                None                => return Err(format!("line {}: expected `rule = level`", i + 1)),
+            };
+
+            if rule_named(rule).is_none() {
+                return Err(format!("line {}: unknown rule `{}`", i + 1, rule))
+            }
+
+            match Level::from_str(level) {
+                Some(level) => levels.insert(rule.to_string(), level),
+                None        => return Err(format!("line {}: expected allow, warn or deny, found `{}`", i + 1, level)),
+            };
+        }
+
+        Ok(Config { levels })
+    }
+
+    pub fn level(&self, rule: &Rule) -> Level {
+        self.levels.get(rule.id).cloned().unwrap_or(rule.level)
+    }
+}
@@ -0,0 +1,49 @@
+// text to put in place of the characters `start .. end` of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub start:       usize,
+    pub end:         usize,
+    pub replacement: String,
+}
+
+// a change that makes a lint go away without altering what the program does
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub message: String,
+    pub changes: Vec<Change>,
+}
+
+// the source with the fixes made, any fix running into an earlier one is
+// left for another pass
+pub fn apply(source: &str, fixes: &[&Fix]) -> (String, usize) {
+    let mut changes = Vec::new();
+    let mut applied = 0;
+
+    for fix in fixes {
+        let overlaps = fix.changes.iter().any(|change| {
+            changes.iter().any(|other: &&Change| change.start < other.end && other.start < change.end || change.start == other.start)
+        });
+
+        if !overlaps {
+            changes.extend(fix.changes.iter());
+            applied += 1
+        }
+    }
+
+    changes.sort_by_key(|change| change.start);
+
+    let chars = source.chars().collect::<Vec<_>>();
+
+    let mut out = String::new();
+    let mut at  = 0;
+
+    for change in changes {
+        out.extend(&chars[at .. change.start]);
+        out.push_str(&change.replacement);
+        at = change.end
+    }
+
+    out.extend(&chars[at ..]);
+
+    (out, applied)
+}
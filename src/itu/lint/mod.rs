@@ -0,0 +1,9 @@
+pub mod config;
+pub mod fix;
+pub mod rules;
+
+pub use super::*;
+
+pub use self::config::*;
+pub use self::fix::*;
+pub use self::rules::*;
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+pub struct Rule {
+    pub id:      &'static str,
+    pub level:   Level,
+    pub summary: &'static str,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule { id: "unused-definition",  level: Level::Warn, summary: "a definition nothing refers to" },
+    Rule { id: "shadowed-name",      level: Level::Warn, summary: "a definition hiding an earlier one of the same name" },
+    Rule { id: "bool-comparison",    level: Level::Warn, summary: "a comparison with `true` or `false`" },
+    Rule { id: "public-any",         level: Level::Warn, summary: "a `pub` function taking or giving `any`" },
+    Rule { id: "redundant-mut",      level: Level::Warn, summary: "a `mut` binding that is never assigned to" },
+    Rule { id: "constant-condition", level: Level::Warn, summary: "an `if` whose condition is known before running" },
+];
+
+pub fn rule_named(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+pub struct Lint {
+    pub rule:     &'static Rule,
+    pub level:    Level,
+    pub position: Option<TokenPosition>,
+    pub message:  String,
+    pub note:     Option<(TokenPosition, String)>,
+    pub fix:      Option<Fix>,
+}
+
+// the nodes the rules look at, operations in the order of their operators
+// and branches in the order of their `if` and `elif`, so each lines up with
+// its token in the source
+#[derive(Default)]
+struct Nodes {
+    operations:  Vec<(Operand, Option<bool>, Option<bool>)>,
+    branches:    Vec<Option<Value>>,
+    // the name of each definition, where it is, whether it is `mut` and
+    // whether it is an array
+    definitions: Vec<(*const Expression, TokenPosition, bool, bool)>,
+    assigned:    Vec<*const Expression>,
+    passed:      Vec<*const Expression>,
+}
+
+impl Visitor for Nodes {
+    fn visit_operation(&mut self, operation: &Operation) {
+        let literal = |e: &Expression| match *e {
+            Expression::Bool(b) => Some(b),
+            _                   => None,
+        };
+
+        self.visit_expression(&operation.left);
+        self.operations.push((operation.op, literal(&operation.left), literal(&operation.right)));
+        self.visit_expression(&operation.right)
+    }
+
+    fn visit_if(&mut self, branch: &If) {
+        self.branches.push(constant(&branch.condition));
+        walk_if(self, branch)
+    }
+
+    fn visit_definition(&mut self, definition: &Definition) {
+        let (mutable, array) = match definition.t {
+            Some(Type::Mut(Some(ref t))) => (true, matches!(**t, Type::Array(..))),
+            Some(Type::Mut(None))        => (true, false),
+            _                            => (false, false),
+        };
+
+        self.definitions.push((&*definition.name as *const Expression, definition.position, mutable, array));
+        walk_definition(self, definition)
+    }
+
+    fn visit_assignment(&mut self, assignment: &Assignment) {
+        let mut target = &*assignment.left;
+
+        while let Expression::Index(ref index) = *target {
+            target = &index.id
+        }
+
+        self.assigned.push(target as *const Expression);
+        walk_assignment(self, assignment)
+    }
+
+    // an array handed to a function may be changed in there, `array.push`
+    fn visit_call(&mut self, call: &Call) {
+        for arg in &call.args {
+            if let Expression::Identifier(_) = **arg {
+                self.passed.push(&**arg as *const Expression)
+            }
+        }
+
+        walk_call(self, call)
+    }
+}
+
+// the value of an expression made only of literals
+fn constant(expression: &Expression) -> Option<Value> {
+    match *expression {
+        Expression::Number(n)         => Some(Value::Number(n)),
+        Expression::Bool(b)           => Some(Value::Bool(b)),
+        Expression::Str(ref s)        => Some(Value::Str(s.clone())),
+        Expression::Char(c)           => Some(Value::Char(c)),
+        Expression::Operation(ref op) => {
+            let left  = constant(&op.left)?;
+            let right = constant(&op.right)?;
+
+            Value::operate(&op.op, left, right).ok()
+        },
+        _ => None,
+    }
+}
+
+fn any(t: &Type) -> bool {
+    match *t {
+        Type::Any              => true,
+        Type::Mut(Some(ref t)) => any(t),
+        Type::Array(ref t, _)  => any(t),
+        _                      => false,
+    }
+}
+
+struct Linter<'a> {
+    config:    &'a Config,
+    tokens:    Vec<SyntaxToken>,
+    positions: HashMap<(usize, usize), usize>,
+    lints:     Vec<Lint>,
+}
+
+impl<'a> Linter<'a> {
+    fn push(&mut self, id: &str, position: Option<TokenPosition>, message: String, note: Option<(TokenPosition, String)>, fix: Option<Fix>) {
+        let rule  = rule_named(id).unwrap();
+        let level = self.config.level(rule);
+
+        if level != Level::Allow {
+            self.lints.push(Lint { rule, level, position, message, note, fix })
+        }
+    }
+
+    // the significant token starting at `position`
+    fn at(&self, position: TokenPosition) -> Option<usize> {
+        self.positions.get(&(position.line, position.col)).cloned()
+    }
+
+    fn next(&self, i: usize) -> Option<&SyntaxToken> {
+        self.tokens.get(i + 1)
+    }
+
+    fn prev(&self, i: usize) -> Option<&SyntaxToken> {
+        i.checked_sub(1).and_then(|i| self.tokens.get(i))
+    }
+
+    // the tokens of one kind, in the order they appear, `contents` narrowing
+    // them down if given
+    fn all(&self, kind: TokenType, contents: Option<&[&str]>) -> Vec<usize> {
+        (0 .. self.tokens.len())
+            .filter(|&i| self.tokens[i].kind() == kind)
+            .filter(|&i| contents.is_none_or(|contents| contents.contains(&self.tokens[i].content().as_str())))
+            .collect()
+    }
+
+    fn unused(&mut self, ast: &[Statement], resolution: &Resolution) {
+        // what is `pub` is there for the files importing this one
+        let exported = ast.iter()
+            .filter_map(|statement| match *statement {
+                Statement::Definition(ref definition) if definition.public => Some(definition.position),
+                _                                                          => None,
+            })
+            .collect::<Vec<_>>();
+
+        for symbol in &resolution.symbols {
+            let position = match symbol.position {
+                Some(position) if symbol.kind == SymbolKind::Definition => position,
+                _                                                       => continue,
+            };
+
+            if symbol.uses > 0 || symbol.name.starts_with('_') || exported.contains(&position) {
+                continue
+            }
+
+            let fix = self.at(position).map(|i| Fix {
+                message: format!("rename it `_{}` to mark it unused", symbol.name),
+                changes: vec![Change { start: self.tokens[i].offset(), end: self.tokens[i].offset(), replacement: "_".to_string() }],
+            });
+
+            self.push("unused-definition", Some(position), format!("`{}` is never used", symbol.name), None, fix)
+        }
+    }
+
+    fn shadowed(&mut self, resolution: &Resolution) {
+        for &(id, earlier) in &resolution.shadows {
+            let (symbol, earlier) = (&resolution.symbols[id], &resolution.symbols[earlier]);
+
+            let message = format!("`{}` shadows an earlier binding", symbol.name);
+            let note    = earlier.position.map(|p| (p, format!("previous binding of `{}` here", symbol.name)));
+
+            self.push("shadowed-name", symbol.position, message, note, None)
+        }
+    }
+
+    fn comparisons(&mut self, nodes: &Nodes) {
+        let operators = self.all(TokenType::Operator, None);
+
+        // every operator token is one operation, a mismatch leaves the lints
+        // without a place to point at
+        let aligned = operators.len() == nodes.operations.len();
+
+        for (n, &(op, left, right)) in nodes.operations.iter().enumerate() {
+            let (value, first) = match (op, left, right) {
+                (Operand::Equal, _, Some(b)) | (Operand::NEqual, _, Some(b)) => (b, false),
+                (Operand::Equal, Some(b), _) | (Operand::NEqual, Some(b), _) => (b, true),
+                _                                                           => continue,
+            };
+
+            let operator = if aligned { Some(operators[n]) } else { None };
+            let position = operator.map(|i| self.tokens[i].position());
+
+            // `== true` and `!= false` only repeat the value compared
+            let redundant = (op == Operand::Equal) == value;
+
+            let fix = operator.filter(|_| redundant).and_then(|i| {
+                let change = if first {
+                    let literal = self.prev(i).filter(|token| token.kind() == TokenType::BoolLiteral)?;
+                    Change { start: literal.offset(), end: self.next(i)?.offset(), replacement: String::new() }
+                } else {
+                    let literal = self.next(i).filter(|token| token.kind() == TokenType::BoolLiteral)?;
+                    Change { start: self.prev(i)?.end(), end: literal.end(), replacement: String::new() }
+                };
+
+                Some(Fix { message: "use the value itself".to_string(), changes: vec![change] })
+            });
+
+            let message = if redundant {
+                format!("comparison with `{}` is the value itself", value)
+            } else {
+                format!("comparison with `{}`, consider swapping the branches instead", value)
+            };
+
+            self.push("bool-comparison", position, message, None, fix)
+        }
+    }
+
+    fn public_any(&mut self, ast: &[Statement]) {
+        for statement in ast {
+            let definition = match *statement {
+                Statement::Definition(ref definition) if definition.public => definition,
+                _                                                          => continue,
+            };
+
+            let (name, lambda) = match (&*definition.name, definition.right.as_deref()) {
+                (Expression::Identifier(name), Some(Expression::Lambda(lambda))) => (name, lambda),
+                _                                                                => continue,
+            };
+
+            let params   = lambda.params.iter().any(|(t, _)| t.as_ref().is_some_and(any));
+            let returned = lambda.t.as_ref().is_some_and(|t| any(t));
+
+            if params || returned {
+                let message = format!("`{}` has `any` in its signature, callers are not type checked against it", name);
+                self.push("public-any", Some(definition.position), message, None, None)
+            }
+        }
+    }
+
+    fn redundant_mut(&mut self, nodes: &Nodes, resolution: &Resolution) {
+        let ids = |nodes: &[*const Expression]| nodes.iter()
+            .filter_map(|target| resolution.references.get(target))
+            .collect::<HashSet<_>>();
+
+        let (assigned, passed) = (ids(&nodes.assigned), ids(&nodes.passed));
+
+        for &(name, position, mutable, array) in &nodes.definitions {
+            let id = match resolution.declarations.get(&name) {
+                Some(id) if mutable => id,
+                _                   => continue,
+            };
+
+            if assigned.contains(id) || array && passed.contains(id) {
+                continue
+            }
+
+            let fix = self.at(position).and_then(|i| {
+                let colon = self.next(i).filter(|token| token.content() == ":")?;
+                let mutable = self.next(i + 1).filter(|token| token.content() == "mut")?;
+                let after = self.next(i + 2);
+
+                let change = match after {
+                    // `x: mut = 1` is `x := 1`
+                    Some(token) if token.content() == "=" => Change {
+                        start:       self.tokens[i].end(),
+                        end:         token.offset(),
+                        replacement: " :".to_string(),
+                    },
+                    Some(token) if token.kind() != TokenType::EOL => Change {
+                        start:       mutable.offset(),
+                        end:         token.offset(),
+                        replacement: String::new(),
+                    },
+                    _ => Change {
+                        start:       colon.end(),
+                        end:         mutable.end(),
+                        replacement: String::new(),
+                    },
+                };
+
+                Some(Fix { message: "drop the `mut`".to_string(), changes: vec![change] })
+            });
+
+            let name = &resolution.symbols[*id].name;
+
+            self.push("redundant-mut", Some(position), format!("`{}` is declared `mut` but never assigned to", name), None, fix)
+        }
+    }
+
+    fn conditions(&mut self, nodes: &Nodes) {
+        let keywords = self.all(TokenType::Keyword, Some(&["if", "elif"]));
+        let aligned  = keywords.len() == nodes.branches.len();
+
+        for (n, condition) in nodes.branches.iter().enumerate() {
+            let value = match *condition {
+                Some(ref value) => value,
+                None            => continue,
+            };
+
+            let position = if aligned { Some(self.tokens[keywords[n]].position()) } else { None };
+
+            let message = match *value {
+                Value::Bool(b) => format!("condition is always {}", b),
+                ref value      => format!("condition is always {}, which is not a bool", value.repr()),
+            };
+
+            self.push("constant-condition", position, message, None, None)
+        }
+    }
+}
+
+// every lint of the source at the level `config` gives it, allowed ones left
+// out, in the order they appear
+pub fn lint(source: &str, config: &Config) -> ParserResult<Vec<Lint>> {
+    let lexer      = lexer(&mut source.chars());
+    let mut parser = Parser::new(Traveler::new(lexer.collect()));
+
+    let ast = parser.parse()?;
+
+    let mut resolver = Resolver::new();
+    stdlib::declare_names(&mut resolver);
+
+    let resolution = resolver.resolve(&ast);
+
+    let mut nodes = Nodes::default();
+    nodes.visit_block(&ast);
+
+    let tokens = cst(source).tokens().into_iter()
+        .filter(|token| !token.kind().trivia())
+        .collect::<Vec<_>>();
+
+    let positions = tokens.iter().enumerate()
+        .map(|(i, token)| ((token.position().line, token.position().col), i))
+        .collect();
+
+    let mut linter = Linter {
+        config,
+        tokens,
+        positions,
+        lints: Vec::new(),
+    };
+
+    linter.unused(&ast, &resolution);
+    linter.shadowed(&resolution);
+    linter.comparisons(&nodes);
+    linter.public_any(&ast);
+    linter.redundant_mut(&nodes, &resolution);
+    linter.conditions(&nodes);
+
+    let mut lints = linter.lints;
+    lints.sort_by_key(|lint| lint.position.map(|p| (p.line, p.col)));
+
+    Ok(lints)
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use super::*;
+
+#[derive(Debug)]
+pub enum LoadErrorValue {
+    Constant(String),
+}
+
+// an error in some module, `file` naming it unless it is the one being run
+#[derive(Debug)]
+pub struct LoadError {
+    pub value:    LoadErrorValue,
+    pub position: Option<TokenPosition>,
+    pub file:     Option<PathBuf>,
+}
+
+#[allow(dead_code)]
+impl LoadError {
+    pub fn new(value: &str) -> LoadError {
+        LoadError {
+            value:    LoadErrorValue::Constant(value.to_owned()),
+            position: None,
+            file:     None,
+        }
+    }
+
+    pub fn new_pos(position: TokenPosition, value: &str) -> LoadError {
+        LoadError {
+            value:    LoadErrorValue::Constant(value.to_owned()),
+            position: Some(position),
+            file:     None,
+        }
+    }
+
+    // the error as found in `file`, unless it already knows where it is
+    pub fn in_file(self, file: Option<PathBuf>) -> LoadError {
+        match self.file {
+            Some(_) => self,
+            None    => LoadError { file, .. self },
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let LoadErrorValue::Constant(ref s) = self.value;
+
+        if let Some(ref file) = self.file {
+            write!(f, "{}: ", file.display())?
+        }
+
+        match self.position {
+            Some(p) => write!(f, "{}: {}", p, s),
+            None    => write!(f, "{}", s),
+        }
+    }
+}
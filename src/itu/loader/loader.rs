@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::*;
+
+// a module loaded from a file, with the `pub` definitions it exports and
+// their signatures, `value` only there when the loader runs modules
+pub struct Unit {
+    pub name:    Rc<String>,
+    pub value:   Option<Value>,
+    pub members: Vec<(Rc<String>, String)>,
+}
+
+// finds the files behind `import` statements in the search roots and loads
+// each once, `a/b` being `a/b.itu` under the first root that has it. an
+// import starting with the name of a package is looked for in its sources
+// instead, and what a package's modules import in those first
+pub struct Loader {
+    search:   Vec<PathBuf>,
+    packages: Vec<(Rc<String>, Vec<PathBuf>)>,
+    run:      bool,
+    cache:    HashMap<PathBuf, Rc<Unit>>,
+    loading:  Vec<(PathBuf, String)>,
+}
+
+#[allow(dead_code)]
+impl Loader {
+    pub fn new(search: Vec<PathBuf>, run: bool) -> Loader {
+        Loader {
+            search,
+            packages: Vec::new(),
+            run,
+            cache:    HashMap::new(),
+            loading:  Vec::new(),
+        }
+    }
+
+    pub fn with_packages(self, packages: Vec<(Rc<String>, Vec<PathBuf>)>) -> Loader {
+        Loader { packages, .. self }
+    }
+
+    // the units the program in `file` imports, in the order it imports them
+    pub fn imports(&mut self, ast: &[Statement], file: Option<&Path>) -> LoadResult<Vec<Rc<Unit>>> {
+        // the program itself is where a cycle back to it starts
+        let root = file.and_then(|file| {
+            let name = file.file_stem()?.to_string_lossy().to_string();
+            Some((fs::canonicalize(file).ok()?, name))
+        });
+
+        let pushed = root.map(|root| self.loading.push(root)).is_some();
+        let units  = self.units(ast, &[]);
+
+        if pushed {
+            self.loading.pop();
+        }
+
+        units
+    }
+
+    // `roots` are where the importing module's package keeps its modules
+    fn units(&mut self, ast: &[Statement], roots: &[PathBuf]) -> LoadResult<Vec<Rc<Unit>>> {
+        let mut units = Vec::new();
+
+        for statement in ast {
+            if let Statement::Import(ref import) = *statement {
+                units.push(self.import(import, roots)?)
+            }
+        }
+
+        Ok(units)
+    }
+
+    fn import(&mut self, import: &Import, roots: &[PathBuf]) -> LoadResult<Rc<Unit>> {
+        let name = import.path.iter().map(|segment| segment.as_str()).collect::<Vec<_>>().join("/");
+
+        let (path, roots) = match self.find(&import.path, roots) {
+            Some(path) => path,
+            None       => return Err(LoadError::new_pos(import.position, &format!("no module {} found", name))),
+        };
+
+        if let Some(unit) = self.cache.get(&path) {
+            return Ok(unit.clone())
+        }
+
+        if let Some(start) = self.loading.iter().position(|(loading, _)| *loading == path) {
+            let mut cycle = self.loading[start ..].iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>();
+            cycle.push(&name);
+
+            return Err(LoadError::new_pos(import.position, &format!("import cycle: {}", cycle.join(" -> "))))
+        }
+
+        self.loading.push((path.clone(), name));
+        let unit = self.load(import.name().clone(), &path, &roots);
+        self.loading.pop();
+
+        let unit = Rc::new(unit.map_err(|err| err.in_file(Some(path.clone())))?);
+        self.cache.insert(path, unit.clone());
+
+        Ok(unit)
+    }
+
+    // the file an import names and the roots of the package it is in, the
+    // search roots being looked at after those of the package
+    fn find(&self, segments: &[Rc<String>], roots: &[PathBuf]) -> Option<(PathBuf, Vec<PathBuf>)> {
+        let file = |segments: &[Rc<String>]| {
+            let name = segments.iter().map(|segment| segment.as_str()).collect::<Vec<_>>().join("/");
+            PathBuf::from(format!("{}.itu", name))
+        };
+
+        let package = self.packages.iter().find(|(name, _)| segments.len() > 1 && *name == segments[0]);
+
+        let (relative, roots) = match package {
+            Some((_, sources)) => (file(&segments[1 ..]), sources.clone()),
+            None               => (file(segments), roots.to_vec()),
+        };
+
+        let path = roots.iter().chain(self.search.iter())
+            .map(|root| root.join(&relative))
+            .find(|path| path.is_file())
+            .and_then(|path| fs::canonicalize(path).ok())?;
+
+        Some((path, roots))
+    }
+
+    fn load(&mut self, name: Rc<String>, path: &Path, roots: &[PathBuf]) -> LoadResult<Unit> {
+        let source = fs::read_to_string(path)
+            .map_err(|why| LoadError::new(&format!("unable to read {}: {}", path.display(), why)))?;
+
+        let lexer      = lexer(&mut source.chars());
+        let mut parser = Parser::new(Traveler::new(lexer.collect()));
+
+        let ast = parser.parse().map_err(|err| {
+            let ParserErrorValue::Constant(ref value) = err.value;
+            LoadError { position: err.position, .. LoadError::new(value) }
+        })?;
+
+        let units = self.units(&ast, roots)?;
+
+        let mut inferer = Inferer::new();
+        stdlib::declare(&mut inferer);
+        declare(&units, &mut inferer);
+
+        let types = inferer.infer(&ast).map_err(|err| {
+            let SemanticErrorValue::Constant(ref value) = err.value;
+            LoadError { position: err.position, .. LoadError::new(value) }
+        })?;
+
+        let public = ast.iter()
+            .filter_map(|statement| match *statement {
+                Statement::Definition(ref definition) if definition.public => Some(definition),
+                _                                                          => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut members = Vec::new();
+
+        for definition in &public {
+            let name = match *definition.name {
+                Expression::Identifier(ref name) => name.clone(),
+                _                                => continue,
+            };
+
+            let scheme = match types.iter().rev().find(|(defined, _)| *defined == name) {
+                Some((_, scheme)) => scheme.closed(),
+                None              => continue,
+            };
+
+            // modules have no signature to be written in
+            if let Ty::Module(_) = scheme.ty {
+                return Err(LoadError::new_pos(definition.position, &format!("{} is a module, import it instead of exporting it", name)))
+            }
+
+            members.push((name, scheme.to_string()))
+        }
+
+        let value = if self.run {
+            let mut evaluator = Evaluator::new();
+            stdlib::register(&evaluator);
+            register(&units, &evaluator);
+
+            evaluator.run(&ast).map_err(|err| {
+                let RuntimeErrorValue::Constant(ref value) = err.value;
+                LoadError { position: err.position, .. LoadError::new(value) }
+            })?;
+
+            let members = members.iter()
+                .filter_map(|(member, _)| evaluator.globals.get(member).map(|value| (member.clone(), value)))
+                .collect::<HashMap<_, _>>();
+
+            Some(Value::Module(Rc::new(Module { name: name.clone(), members })))
+        } else {
+            None
+        };
+
+        Ok(Unit {
+            name,
+            value,
+            members,
+        })
+    }
+}
+
+// the directories imports are looked for in, that of the program in `file`
+// and then those of ITU_PATH
+pub fn search(file: &Path) -> Vec<PathBuf> {
+    let mut search = vec![file.parent().map_or(PathBuf::from("."), Path::to_path_buf)];
+    search.extend(paths());
+
+    search
+}
+
+pub fn paths() -> Vec<PathBuf> {
+    env::var_os("ITU_PATH").map_or(Vec::new(), |paths| env::split_paths(&paths).collect())
+}
+
+// binds every unit as a module global of the evaluator
+pub fn register(units: &[Rc<Unit>], evaluator: &Evaluator) {
+    for unit in units {
+        if let Some(ref value) = unit.value {
+            evaluator.globals.define(unit.name.clone(), value.clone())
+        }
+    }
+}
+
+// tells the type checker about every unit and the types of what it exports
+pub fn declare(units: &[Rc<Unit>], inferer: &mut Inferer) {
+    for unit in units {
+        inferer.define(&unit.name, Scheme::mono(Ty::Module(unit.name.clone())));
+
+        for (member, signature) in &unit.members {
+            let scheme = inferer.signature(signature);
+            inferer.define(&format!("{}.{}", unit.name, member), scheme)
+        }
+    }
+}
+
+pub fn declare_names(units: &[Rc<Unit>], resolver: &mut Resolver) {
+    for unit in units {
+        resolver.define(&unit.name)
+    }
+}
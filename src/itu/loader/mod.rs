@@ -0,0 +1,9 @@
+pub mod error;
+pub mod loader;
+
+pub use super::*;
+
+pub use self::error::*;
+pub use self::loader::*;
+
+pub type LoadResult<T> = Result<T, LoadError>;
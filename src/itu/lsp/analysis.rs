@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub position: Option<TokenPosition>,
+    pub message:  String,
+    pub note:     Option<(TokenPosition, String)>,
+}
+
+// a top-level definition as listed in the editor's outline, `end` being
+// the last character of its last line
+#[derive(Debug, Clone)]
+pub struct Outline {
+    pub name:     Rc<String>,
+    pub position: TokenPosition,
+    pub end:      TokenPosition,
+    pub function: bool,
+    pub detail:   Option<String>,
+}
+
+// an identifier as written and what name resolution made of it
+#[derive(Debug, Clone)]
+pub struct Occurrence {
+    pub name:     Rc<String>,
+    pub position: TokenPosition,
+    pub symbol:   Option<usize>,
+    pub hover:    Option<String>,
+}
+
+// everything the server answers from, rebuilt whenever the document changes
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub tokens:       Vec<Token>,
+    pub diagnostics:  Vec<Diagnostic>,
+    pub outline:      Vec<Outline>,
+    pub occurrences:  Vec<Occurrence>,
+    pub symbols:      Vec<Symbol>,
+    pub declarations: HashMap<usize, TokenPosition>,
+}
+
+#[allow(dead_code)]
+impl Analysis {
+    // `path` is the file the source is in, for finding what it imports
+    pub fn new(syntax: &Incremental, path: Option<&Path>) -> Analysis {
+        let mut analysis = Analysis::default();
+
+        let source = syntax.source();
+        let tokens = syntax.tokens();
+
+        if let Some(position) = syntax.stopped() {
+            let found = source.lines().nth(position.line - 1).and_then(|line| line.chars().nth(position.col));
+
+            let message = match found {
+                Some(c) => format!("unexpected character `{}`", c),
+                None    => "unexpected character".to_string(),
+            };
+
+            analysis.report(Severity::Error, Some(position), message, None)
+        }
+
+        analysis.tokens = tokens.iter().chain(syntax.comments().iter()).cloned().collect();
+        analysis.tokens.sort_by_key(|token| (token.position.line, token.position.col));
+
+        if let Some(err) = syntax.error() {
+            let ParserErrorValue::Constant(ref value) = err.value;
+            analysis.report(Severity::Error, err.position, value.clone(), None);
+
+            return analysis
+        }
+
+        let ast = syntax.ast();
+
+        // modules are only typed, running them could write to the protocol's stdout
+        let units = match path {
+            Some(path) => match project::loader(path, false) {
+                Ok(mut loader) => loader.imports(&ast, Some(path)),
+                Err(why)       => Err(loader::LoadError::new(&why)),
+            },
+            None       => Ok(Vec::new()),
+        };
+
+        let units = match units {
+            Ok(units) => units,
+            // a module's own errors are told with its file, at the top of this one
+            Err(err) => {
+                let loader::LoadErrorValue::Constant(ref value) = err.value;
+
+                match err.file {
+                    Some(_) => analysis.report(Severity::Error, None, err.to_string(), None),
+                    None    => analysis.report(Severity::Error, err.position, value.clone(), None),
+                }
+
+                return analysis
+            },
+        };
+
+        let mut resolver = Resolver::new();
+        stdlib::declare_names(&mut resolver);
+        loader::declare_names(&units, &mut resolver);
+
+        let resolution = resolver.resolve(&ast);
+
+        for warning in &resolution.warnings {
+            analysis.semantic(Severity::Warning, warning)
+        }
+
+        for err in &resolution.errors {
+            analysis.semantic(Severity::Error, err)
+        }
+
+        let mut inferer = Inferer::new();
+        stdlib::declare(&mut inferer);
+        loader::declare(&units, &mut inferer);
+
+        // what types is typed for hovers even when some names did not
+        // resolve, the errors that follow from those would only repeat them
+        let (types, errors) = inferer.recover(&ast);
+        let schemes         = types.into_iter().collect::<HashMap<_, _>>();
+
+        if resolution.errors.is_empty() {
+            if let Err(err) = MutabilityChecker::new().check(&ast) {
+                analysis.semantic(Severity::Error, &err)
+            }
+
+            for err in &errors {
+                analysis.semantic(Severity::Error, err)
+            }
+        }
+
+        let mut names = Names {
+            resolution: &resolution,
+            inferer:    &inferer,
+            schemes:    &schemes,
+            depth:      0,
+            params:     Vec::new(),
+            found:      Vec::new(),
+            declared:   HashMap::new(),
+        };
+
+        for statement in &ast {
+            names.visit_statement(statement)
+        }
+
+        analysis.occurrences(tokens, names.found, &names.declared);
+        analysis.symbols = resolution.symbols.clone();
+        analysis.outline(source, &ast, &syntax.starts());
+
+        analysis
+    }
+
+    fn report(&mut self, severity: Severity, position: Option<TokenPosition>, message: String, note: Option<(TokenPosition, String)>) {
+        self.diagnostics.push(Diagnostic { severity, position, message, note })
+    }
+
+    fn semantic(&mut self, severity: Severity, err: &SemanticError) {
+        let SemanticErrorValue::Constant(ref value) = err.value;
+        self.report(severity, err.position, value.clone(), err.note.clone())
+    }
+
+    // identifier tokens and the identifiers of the tree come in the same
+    // order, name by name, which is what pins a position to each node
+    fn occurrences(&mut self, tokens: &[Token], found: Vec<Found>, declared: &HashMap<usize, String>) {
+        let mut positions = HashMap::new();
+
+        for token in tokens.iter().rev().filter(|token| token.token_type == TokenType::Identifier) {
+            positions.entry(token.content().clone()).or_insert_with(Vec::new).push(token.position)
+        }
+
+        for Found { name, symbol, binds, inferred } in found {
+            let position = match positions.get_mut(name.as_str()).and_then(|positions| positions.pop()) {
+                Some(position) => position,
+                None           => continue,
+            };
+
+            if let (true, Some(id)) = (binds, symbol) {
+                self.declarations.entry(id).or_insert(position);
+            }
+
+            let t     = symbol.and_then(|id| declared.get(&id)).cloned().or(inferred);
+            let hover = t.map(|t| format!("{}: {}", name, t));
+
+            self.occurrences.push(Occurrence { name, position, symbol, hover })
+        }
+
+        self.occurrences.sort_by_key(|occurrence| (occurrence.position.line, occurrence.position.col))
+    }
+
+    fn outline(&mut self, source: &str, ast: &[Statement], starts: &[TokenPosition]) {
+        let lines = source.lines().collect::<Vec<_>>();
+
+        // only top-level statements start at the left edge
+        let mut starts = starts.iter().filter(|start| start.col == 0).map(|start| start.line).collect::<Vec<_>>();
+        starts.push(lines.len() + 1);
+
+        for (statement, next) in ast.iter().zip(starts.iter().skip(1)) {
+            let definition = match *statement {
+                Statement::Definition(ref definition) => definition,
+                _                                     => continue,
+            };
+
+            let name = match *definition.name {
+                Expression::Identifier(ref name) => name.clone(),
+                _                                => continue,
+            };
+
+            let found = self.occurrences.iter().find(|occurrence| {
+                occurrence.name == name && occurrence.symbol.is_some_and(|id| self.declarations.get(&id) == Some(&occurrence.position))
+            });
+
+            let (position, detail) = match found {
+                Some(occurrence) => (occurrence.position, occurrence.hover.clone()),
+                None             => (definition.position, None),
+            };
+
+            // trailing blank lines and comments belong to whatever follows
+            let mut last = next - 1;
+
+            while last > position.line && lines.get(last - 1).is_none_or(|line| line.trim().is_empty() || line.trim_start().starts_with("--")) {
+                last -= 1
+            }
+
+            let end = TokenPosition::new(last, lines.get(last - 1).map_or(0, |line| line.chars().count()));
+
+            let function = matches!(definition.right.as_deref(), Some(Expression::Lambda(_)));
+
+            self.outline.push(Outline { name, position, end, function, detail })
+        }
+    }
+
+    pub fn occurrence(&self, line: usize, col: usize) -> Option<&Occurrence> {
+        self.occurrences.iter().find(|occurrence| {
+            occurrence.position.line == line &&
+            occurrence.position.col <= col && col <= occurrence.position.col + occurrence.name.chars().count()
+        })
+    }
+
+    // where the name under the cursor was bound, builtins have no place to go
+    pub fn definition(&self, line: usize, col: usize) -> Option<TokenPosition> {
+        let id = self.occurrence(line, col)?.symbol?;
+        self.declarations.get(&id).cloned().or(self.symbols[id].position)
+    }
+
+    pub fn hover(&self, line: usize, col: usize) -> Option<&String> {
+        self.occurrence(line, col)?.hover.as_ref()
+    }
+
+    pub fn kind(&self, token: &Token) -> Option<SymbolKind> {
+        let occurrence = self.occurrences.binary_search_by_key(&(token.position.line, token.position.col), |occurrence| {
+            (occurrence.position.line, occurrence.position.col)
+        });
+
+        occurrence.ok()
+            .and_then(|i| self.occurrences[i].symbol)
+            .map(|id| self.symbols[id].kind)
+    }
+}
+
+struct Found {
+    name:     Rc<String>,
+    symbol:   Option<usize>,
+    binds:    bool,
+    inferred: Option<String>,
+}
+
+// every identifier in the order it is written, be it a name bound, one
+// referred to, a field or a type
+struct Names<'a> {
+    resolution: &'a Resolution,
+    inferer:    &'a Inferer,
+    schemes:    &'a HashMap<Rc<String>, Scheme>,
+    depth:      usize,
+    // the types of the parameters of the lambda being visited, last first
+    params:     Vec<Ty>,
+    found:      Vec<Found>,
+    declared:   HashMap<usize, String>,
+}
+
+impl<'a> Names<'a> {
+    fn push(&mut self, name: &Rc<String>, symbol: Option<usize>, binds: bool, inferred: Option<String>) {
+        self.found.push(Found { name: name.clone(), symbol, binds, inferred })
+    }
+
+    // type variables read as letters, the way they do in a function's scheme
+    fn typed(&self, expression: &Expression) -> Option<String> {
+        self.inferer.type_of(expression).map(|t| Scheme::mono(t).to_string())
+    }
+
+    fn declare(&mut self, symbol: Option<usize>, t: Option<&Type>) {
+        match (symbol, t) {
+            // a bare `mut` says less than inference does
+            (_, Some(&Type::Mut(None))) | (_, None) | (None, _) => (),
+            (Some(id), Some(t))                                  => {
+                self.declared.insert(id, t.to_string());
+            },
+        }
+    }
+}
+
+impl<'a> Visitor for Names<'a> {
+    fn visit_definition(&mut self, definition: &Definition) {
+        if let Expression::Identifier(ref name) = *definition.name {
+            let symbol = self.resolution.declarations.get(&(&*definition.name as *const Expression)).cloned();
+
+            let inferred = match self.schemes.get(name) {
+                Some(scheme) if self.depth == 0 => Some(scheme.to_string()),
+                _                               => definition.right.as_ref().and_then(|right| self.typed(right)),
+            };
+
+            self.declare(symbol, definition.t.as_ref());
+            self.push(name, symbol, true, inferred)
+        }
+
+        walk_definition(self, definition)
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match *expression {
+            Expression::Identifier(ref name) => {
+                let key    = expression as *const Expression;
+                let symbol = self.resolution.references.get(&key).or_else(|| self.resolution.declarations.get(&key)).cloned();
+
+                let inferred = self.typed(expression);
+
+                self.push(name, symbol, false, inferred)
+            },
+
+            // parameters are only typed as part of their lambda
+            Expression::Lambda(_) => {
+                self.params = match self.inferer.type_of(expression) {
+                    Some(Ty::Function(params, _)) => params.into_iter().rev().collect(),
+                    _                             => Vec::new(),
+                };
+
+                walk_expression(self, expression)
+            },
+
+            _ => walk_expression(self, expression),
+        }
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) {
+        self.depth += 1;
+        walk_block(self, statements);
+        self.depth -= 1
+    }
+
+    fn visit_param(&mut self, t: Option<&Type>, name: &Rc<String>) {
+        let symbol   = self.resolution.parameters.get(&(name as *const Rc<String>)).cloned();
+        let inferred = self.params.pop().map(|t| Scheme::mono(t).to_string());
+
+        self.declare(symbol, t);
+        self.push(name, symbol, true, inferred);
+
+        if let Some(t) = t {
+            self.visit_type(t)
+        }
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        match *pattern {
+            Pattern::Binding(ref name) => {
+                let symbol = self.resolution.parameters.get(&(name as *const Rc<String>)).cloned();
+                self.push(name, symbol, true, None)
+            },
+
+            // `_` is an identifier token too
+            Pattern::Wildcard => self.push(&Rc::new("_".to_string()), None, false, None),
+
+            _ => walk_pattern(self, pattern),
+        }
+    }
+
+    fn visit_field(&mut self, field: &Field) {
+        walk_field(self, field);
+        self.push(&field.name, None, false, None)
+    }
+
+    fn visit_type(&mut self, t: &Type) {
+        match *t {
+            Type::Identifier(ref name) => self.push(name, None, false, None),
+            _                          => walk_type(self, t),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod analysis;
+pub mod server;
+
+pub use super::*;
+
+pub use self::analysis::*;
+pub use self::server::*;
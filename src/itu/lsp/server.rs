@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use serde_json::{self, Value};
+
+use super::*;
+
+// the order here is the legend sent on initialize, tokens refer to it by index
+const TOKEN_TYPES: [&str; 9] = [
+    "keyword",
+    "type",
+    "variable",
+    "parameter",
+    "function",
+    "number",
+    "string",
+    "operator",
+    "comment",
+];
+
+const PARSE_ERROR:      i64 = -32700;
+const INVALID_REQUEST:  i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// the text is kept apart from its syntax, which an edit updates in place
+// once the document has been parsed whole
+struct Document {
+    source:   String,
+    path:     Option<PathBuf>,
+    syntax:   Option<Incremental>,
+    analysis: Analysis,
+}
+
+impl Document {
+    // `uri` gives the file imports are found from, when it names one
+    fn new(source: String, uri: &str) -> Document {
+        let mut document = Document {
+            source,
+            path:     uri.strip_prefix("file://").map(PathBuf::from),
+            syntax:   None,
+            analysis: Analysis::default(),
+        };
+
+        document.update(None);
+        document
+    }
+
+    // applies an edit, `None` having the source taken as it is
+    fn update(&mut self, edit: Option<Edit>) {
+        if let Some(ref edit) = edit {
+            edit.apply(&mut self.source)
+        }
+
+        let source = &self.source;
+        let path   = self.path.as_deref();
+        let syntax = self.syntax.take();
+
+        let syntax = match (syntax, edit) {
+            (Some(mut syntax), Some(edit)) => {
+                syntax.edit(&edit);
+                syntax
+            },
+
+            _ => Incremental::new(source),
+        };
+
+        self.analysis = Analysis::new(&syntax, path);
+        self.syntax   = Some(syntax);
+    }
+
+    // a change carries a range unless it is the whole document again
+    fn change(&mut self, change: &Value) -> Option<()> {
+        let text = change["text"].as_str()?.to_string();
+
+        match change.get("range") {
+            Some(range) => {
+                let start = self.point(&range["start"])?;
+                let end   = self.point(&range["end"])?;
+
+                self.update(Some(Edit { start, end, text }))
+            },
+
+            None => {
+                self.source = text;
+                self.update(None)
+            },
+        }
+
+        Some(())
+    }
+
+    fn line(&self, line: usize) -> &str {
+        self.source.lines().nth(line - 1).unwrap_or("")
+    }
+
+    // lsp counts lines from 0 and columns in utf-16 code units
+    fn position(&self, position: TokenPosition) -> Value {
+        let character = self.line(position.line).chars().take(position.col).map(char::len_utf16).sum::<usize>();
+
+        json!({ "line": position.line - 1, "character": character })
+    }
+
+    fn range(&self, position: TokenPosition, width: usize) -> Value {
+        json!({
+            "start": self.position(position),
+            "end":   self.position(TokenPosition::new(position.line, position.col + width)),
+        })
+    }
+
+    fn point(&self, position: &Value) -> Option<TokenPosition> {
+        let line      = position["line"].as_u64()? as usize + 1;
+        let character = position["character"].as_u64()? as usize;
+
+        let mut units = 0;
+        let col = self.line(line).chars().take_while(|c| {
+            units += c.len_utf16();
+            units <= character
+        }).count();
+
+        Some(TokenPosition::new(line, col))
+    }
+
+    fn cursor(&self, params: &Value) -> Option<(usize, usize)> {
+        let point = self.point(&params["position"])?;
+        Some((point.line, point.col))
+    }
+
+    // how many characters the token takes up in the source, literals
+    // having lost their quotes and escapes on the way
+    fn width(&self, token: &Token) -> usize {
+        match token.token_type {
+            TokenType::StringLiteral | TokenType::CharLiteral => {
+                let mut rest = self.line(token.position.line).chars().skip(token.position.col).peekable();
+
+                let mut width = 0;
+
+                if rest.peek() == Some(&'r') {
+                    rest.next();
+                    width += 1
+                }
+
+                let delimiter = rest.next();
+                let mut escaped = false;
+
+                width += 1;
+
+                for c in rest {
+                    width += 1;
+
+                    match c {
+                        _ if escaped                 => escaped = false,
+                        '\\'                         => escaped = true,
+                        c if Some(c) == delimiter    => break,
+                        _                            => (),
+                    }
+                }
+
+                width
+            },
+
+            _ => token.content().chars().count(),
+        }
+    }
+
+    fn width_at(&self, position: TokenPosition) -> usize {
+        self.analysis.tokens.iter()
+            .find(|token| token.position == position)
+            .map_or(1, |token| self.width(token))
+    }
+
+    fn diagnostics(&self, uri: &str) -> Value {
+        let diagnostics = self.analysis.diagnostics.iter().map(|diagnostic| {
+            let position = diagnostic.position.unwrap_or_default();
+
+            let mut value = json!({
+                "range":    self.range(position, self.width_at(position)),
+                "severity": match diagnostic.severity {
+                    Severity::Error   => 1,
+                    Severity::Warning => 2,
+                },
+                "source":   "itu",
+                "message":  diagnostic.message,
+            });
+
+            if let Some((position, ref note)) = diagnostic.note {
+                value["relatedInformation"] = json!([{
+                    "location": { "uri": uri, "range": self.range(position, self.width_at(position)) },
+                    "message":  note,
+                }]);
+            }
+
+            value
+        }).collect::<Vec<_>>();
+
+        json!({ "uri": uri, "diagnostics": diagnostics })
+    }
+
+    fn symbols(&self) -> Value {
+        let symbols = self.analysis.outline.iter().map(|outline| {
+            let selection = self.range(outline.position, outline.name.chars().count());
+
+            json!({
+                "name":           *outline.name,
+                "detail":         outline.detail,
+                "kind":           if outline.function { 12 } else { 13 },
+                "range":          { "start": selection["start"], "end": self.position(outline.end) },
+                "selectionRange": selection,
+            })
+        }).collect::<Vec<_>>();
+
+        Value::Array(symbols)
+    }
+
+    fn tokens(&self) -> Value {
+        let mut data = Vec::new();
+        let mut last = (0, 0);
+
+        for token in &self.analysis.tokens {
+            let kind = match token.token_type {
+                TokenType::Keyword | TokenType::BoolLiteral   => "keyword",
+                TokenType::Type                               => "type",
+                TokenType::IntLiteral | TokenType::FloatLiteral => "number",
+                TokenType::StringLiteral | TokenType::CharLiteral => "string",
+                TokenType::Operator                           => "operator",
+                TokenType::Comment | TokenType::DocComment    => "comment",
+                TokenType::Identifier                         => match self.analysis.kind(token) {
+                    Some(SymbolKind::Parameter) => "parameter",
+                    Some(SymbolKind::Builtin)   => "function",
+                    _                           => "variable",
+                },
+                _ => continue,
+            };
+
+            let start = self.position(token.position);
+            let end   = self.position(TokenPosition::new(token.position.line, token.position.col + self.width(token)));
+
+            let line      = start["line"].as_u64().unwrap();
+            let character = start["character"].as_u64().unwrap();
+
+            let (delta_line, delta_start) = if line == last.0 {
+                (0, character - last.1)
+            } else {
+                (line - last.0, character)
+            };
+
+            data.extend_from_slice(&[
+                delta_line,
+                delta_start,
+                end["character"].as_u64().unwrap() - character,
+                TOKEN_TYPES.iter().position(|t| *t == kind).unwrap() as u64,
+                0,
+            ]);
+
+            last = (line, character)
+        }
+
+        json!({ "data": data })
+    }
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown:  bool,
+}
+
+#[allow(dead_code)]
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            documents: HashMap::new(),
+            shutdown:  false,
+        }
+    }
+
+    // answers messages until the client says `exit`, the result being
+    // whether it asked for a shutdown first
+    pub fn serve(&mut self, input: &mut dyn BufRead, output: &mut dyn Write) -> io::Result<bool> {
+        while let Some(body) = read(input)? {
+            let message = match serde_json::from_slice::<Value>(&body) {
+                Ok(message) => message,
+                Err(why)    => {
+                    write(output, &error(Value::Null, PARSE_ERROR, &why.to_string()))?;
+                    continue
+                },
+            };
+
+            let method = message["method"].as_str().unwrap_or("").to_string();
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            if method == "exit" {
+                return Ok(self.shutdown)
+            }
+
+            match message.get("id").cloned() {
+                Some(id) => {
+                    let response = if self.shutdown {
+                        error(id, INVALID_REQUEST, "the server is shutting down")
+                    } else {
+                        match self.request(&method, &params) {
+                            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                            None         => error(id, METHOD_NOT_FOUND, &format!("unknown method `{}`", method)),
+                        }
+                    };
+
+                    write(output, &response)?
+                },
+
+                None => {
+                    if let Some(notification) = self.notify(&method, &params) {
+                        write(output, &notification)?
+                    }
+                },
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let document = params.pointer("/textDocument/uri")
+            .and_then(|uri| uri.as_str())
+            .and_then(|uri| self.documents.get(uri));
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync":       2,
+                    "hoverProvider":          true,
+                    "definitionProvider":     true,
+                    "documentSymbolProvider": true,
+                    "semanticTokensProvider": {
+                        "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                        "full":   true,
+                    },
+                },
+                "serverInfo": { "name": "itu" },
+            }),
+
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            },
+
+            "textDocument/hover" => document.and_then(|document| {
+                let (line, col) = document.cursor(params)?;
+                let occurrence  = document.analysis.occurrence(line, col)?;
+                let hover       = occurrence.hover.as_ref()?;
+
+                Some(json!({
+                    "contents": { "kind": "markdown", "value": format!("```itu\n{}\n```", hover) },
+                    "range":    document.range(occurrence.position, occurrence.name.chars().count()),
+                }))
+            }).unwrap_or(Value::Null),
+
+            "textDocument/definition" => document.and_then(|document| {
+                let (line, col) = document.cursor(params)?;
+                let target      = document.analysis.definition(line, col)?;
+                let width       = document.analysis.occurrence(line, col)?.name.chars().count();
+
+                Some(json!({ "uri": params["textDocument"]["uri"], "range": document.range(target, width) }))
+            }).unwrap_or(Value::Null),
+
+            "textDocument/documentSymbol" => document.map_or(Value::Null, |document| document.symbols()),
+
+            "textDocument/semanticTokens/full" => document.map_or(Value::Null, |document| document.tokens()),
+
+            _ => return None,
+        };
+
+        Some(result)
+    }
+
+    // open and changed documents are analysed right away, the diagnostics
+    // going back as a notification of their own
+    fn notify(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let uri = params.pointer("/textDocument/uri")?.as_str()?.to_string();
+
+        let diagnostics = match method {
+            "textDocument/didOpen" => {
+                let text = params.pointer("/textDocument/text")?.as_str()?;
+                self.documents.insert(uri.clone(), Document::new(text.to_string(), &uri));
+
+                self.documents[&uri].diagnostics(&uri)
+            },
+
+            // changes come in order, each against the text the last one left
+            "textDocument/didChange" => {
+                let document = self.documents.get_mut(&uri)?;
+
+                for change in params["contentChanges"].as_array()? {
+                    document.change(change)?
+                }
+
+                document.diagnostics(&uri)
+            },
+
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                json!({ "uri": uri, "diagnostics": [] })
+            },
+
+            _ => return None,
+        };
+
+        Some(json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": diagnostics }))
+    }
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+// a message is a block of headers, a blank line and as many bytes of json
+// as `Content-Length` said
+fn read(input: &mut dyn BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None)
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok()
+            }
+        }
+    }
+
+    let length = match length {
+        Some(length) => length,
+        None         => return Err(io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")),
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+
+    Ok(Some(body))
+}
+
+fn write(output: &mut dyn Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
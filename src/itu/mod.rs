@@ -1,3 +1,18 @@
 pub mod syntax;
+pub mod semantic;
+pub mod interpreter;
+pub mod vm;
+pub mod codegen;
+pub mod format;
+pub mod doc;
+pub mod testing;
+pub mod lint;
+pub mod loader;
+pub mod project;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod stdlib;
 
 pub use self::syntax::*;
+pub use self::semantic::*;
+pub use self::interpreter::*;
@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+
+use super::*;
+
+// the name of the file a package is described in, at its root
+pub const MANIFEST: &str = "itu.toml";
+
+// where a dependency lives, a directory of its own or one kept in the
+// package's `vendor` directory under the dependency's name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Path(PathBuf),
+    Vendored,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name:   String,
+    pub source: Source,
+}
+
+// a package as its manifest describes it, paths relative to its root,
+//
+//     [package]
+//     name    = "shapes"
+//     version = "0.1.0"
+//     entry   = "src/main.itu"
+//     sources = ["src"]
+//
+//     [dependencies]
+//     geo  = { path = "../geo" }
+//     json = { vendored = true }
+//
+// with everything but the name optional
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub name:         String,
+    pub version:      Option<String>,
+    pub entry:        PathBuf,
+    pub sources:      Vec<PathBuf>,
+    pub dependencies: Vec<Dependency>,
+}
+
+#[allow(dead_code)]
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Manifest, String> {
+        let document = toml::parse(text)?;
+
+        let tables = match document {
+            toml::Item::Table(tables) => tables,
+            _                         => unreachable!(),
+        };
+
+        let mut package      = None;
+        let mut dependencies = Vec::new();
+
+        for (name, table) in &tables {
+            if !matches!(*table, toml::Item::Table(_)) {
+                return Err(format!("`{}` is outside of any table", name))
+            }
+
+            match name.as_str() {
+                "package"      => package = Some(Self::package(table)?),
+                "dependencies" => dependencies = Self::dependencies(table)?,
+                name           => return Err(format!("unknown table [{}]", name)),
+            }
+        }
+
+        let (name, version, entry, sources) = package.ok_or_else(|| "missing [package] table".to_string())?;
+
+        Ok(Manifest {
+            name,
+            version,
+            entry,
+            sources,
+            dependencies,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn package(table: &toml::Item) -> Result<(String, Option<String>, PathBuf, Vec<PathBuf>), String> {
+        let mut name    = None;
+        let mut version = None;
+        let mut entry   = PathBuf::from("src/main.itu");
+        let mut sources = vec![PathBuf::from("src")];
+
+        let string = |key: &str, item: &toml::Item| item.as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("[package] {} must be a string, found {}", key, item.type_name()));
+
+        if let toml::Item::Table(ref entries) = *table {
+            for (key, item) in entries {
+                match key.as_str() {
+                    "name"    => name    = Some(string(key, item)?),
+                    "version" => version = Some(string(key, item)?),
+                    "entry"   => entry   = PathBuf::from(string(key, item)?),
+                    "sources" => sources = match *item {
+                        toml::Item::Array(ref items) => items.iter()
+                            .map(|item| string(key, item).map(PathBuf::from))
+                            .collect::<Result<_, _>>()?,
+                        ref item => return Err(format!("[package] sources must be an array, found {}", item.type_name())),
+                    },
+                    key       => return Err(format!("unknown key `{}` in [package]", key)),
+                }
+            }
+        }
+
+        let name = name.ok_or_else(|| "[package] has no name".to_string())?;
+        check_name(&name)?;
+
+        Ok((name, version, entry, sources))
+    }
+
+    fn dependencies(table: &toml::Item) -> Result<Vec<Dependency>, String> {
+        let mut dependencies = Vec::new();
+
+        if let toml::Item::Table(ref entries) = *table {
+            for (name, item) in entries {
+                check_name(name)?;
+
+                let source = match (item.get("path"), item.get("vendored")) {
+                    (Some(toml::Item::Str(path)), None)         => Source::Path(PathBuf::from(path)),
+                    (None, Some(toml::Item::Bool(true)))        => Source::Vendored,
+                    _                                           => {
+                        return Err(format!("dependency `{}` must be `{{ path = \"...\" }}` or `{{ vendored = true }}`", name))
+                    },
+                };
+
+                dependencies.push(Dependency { name: name.clone(), source })
+            }
+        }
+
+        Ok(dependencies)
+    }
+
+    // the manifest `itu new` starts a package with
+    pub fn template(name: &str) -> String {
+        format!("[package]\nname    = \"{}\"\nversion = \"0.1.0\"\nentry   = \"src/main.itu\"\nsources = [\"src\"]\n\n[dependencies]\n", name)
+    }
+}
+
+// packages are imported by name, so names have to be ones `import` takes
+pub fn check_name(name: &str) -> Result<(), String> {
+    let identifier = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+    if !identifier || KEYWORDS.contains(&name) {
+        return Err(format!("`{}` is not a valid package name, it has to be usable in an import", name))
+    }
+
+    Ok(())
+}
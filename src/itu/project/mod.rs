@@ -0,0 +1,8 @@
+pub mod toml;
+pub mod manifest;
+pub mod package;
+
+pub use super::*;
+
+pub use self::manifest::*;
+pub use self::package::*;
@@ -0,0 +1,121 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::*;
+
+// where vendored dependencies are kept, a directory each under their name
+pub const VENDOR: &str = "vendor";
+
+#[derive(Debug)]
+pub struct Package {
+    pub root:     PathBuf,
+    pub manifest: Manifest,
+}
+
+#[allow(dead_code)]
+impl Package {
+    // the root of the package `path` is in, the nearest directory holding a
+    // manifest from `path` up, `path` itself need not exist
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        let path = env::current_dir().ok()?.join(path);
+
+        let root = path.ancestors().find(|dir| dir.join(MANIFEST).is_file())?;
+
+        Some(fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()))
+    }
+
+    pub fn open(root: &Path) -> Result<Package, String> {
+        let file = root.join(MANIFEST);
+
+        let text = fs::read_to_string(&file).map_err(|why| format!("unable to read {}: {}", file.display(), why))?;
+
+        let manifest = Manifest::parse(&text).map_err(|why| format!("{}: {}", file.display(), why))?;
+
+        Ok(Package {
+            root: root.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    pub fn entry(&self) -> PathBuf {
+        self.root.join(&self.manifest.entry)
+    }
+
+    pub fn sources(&self) -> Vec<PathBuf> {
+        self.manifest.sources.iter().map(|dir| self.root.join(dir)).collect()
+    }
+
+    // every package this one depends on, directly or through another, each
+    // once and after those it depends on
+    pub fn dependencies(&self) -> Result<Vec<Rc<Package>>, String> {
+        let mut found = Vec::new();
+        let mut seen  = vec![fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone())];
+
+        self.collect(&mut found, &mut seen)?;
+
+        Ok(found)
+    }
+
+    fn collect(&self, found: &mut Vec<Rc<Package>>, seen: &mut Vec<PathBuf>) -> Result<(), String> {
+        for dependency in &self.manifest.dependencies {
+            let root = match dependency.source {
+                Source::Path(ref path) => self.root.join(path),
+                Source::Vendored       => self.root.join(VENDOR).join(&dependency.name),
+            };
+
+            let root = fs::canonicalize(&root)
+                .map_err(|why| format!("dependency `{}` of {}: unable to open {}: {}", dependency.name, self.name(), root.display(), why))?;
+
+            if seen.contains(&root) {
+                continue
+            }
+
+            seen.push(root.clone());
+
+            let package = Package::open(&root)?;
+
+            // imports name the dependency, which has to be the package found
+            if package.name() != dependency.name {
+                return Err(format!("dependency `{}` of {} is the package `{}` at {}", dependency.name, self.name(), package.name(), root.display()))
+            }
+
+            if let Some(other) = found.iter().find(|other| other.name() == package.name()) {
+                return Err(format!("two packages named `{}`, at {} and {}", package.name(), other.root.display(), root.display()))
+            }
+
+            package.collect(found, seen)?;
+            found.push(Rc::new(package))
+        }
+
+        Ok(())
+    }
+
+    // a loader finding this package's modules in its sources, those of its
+    // dependencies under their names, and then in ITU_PATH
+    pub fn loader(&self, run: bool) -> Result<loader::Loader, String> {
+        let mut packages = vec![(Rc::new(self.name().to_string()), self.sources())];
+
+        for dependency in self.dependencies()? {
+            packages.push((Rc::new(dependency.name().to_string()), dependency.sources()))
+        }
+
+        let mut search = self.sources();
+        search.extend(loader::paths());
+
+        Ok(loader::Loader::new(search, run).with_packages(packages))
+    }
+}
+
+// the loader for the program in `file`, that of its package if it is in one
+pub fn loader(file: &Path, run: bool) -> Result<loader::Loader, String> {
+    match Package::find(file) {
+        Some(root) => Package::open(&root)?.loader(run),
+        None       => Ok(loader::Loader::new(loader::search(file), run)),
+    }
+}
@@ -0,0 +1,460 @@
+// the part of toml a manifest is written in: `[table]` headers, dotted
+// and quoted keys set to strings, booleans, arrays of those and inline
+// tables, with `#` comments
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Str(String),
+    Bool(bool),
+    Array(Vec<Item>),
+    Table(Vec<(String, Item)>),
+}
+
+#[allow(dead_code)]
+impl Item {
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Item::Str(ref s) => Some(s),
+            _                => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        match *self {
+            Item::Table(ref entries) => entries.iter().find(|(k, _)| k == key).map(|(_, item)| item),
+            _                        => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Item::Str(_)   => "string",
+            Item::Bool(_)  => "boolean",
+            Item::Array(_) => "array",
+            Item::Table(_) => "table",
+        }
+    }
+}
+
+// the document as a table, keys before the first header going in it and
+// every header's table nested under the names its path is made of
+pub fn parse(text: &str) -> Result<Item, String> {
+    let mut parser = Parser { chars: text.chars().peekable(), line: 1 };
+
+    parser.document().map_err(|message| format!("line {}: {}", parser.line, message))
+}
+
+struct Parser<'a> {
+    chars: ::std::iter::Peekable<::std::str::Chars<'a>>,
+    line:  usize,
+}
+
+type Entries = Vec<(String, Item)>;
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+
+        if c == Some('\n') {
+            self.line += 1
+        }
+
+        c
+    }
+
+    // spaces within a line
+    fn space(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.next();
+        }
+    }
+
+    // whitespace, line ends and comments, between statements and array items
+    fn blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some('#')                    => self.comment(),
+                Some(c) if c.is_whitespace() => { self.next(); },
+                _                            => return,
+            }
+        }
+    }
+
+    fn comment(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.next();
+        }
+    }
+
+    // nothing but a comment may follow what a line holds
+    fn end(&mut self) -> Result<(), String> {
+        self.space();
+
+        match self.peek() {
+            None | Some('\n') | Some('\r') | Some('#') => Ok(()),
+            Some(c)                                    => Err(format!("unexpected `{}`", c)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Item, String> {
+        let mut root: Entries             = Vec::new();
+        let mut headers: Vec<Vec<String>> = Vec::new();
+        let mut current: Vec<String>      = Vec::new();
+
+        loop {
+            self.blank();
+
+            match self.peek() {
+                None => return Ok(Item::Table(root)),
+
+                Some('[') => {
+                    self.next();
+
+                    if self.peek() == Some('[') {
+                        return Err("arrays of tables are not supported".to_string())
+                    }
+
+                    self.space();
+                    let path = self.path()?;
+                    self.space();
+
+                    if self.peek() != Some(']') {
+                        return Err(format!("unclosed table header `[{}`", path.join(".")))
+                    }
+
+                    self.next();
+                    self.end()?;
+
+                    if headers.contains(&path) {
+                        return Err(format!("table `{}` defined twice", path.join(".")))
+                    }
+
+                    table(&mut root, &path)?;
+
+                    headers.push(path.clone());
+                    current = path
+                },
+
+                Some(_) => {
+                    let (path, item) = self.entry()?;
+                    self.end()?;
+
+                    insert(table(&mut root, &current)?, &path, item)?
+                },
+            }
+        }
+    }
+
+    fn entry(&mut self) -> Result<(Vec<String>, Item), String> {
+        let path = self.path()?;
+        self.space();
+
+        if self.next() != Some('=') {
+            return Err(format!("expected `=` after `{}`", path.join(".")))
+        }
+
+        self.space();
+
+        Ok((path, self.item()?))
+    }
+
+    // `a`, `"a b"` or `a.'b'.c`
+    fn path(&mut self) -> Result<Vec<String>, String> {
+        let mut path = vec![self.key()?];
+
+        loop {
+            self.space();
+
+            if self.peek() != Some('.') {
+                return Ok(path)
+            }
+
+            self.next();
+            self.space();
+
+            path.push(self.key()?)
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"')  => { self.next(); self.string() },
+            Some('\'') => { self.next(); self.literal() },
+            _          => match self.word() {
+                ref key if key.is_empty() => Err("expected a key".to_string()),
+                key                       => Ok(key),
+            },
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+
+        while let Some(c) = self.peek() {
+            if !bare(c) {
+                break
+            }
+
+            word.push(c);
+            self.next();
+        }
+
+        word
+    }
+
+    fn item(&mut self) -> Result<Item, String> {
+        match self.peek() {
+            Some('"') => {
+                self.next();
+                self.string().map(Item::Str)
+            },
+
+            Some('\'') => {
+                self.next();
+                self.literal().map(Item::Str)
+            },
+
+            // arrays may run over lines, with comments between their items
+            Some('[') => {
+                self.next();
+                let mut items = Vec::new();
+
+                loop {
+                    self.blank();
+
+                    if self.peek() == Some(']') {
+                        self.next();
+                        return Ok(Item::Array(items))
+                    }
+
+                    items.push(self.item()?);
+                    self.blank();
+
+                    match self.next() {
+                        Some(',') => (),
+                        Some(']') => return Ok(Item::Array(items)),
+                        _         => return Err("expected `,` or `]` in array".to_string()),
+                    }
+                }
+            },
+
+            Some('{') => {
+                self.next();
+                let mut entries = Vec::new();
+
+                loop {
+                    self.space();
+
+                    if self.peek() == Some('}') && entries.is_empty() {
+                        self.next();
+                        return Ok(Item::Table(entries))
+                    }
+
+                    let (path, item) = self.entry()?;
+                    insert(&mut entries, &path, item)?;
+                    self.space();
+
+                    match self.next() {
+                        Some(',') => (),
+                        Some('}') => return Ok(Item::Table(entries)),
+                        _         => return Err("expected `,` or `}` in inline table".to_string()),
+                    }
+                }
+            },
+
+            _ => match self.word().as_str() {
+                "true"  => Ok(Item::Bool(true)),
+                "false" => Ok(Item::Bool(false)),
+                ""      => Err("expected a value".to_string()),
+                word    => Err(format!("unsupported value `{}`, expected a string, boolean, array or table", word)),
+            },
+        }
+    }
+
+    // the rest of a string whose opening quote was taken
+    fn string(&mut self) -> Result<String, String> {
+        let mut s = String::new();
+
+        loop {
+            match self.next() {
+                Some('"')  => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('n')  => s.push('\n'),
+                    Some('t')  => s.push('\t'),
+                    Some('r')  => s.push('\r'),
+                    Some('b')  => s.push('\u{8}'),
+                    Some('f')  => s.push('\u{c}'),
+                    Some('"')  => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('u')  => s.push(self.unicode(4)?),
+                    Some('U')  => s.push(self.unicode(8)?),
+                    Some(c)    => return Err(format!("unknown escape `\\{}`", c)),
+                    None       => return Err("unterminated string".to_string()),
+                },
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c)           => s.push(c),
+            }
+        }
+    }
+
+    // 'literal' strings keep every character as written
+    fn literal(&mut self) -> Result<String, String> {
+        let mut s = String::new();
+
+        loop {
+            match self.next() {
+                Some('\'')        => return Ok(s),
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c)           => s.push(c),
+            }
+        }
+    }
+
+    // `\uXXXX` and `\UXXXXXXXX`, the digits being a scalar value in hex
+    fn unicode(&mut self, digits: usize) -> Result<char, String> {
+        let mut hex = String::new();
+
+        for _ in 0 .. digits {
+            match self.peek() {
+                Some(c) if c.is_ascii_hexdigit() => { hex.push(c); self.next(); },
+                _                                => break,
+            }
+        }
+
+        u32::from_str_radix(&hex, 16).ok()
+            .filter(|_| hex.len() == digits)
+            .and_then(::std::char::from_u32)
+            .ok_or_else(|| format!("invalid unicode escape `{}`", hex))
+    }
+}
+
+fn bare(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+// the table a path leads to, tables along it made as they are missing
+fn table<'t>(entries: &'t mut Entries, path: &[String]) -> Result<&'t mut Entries, String> {
+    let (first, rest) = match path.split_first() {
+        Some(split) => split,
+        None        => return Ok(entries),
+    };
+
+    let index = match entries.iter().position(|(key, _)| key == first) {
+        Some(index) => index,
+        None        => {
+            entries.push((first.clone(), Item::Table(Vec::new())));
+            entries.len() - 1
+        },
+    };
+
+    match entries[index].1 {
+        Item::Table(ref mut inner) => table(inner, rest),
+        ref item                   => Err(format!("`{}` is a {}, not a table", first, item.type_name())),
+    }
+}
+
+fn insert(entries: &mut Entries, path: &[String], item: Item) -> Result<(), String> {
+    let (key, parents) = path.split_last().unwrap();
+    let entries        = table(entries, parents)?;
+
+    if entries.iter().any(|(k, _)| k == key) {
+        return Err(format!("`{}` set twice", path.join(".")))
+    }
+
+    entries.push((key.clone(), item));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Item {
+        Item::Str(s.to_string())
+    }
+
+    fn at<'i>(document: &'i Item, path: &[&str]) -> &'i Item {
+        path.iter().fold(document, |item, key| item.get(key).unwrap_or_else(|| panic!("no `{}` in {:?}", key, item)))
+    }
+
+    #[test]
+    fn arrays_run_over_lines() {
+        let document = parse("[package]\nsources = [\n  \"src\", # the code\n  'lib',\n\n]\nname = \"x\"\n").unwrap();
+
+        assert_eq!(*at(&document, &["package", "sources"]), Item::Array(vec![string("src"), string("lib")]));
+        assert_eq!(*at(&document, &["package", "name"]), string("x"));
+    }
+
+    #[test]
+    fn dotted_headers_nest_their_tables() {
+        let document = parse("[dependencies.geo]\npath = \"../geo\"\n\n[dependencies . 'json']\nvendored = true\n").unwrap();
+
+        assert_eq!(*at(&document, &["dependencies", "geo", "path"]), string("../geo"));
+        assert_eq!(*at(&document, &["dependencies", "json", "vendored"]), Item::Bool(true));
+    }
+
+    #[test]
+    fn dotted_keys_nest_their_values() {
+        let document = parse("[dependencies]\ngeo.path = \"../geo\"\njson = { source.vendored = true }\n").unwrap();
+
+        assert_eq!(*at(&document, &["dependencies", "geo", "path"]), string("../geo"));
+        assert_eq!(*at(&document, &["dependencies", "json", "source", "vendored"]), Item::Bool(true));
+    }
+
+    #[test]
+    fn literal_strings_keep_backslashes() {
+        let document = parse("[package]\nentry = 'C:\\src\\main.itu'\n").unwrap();
+
+        assert_eq!(*at(&document, &["package", "entry"]), string("C:\\src\\main.itu"));
+    }
+
+    #[test]
+    fn quoted_keys_hold_any_character() {
+        let document = parse("[\"my table\"]\n\"a.b\" = true\n'c d' = false\n").unwrap();
+
+        assert_eq!(*at(&document, &["my table", "a.b"]), Item::Bool(true));
+        assert_eq!(*at(&document, &["my table", "c d"]), Item::Bool(false));
+    }
+
+    #[test]
+    fn unicode_escapes_are_decoded() {
+        let document = parse("[package]\nname = \"caf\\u00e9 \\U0001F600 \\\"q\\\"\"\n").unwrap();
+
+        assert_eq!(*at(&document, &["package", "name"]), string("café 😀 \"q\""));
+
+        assert!(parse("a = \"\\u00\"").unwrap_err().contains("invalid unicode escape"));
+        assert!(parse("a = \"\\uD800\"").unwrap_err().contains("invalid unicode escape"));
+    }
+
+    #[test]
+    fn unclosed_headers_are_refused() {
+        assert_eq!(parse("[package\nname = \"x\"\n").unwrap_err(), "line 1: unclosed table header `[package`");
+        assert_eq!(parse("[package").unwrap_err(), "line 1: unclosed table header `[package`");
+    }
+
+    #[test]
+    fn mistakes_are_reported_at_their_line() {
+        assert_eq!(parse("[a]\nx = 1\n").unwrap_err(), "line 2: unsupported value `1`, expected a string, boolean, array or table");
+        assert_eq!(parse("[a]\nx = true\n[a]\n").unwrap_err(), "line 3: table `a` defined twice");
+        assert_eq!(parse("[a]\nx = true\nx = false\n").unwrap_err(), "line 3: `x` set twice");
+        assert_eq!(parse("[a]\nx = true\n[a.x]\n").unwrap_err(), "line 3: `x` is a boolean, not a table");
+        assert_eq!(parse("[a]\nx = \"open\ny = true\n").unwrap_err(), "line 3: unterminated string");
+        assert_eq!(parse("[a] b = true\n").unwrap_err(), "line 1: unexpected `b`");
+        assert_eq!(parse("xs = [\"a\"\n\"b\"]\n").unwrap_err(), "line 2: expected `,` or `]` in array");
+    }
+
+    #[test]
+    fn keys_before_any_header_go_in_the_document() {
+        let document = parse("# a comment\ntop = true\n\n[a]\n").unwrap();
+
+        assert_eq!(document, Item::Table(vec![
+            ("top".to_string(), Item::Bool(true)),
+            ("a".to_string(), Item::Table(Vec::new())),
+        ]));
+    }
+}
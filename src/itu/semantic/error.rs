@@ -0,0 +1,52 @@
+use super::*;
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum SemanticErrorValue {
+    Constant(String),
+}
+
+#[derive(Debug)]
+pub struct SemanticError {
+    pub value:    SemanticErrorValue,
+    pub position: Option<TokenPosition>,
+    pub note:     Option<(TokenPosition, String)>,
+}
+
+#[allow(dead_code)]
+impl SemanticError {
+    pub fn new(value: &str) -> SemanticError {
+        SemanticError {
+            value:    SemanticErrorValue::Constant(value.to_owned()),
+            position: None,
+            note:     None,
+        }
+    }
+
+    pub fn new_pos(position: TokenPosition, value: &str) -> SemanticError {
+        SemanticError {
+            value:    SemanticErrorValue::Constant(value.to_owned()),
+            position: Some(position),
+            note:     None,
+        }
+    }
+
+    pub fn with_note(self, position: TokenPosition, note: &str) -> SemanticError {
+        SemanticError {
+            note: Some((position, note.to_owned())),
+            .. self
+        }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            SemanticErrorValue::Constant(ref s) => match self.position {
+                Some(p) => write!(f, "{}: {}", p, s),
+                None    => write!(f, "{}", s),
+            }
+        }
+    }
+}
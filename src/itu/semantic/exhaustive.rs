@@ -0,0 +1,168 @@
+use std::fmt;
+
+use super::*;
+
+// a value no arm of a match takes, written as the pattern that would
+#[derive(Debug, Clone)]
+pub enum Witness {
+    Any,
+    Bool(bool),
+    // an array of exactly these elements, or of at least them when open
+    Array(Vec<Witness>, bool),
+}
+
+impl fmt::Display for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Witness::Any     => write!(f, "_"),
+            Witness::Bool(b) => write!(f, "{}", b),
+            Witness::Array(ref elements, open) => {
+                let mut items = elements.iter().map(|element| element.to_string()).collect::<Vec<_>>();
+
+                if open {
+                    items.push("...".to_string())
+                }
+
+                write!(f, "{{{}}}", items.join(", "))
+            },
+        }
+    }
+}
+
+// what the patterns heading a column may be split into
+enum Constructor {
+    Bool(bool),
+    // arrays of a length, or of that length and longer when open
+    Length(usize, bool),
+}
+
+// the first value the unguarded arms leave unmatched, if any. bools are
+// covered by both literals and arrays by lengths up to the longest pattern
+// and past it by one with a rest, other literals only ever by a binding or `_`
+pub fn missing(arms: &[Arm]) -> Option<Witness> {
+    let rows = arms.iter()
+        .filter(|arm| arm.guard.is_none())
+        .map(|arm| vec![arm.pattern.clone()])
+        .collect::<Vec<_>>();
+
+    uncovered(&rows, 1).map(|mut witness| witness.remove(0))
+}
+
+fn uncovered(rows: &[Vec<Pattern>], width: usize) -> Option<Vec<Witness>> {
+    if width == 0 {
+        return if rows.is_empty() { Some(Vec::new()) } else { None }
+    }
+
+    let heads = rows.iter().map(|row| &row[0]).filter(|head| !wild(head)).collect::<Vec<_>>();
+
+    let constructors = match heads.first() {
+        Some(&&Pattern::Bool(_)) => {
+            let seen = |b| heads.iter().any(|head| **head == Pattern::Bool(b));
+
+            if seen(true) && seen(false) {
+                Some(vec![Constructor::Bool(true), Constructor::Bool(false)])
+            } else {
+                None
+            }
+        },
+
+        Some(&&Pattern::Array(..)) => {
+            // past the longest exact pattern and every prefix, lengths no
+            // longer tell the rows apart
+            let longest = heads.iter()
+                .map(|head| match **head {
+                    Pattern::Array(ref elements, None)    => elements.len() + 1,
+                    Pattern::Array(ref elements, Some(_)) => elements.len(),
+                    _                                     => 0,
+                })
+                .max()
+                .unwrap_or(0);
+
+            let mut lengths = (0 .. longest).map(|n| Constructor::Length(n, false)).collect::<Vec<_>>();
+            lengths.push(Constructor::Length(longest, true));
+
+            Some(lengths)
+        },
+
+        _ => None,
+    };
+
+    match constructors {
+        Some(constructors) => {
+            for constructor in constructors {
+                let arity = match constructor {
+                    Constructor::Bool(_)      => 0,
+                    Constructor::Length(n, _) => n,
+                };
+
+                let specialized = rows.iter()
+                    .filter_map(|row| specialize(row, &constructor))
+                    .collect::<Vec<_>>();
+
+                if let Some(mut witness) = uncovered(&specialized, arity + width - 1) {
+                    let rest = witness.split_off(arity);
+
+                    let head = match constructor {
+                        Constructor::Bool(b)         => Witness::Bool(b),
+                        Constructor::Length(_, open) => Witness::Array(witness, open),
+                    };
+
+                    let mut witness = vec![head];
+                    witness.extend(rest);
+
+                    return Some(witness)
+                }
+            }
+
+            None
+        },
+
+        // the rows with anything in the column have to cover the rest
+        None => {
+            let default = rows.iter()
+                .filter(|row| wild(&row[0]))
+                .map(|row| row[1 ..].to_vec())
+                .collect::<Vec<_>>();
+
+            let rest = uncovered(&default, width - 1)?;
+
+            let head = match heads.first() {
+                Some(&&Pattern::Bool(_)) => Witness::Bool(!heads.iter().any(|head| **head == Pattern::Bool(true))),
+                _                        => Witness::Any,
+            };
+
+            let mut witness = vec![head];
+            witness.extend(rest);
+
+            Some(witness)
+        },
+    }
+}
+
+fn wild(pattern: &Pattern) -> bool {
+    matches!(*pattern, Pattern::Wildcard | Pattern::Binding(_))
+}
+
+// the row with its head taken apart as `constructor`, none if it cannot be one
+fn specialize(row: &[Pattern], constructor: &Constructor) -> Option<Vec<Pattern>> {
+    let mut fields = match (&row[0], constructor) {
+        (head, &Constructor::Length(n, _)) if wild(head) => vec![Pattern::Wildcard; n],
+        (head, &Constructor::Bool(_))      if wild(head) => Vec::new(),
+
+        (&Pattern::Bool(b), &Constructor::Bool(c)) if b == c => Vec::new(),
+
+        (&Pattern::Array(ref elements, None), &Constructor::Length(n, false)) if elements.len() == n => elements.clone(),
+
+        (&Pattern::Array(ref elements, Some(_)), &Constructor::Length(n, _)) if elements.len() <= n => {
+            let mut fields = elements.clone();
+            fields.resize(n, Pattern::Wildcard);
+            fields
+        },
+
+        _ => return None,
+    };
+
+    fields.extend_from_slice(&row[1 ..]);
+
+    Some(fields)
+}
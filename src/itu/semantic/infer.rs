@@ -0,0 +1,990 @@
+use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty {
+    Var(usize),
+    Any,
+    Unit,
+    Prim(Type),
+    Array(Rc<Ty>),
+    Named(Rc<String>),
+    Module(Rc<String>),
+    Function(Vec<Ty>, Rc<Ty>),
+}
+
+impl Ty {
+    pub fn is_numeric(&self) -> bool {
+        match *self {
+            Ty::Prim(ref t) => matches!(
+                *t,
+                Type::I08 | Type::I16 | Type::I32 | Type::I64 | Type::I128 |
+                Type::U08 | Type::U16 | Type::U32 | Type::U64 | Type::U128 |
+                Type::F32 | Type::F64
+            ),
+            _ => false,
+        }
+    }
+
+    fn vars(&self, acc: &mut Vec<usize>) {
+        match *self {
+            Ty::Var(v) if !acc.contains(&v) => acc.push(v),
+            Ty::Array(ref t) => t.vars(acc),
+            Ty::Function(ref params, ref ret) => {
+                for p in params {
+                    p.vars(acc)
+                }
+                ret.vars(acc)
+            },
+            _ => (),
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, names: &HashMap<usize, String>) -> fmt::Result {
+        match *self {
+            Ty::Var(v) => match names.get(&v) {
+                Some(name) => write!(f, "{}", name),
+                None       => write!(f, "_"),
+            },
+            Ty::Any     => write!(f, "any"),
+            Ty::Unit    => write!(f, "()"),
+            Ty::Prim(ref t) => write!(f, "{}", primitive_name(t)),
+            Ty::Array(ref t) => {
+                write!(f, "[")?;
+                t.write(f, names)?;
+                write!(f, "]")
+            },
+            Ty::Named(ref name)  => write!(f, "{}", name),
+            Ty::Module(ref name) => write!(f, "module {}", name),
+            Ty::Function(ref params, ref ret) => {
+                write!(f, "(")?;
+                for (i, p) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?
+                    }
+                    p.write(f, names)?
+                }
+                write!(f, "): ")?;
+                ret.write(f, names)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, &HashMap::new())
+    }
+}
+
+fn primitive_name(t: &Type) -> &'static str {
+    match *t {
+        Type::I08  => "i08",
+        Type::I16  => "i16",
+        Type::I32  => "i32",
+        Type::I64  => "i64",
+        Type::I128 => "i128",
+        Type::F32  => "f32",
+        Type::F64  => "f64",
+        Type::U08  => "u08",
+        Type::U16  => "u16",
+        Type::U32  => "u32",
+        Type::U64  => "u64",
+        Type::U128 => "u128",
+        Type::Char => "char",
+        Type::Str  => "str",
+        Type::Bool => "bool",
+        _          => "any",
+    }
+}
+
+// a type with its quantified variables, `numeric` being the ones that must
+// be instantiated with a number type
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    pub vars:    Vec<usize>,
+    pub numeric: Vec<usize>,
+    pub ty:      Ty,
+}
+
+impl Scheme {
+    pub fn mono(ty: Ty) -> Scheme {
+        Scheme {
+            vars:    Vec::new(),
+            numeric: Vec::new(),
+            ty,
+        }
+    }
+
+    // the scheme with every variable it mentions quantified, so that it
+    // reads back in through `Inferer::signature` the way it was written
+    pub fn closed(&self) -> Scheme {
+        let mut vars = Vec::new();
+        self.ty.vars(&mut vars);
+
+        Scheme {
+            vars,
+            numeric: self.numeric.clone(),
+            ty:      self.ty.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut vars = Vec::new();
+        self.ty.vars(&mut vars);
+
+        let mut names = HashMap::new();
+
+        for (i, v) in vars.iter().enumerate() {
+            let name = if i < 26 {
+                ((b'a' + i as u8) as char).to_string()
+            } else {
+                format!("t{}", i)
+            };
+
+            names.insert(*v, name);
+        }
+
+        let quantified = vars.iter().filter(|v| self.vars.contains(v)).collect::<Vec<_>>();
+
+        if !quantified.is_empty() {
+            write!(f, "<")?;
+            for (i, v) in quantified.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?
+                }
+                write!(f, "{}", names[v])?;
+                if self.numeric.contains(v) {
+                    write!(f, ": num")?
+                }
+            }
+            write!(f, ">")?
+        }
+
+        self.ty.write(f, &names)
+    }
+}
+
+pub struct Inferer {
+    bindings: Vec<Option<Ty>>,
+    numeric:  HashSet<usize>,
+    scopes:   Vec<HashMap<Rc<String>, Scheme>>,
+    pending:  HashMap<Rc<String>, Ty>,
+    // the type of every expression inferred, keyed by node address
+    types:    HashMap<*const Expression, Ty>,
+    // names declared `mut` with no type, and where
+    untyped:  HashMap<Rc<String>, TokenPosition>,
+}
+
+#[allow(dead_code)]
+impl Inferer {
+    pub fn new() -> Inferer {
+        Inferer {
+            bindings: Vec::new(),
+            numeric:  HashSet::new(),
+            scopes:   vec![HashMap::new()],
+            pending:  HashMap::new(),
+            types:    HashMap::new(),
+            untyped:  HashMap::new(),
+        }
+    }
+
+    pub fn define(&mut self, name: &str, scheme: Scheme) {
+        self.scopes.last_mut().unwrap().insert(Rc::new(name.to_owned()), scheme);
+    }
+
+    // infers every statement in order, returning the generalized type of
+    // each top-level definition
+    pub fn infer(&mut self, ast: &[Statement]) -> SemanticResult<Vec<(Rc<String>, Scheme)>> {
+        self.forward(ast)?;
+
+        let mut types = Vec::new();
+
+        for statement in ast {
+            self.statement(statement)?;
+
+            if let Statement::Definition(ref definition) = *statement {
+                let name = Self::name(&definition.name)?;
+                types.push((name.clone(), self.lookup(&name).unwrap().clone()));
+            }
+        }
+
+        Ok(self.finish(types))
+    }
+
+    // like `infer`, but going on past statements that fail, what they define
+    // being left a type nothing is known of. editors want the rest typed
+    // while the program is still being written
+    pub fn recover(&mut self, ast: &[Statement]) -> (Vec<(Rc<String>, Scheme)>, Vec<SemanticError>) {
+        let mut errors = Vec::new();
+
+        if let Err(err) = self.forward(ast) {
+            errors.push(err)
+        }
+
+        let mut types = Vec::new();
+
+        for statement in ast {
+            let result = self.statement(statement);
+
+            let definition = match *statement {
+                Statement::Definition(ref definition) => definition,
+                _                                     => {
+                    errors.extend(result.err());
+                    continue
+                },
+            };
+
+            let name = match Self::name(&definition.name) {
+                Ok(name) => name,
+                Err(err) => {
+                    errors.extend(result.err().or(Some(err)));
+                    continue
+                },
+            };
+
+            if let Err(err) = result {
+                errors.push(err);
+
+                if !self.scopes.last().unwrap().contains_key(&name) {
+                    let t = self.fresh();
+                    self.define(&name, Scheme::mono(t))
+                }
+            }
+
+            types.push((name.clone(), self.lookup(&name).unwrap().clone()));
+        }
+
+        (self.finish(types), errors)
+    }
+
+    // top-level functions may refer to each other regardless of order
+    fn forward(&mut self, ast: &[Statement]) -> SemanticResult<()> {
+        for statement in ast {
+            if let Statement::Definition(ref definition) = *statement {
+                if let Some(ref right) = definition.right {
+                    if let Expression::Lambda(_) = **right {
+                        let name = Self::name(&definition.name)?;
+                        let var  = self.fresh();
+                        self.pending.insert(name, var);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self, types: Vec<(Rc<String>, Scheme)>) -> Vec<(Rc<String>, Scheme)> {
+        // numbers nobody constrained end up as f64
+        for v in 0 .. self.bindings.len() {
+            if self.numeric.contains(&v) && self.bindings[v].is_none() && !self.quantified(v, &types) {
+                self.bindings[v] = Some(Ty::Prim(Type::F64))
+            }
+        }
+
+        types.into_iter().map(|(name, scheme)| {
+            let ty = self.resolve(&scheme.ty);
+            (name, Scheme { ty, .. scheme })
+        }).collect()
+    }
+
+    // what `expression` was found to be, as far as inference pinned it down
+    pub fn type_of(&self, expression: &Expression) -> Option<Ty> {
+        self.types.get(&(expression as *const Expression)).map(|t| self.resolve(t))
+    }
+
+    // whether `t` is, or must become, a number type
+    pub fn numeric(&self, t: &Ty) -> bool {
+        match self.prune(t) {
+            Ty::Var(v) => self.numeric.contains(&v),
+            t          => t.is_numeric(),
+        }
+    }
+
+    fn quantified(&self, v: usize, types: &[(Rc<String>, Scheme)]) -> bool {
+        types.iter().any(|(_, scheme)| scheme.vars.contains(&v))
+    }
+
+    fn name(expression: &Expression) -> SemanticResult<Rc<String>> {
+        match *expression {
+            Expression::Identifier(ref name) => Ok(name.clone()),
+            _ => Err(SemanticError::new(&format!("expected name, found: {:?}", expression))),
+        }
+    }
+
+    fn fresh(&mut self) -> Ty {
+        self.bindings.push(None);
+        Ty::Var(self.bindings.len() - 1)
+    }
+
+    fn fresh_numeric(&mut self) -> Ty {
+        let var = self.fresh();
+        if let Ty::Var(v) = var {
+            self.numeric.insert(v);
+        }
+        var
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Scheme> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(scheme) = scope.get(&name.to_owned()) {
+                return Some(scheme)
+            }
+        }
+        None
+    }
+
+    fn prune(&self, t: &Ty) -> Ty {
+        match *t {
+            Ty::Var(v) => match self.bindings[v] {
+                Some(ref bound) => self.prune(bound),
+                None            => t.clone(),
+            },
+            _ => t.clone(),
+        }
+    }
+
+    pub fn resolve(&self, t: &Ty) -> Ty {
+        match self.prune(t) {
+            Ty::Array(ref t) => Ty::Array(Rc::new(self.resolve(t))),
+            Ty::Function(ref params, ref ret) => Ty::Function(
+                params.iter().map(|p| self.resolve(p)).collect(),
+                Rc::new(self.resolve(ret))
+            ),
+            t => t,
+        }
+    }
+
+    fn occurs(&self, v: usize, t: &Ty) -> bool {
+        match self.prune(t) {
+            Ty::Var(w) => v == w,
+            Ty::Array(ref t) => self.occurs(v, t),
+            Ty::Function(ref params, ref ret) => params.iter().any(|p| self.occurs(v, p)) || self.occurs(v, ret),
+            _ => false,
+        }
+    }
+
+    fn bind(&mut self, v: usize, t: &Ty) -> SemanticResult<()> {
+        if self.occurs(v, t) {
+            return Err(SemanticError::new(&format!("infinite type: `{}` contains itself", self.resolve(t))))
+        }
+
+        if self.numeric.contains(&v) {
+            match *t {
+                Ty::Var(w) => {
+                    self.numeric.insert(w);
+                },
+                Ty::Any => (),
+                ref t if t.is_numeric() => (),
+                _ => return Err(SemanticError::new(&format!("expected number, found `{}`", self.resolve(t)))),
+            }
+        }
+
+        self.bindings[v] = Some(t.clone());
+
+        Ok(())
+    }
+
+    fn unify(&mut self, expected: &Ty, found: &Ty) -> SemanticResult<()> {
+        let a = self.prune(expected);
+        let b = self.prune(found);
+
+        match (&a, &b) {
+            (Ty::Var(x), Ty::Var(y)) if x == y => Ok(()),
+            (Ty::Var(x), _) => self.bind(*x, &b),
+            (_, Ty::Var(y)) => self.bind(*y, &a),
+            (Ty::Any, _) | (_, Ty::Any) => Ok(()),
+            (Ty::Unit, Ty::Unit) => Ok(()),
+            (Ty::Prim(p), Ty::Prim(q)) if p == q => Ok(()),
+            (Ty::Named(p), Ty::Named(q)) if p == q => Ok(()),
+            (Ty::Module(p), Ty::Module(q)) if p == q => Ok(()),
+            (Ty::Array(x), Ty::Array(y)) => self.unify(x, y),
+            (Ty::Function(pa, ra), Ty::Function(pb, rb)) => {
+                if pa.len() != pb.len() {
+                    return Err(SemanticError::new(&format!("expected {} argument(s), found {}", pa.len(), pb.len())))
+                }
+
+                for (x, y) in pa.iter().zip(pb.iter()) {
+                    self.unify(x, y)?
+                }
+
+                self.unify(ra, rb)
+            },
+            _ => Err(SemanticError::new(&format!("mismatched types: expected `{}`, found `{}`", self.resolve(&a), self.resolve(&b)))),
+        }
+    }
+
+    fn free(&self, t: &Ty, acc: &mut Vec<usize>) {
+        self.resolve(t).vars(acc)
+    }
+
+    fn generalize(&self, t: &Ty) -> Scheme {
+        let mut bound = Vec::new();
+
+        for scope in &self.scopes {
+            for scheme in scope.values() {
+                let mut vars = Vec::new();
+                self.free(&scheme.ty, &mut vars);
+                bound.extend(vars.into_iter().filter(|v| !scheme.vars.contains(v)));
+            }
+        }
+
+        for t in self.pending.values() {
+            self.free(t, &mut bound)
+        }
+
+        let mut vars = Vec::new();
+        self.free(t, &mut vars);
+        vars.retain(|v| !bound.contains(v));
+
+        let numeric = vars.iter().cloned().filter(|v| self.numeric.contains(v)).collect();
+
+        Scheme {
+            vars,
+            numeric,
+            ty: self.resolve(t),
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Ty {
+        let mut fresh = HashMap::new();
+
+        for v in &scheme.vars {
+            let t = if scheme.numeric.contains(v) {
+                self.fresh_numeric()
+            } else {
+                self.fresh()
+            };
+
+            fresh.insert(*v, t);
+        }
+
+        self.substitute(&scheme.ty, &fresh)
+    }
+
+    fn substitute(&self, t: &Ty, fresh: &HashMap<usize, Ty>) -> Ty {
+        match self.prune(t) {
+            Ty::Var(v) => match fresh.get(&v) {
+                Some(t) => t.clone(),
+                None    => Ty::Var(v),
+            },
+            Ty::Array(ref t) => Ty::Array(Rc::new(self.substitute(t, fresh))),
+            Ty::Function(ref params, ref ret) => Ty::Function(
+                params.iter().map(|p| self.substitute(p, fresh)).collect(),
+                Rc::new(self.substitute(ret, fresh))
+            ),
+            t => t,
+        }
+    }
+
+    // reads a signature written the way `Scheme` displays it, e.g.
+    // `<a, b>([a], (a): b): [b]`, panicking on malformed ones
+    pub fn signature(&mut self, text: &str) -> Scheme {
+        let mut tokens = Vec::new();
+        let mut chars  = text.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c.is_alphanumeric() || c == '_' {
+                let mut word = String::new();
+
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break
+                    }
+                }
+
+                tokens.push(word)
+            } else {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+        }
+
+        tokens.reverse();
+
+        let mut vars    = Vec::new();
+        let mut numeric = Vec::new();
+        let mut names   = HashMap::new();
+
+        if tokens.last().map(|t| t == "<") == Some(true) {
+            tokens.pop();
+
+            while tokens.last().map(|t| t != ">") == Some(true) {
+                let name = tokens.pop().unwrap();
+
+                let var = if tokens.last().map(|t| t == ":") == Some(true) {
+                    tokens.pop();
+                    assert_eq!(tokens.pop().as_deref(), Some("num"), "malformed signature: {}", text);
+                    self.fresh_numeric()
+                } else {
+                    self.fresh()
+                };
+
+                if let Ty::Var(v) = var {
+                    vars.push(v);
+
+                    if self.numeric.contains(&v) {
+                        numeric.push(v)
+                    }
+                }
+
+                names.insert(name, var);
+
+                if tokens.last().map(|t| t == ",") == Some(true) {
+                    tokens.pop();
+                }
+            }
+
+            tokens.pop();
+        }
+
+        let ty = Self::signature_type(&mut tokens, &names, text);
+
+        Scheme {
+            vars,
+            numeric,
+            ty,
+        }
+    }
+
+    fn signature_type(tokens: &mut Vec<String>, names: &HashMap<String, Ty>, text: &str) -> Ty {
+        let token = tokens.pop().unwrap_or_else(|| panic!("malformed signature: {}", text));
+
+        match token.as_str() {
+            "[" => {
+                let t = Self::signature_type(tokens, names, text);
+                tokens.pop();
+
+                Ty::Array(Rc::new(t))
+            },
+
+            "(" => {
+                let mut params = Vec::new();
+
+                while tokens.last().map(|t| t != ")") == Some(true) {
+                    params.push(Self::signature_type(tokens, names, text));
+
+                    if tokens.last().map(|t| t == ",") == Some(true) {
+                        tokens.pop();
+                    }
+                }
+
+                tokens.pop();
+
+                if tokens.last().map(|t| t == ":") == Some(true) {
+                    tokens.pop();
+                    Ty::Function(params, Rc::new(Self::signature_type(tokens, names, text)))
+                } else {
+                    Ty::Unit
+                }
+            },
+
+            "any" => Ty::Any,
+
+            name => match names.get(name) {
+                Some(var) => var.clone(),
+                None      => {
+                    let primitive = Token::new(TokenType::Type, TokenPosition::default(), name.to_owned());
+
+                    match Type::from(&primitive) {
+                        Some(t) => Ty::Prim(t),
+                        None    => Ty::Named(Rc::new(name.to_owned())),
+                    }
+                },
+            },
+        }
+    }
+
+    pub fn annotation(&mut self, t: &Type) -> Ty {
+        match *t {
+            Type::Mut(Some(ref t))    => self.annotation(t),
+            Type::Mut(None)           => self.fresh(),
+            Type::Array(ref t, _)     => Ty::Array(Rc::new(self.annotation(t))),
+            Type::Identifier(ref name) => Ty::Named(name.clone()),
+            Type::Any                 => Ty::Any,
+            ref t                     => Ty::Prim(t.clone()),
+        }
+    }
+
+    // errors without a place of their own are put at the statement they
+    // were found in
+    fn statement(&mut self, statement: &Statement) -> SemanticResult<Ty> {
+        let t = match *statement {
+            Statement::Expression(ref expression, _) => self.expression(expression),
+
+            Statement::Assignment(ref assignment) => {
+                let left  = self.expression(&assignment.left);
+                let right = self.expression(&assignment.right);
+
+                left.and_then(|left| right.and_then(|right| {
+                    self.unify(&left, &right).map_err(|e| self.untyped(&assignment.left, &left, e))
+                })).map(|_| Ty::Unit)
+            },
+
+            Statement::Definition(ref definition) => self.definition(definition).map(|_| Ty::Unit),
+            Statement::Test(ref test)             => self.expression(&test.body).map(|_| Ty::Unit),
+
+            // the module was declared along with everything else in scope
+            Statement::Import(_) => Ok(Ty::Unit),
+        };
+
+        t.map_err(|e| Self::locate(e, statement.position()))
+    }
+
+    // a `mut` without a type keeps the type of its first value, which is
+    // what an assignment of anything else has to be told
+    fn untyped(&self, left: &Expression, t: &Ty, error: SemanticError) -> SemanticError {
+        let (name, position) = match *left {
+            Expression::Identifier(ref name) => match self.untyped.get(name) {
+                Some(position) => (name, *position),
+                None           => return error,
+            },
+            _ => return error,
+        };
+
+        let held = match self.resolve(t) {
+            ref t @ Ty::Var(_) if self.numeric(t) => "a number".to_string(),
+            t                                     => format!("`{}`", t),
+        };
+
+        let message = format!("`{}` holds {} as its first value did, declare it `mut any` to give it others", name, held);
+
+        SemanticError::new(&message).with_note(position, &format!("`{}` declared `mut` without a type here", name))
+    }
+
+    fn locate(error: SemanticError, position: TokenPosition) -> SemanticError {
+        match error.position {
+            Some(_) => error,
+            None    => SemanticError { position: Some(position), .. error },
+        }
+    }
+
+    fn definition(&mut self, definition: &Definition) -> SemanticResult<()> {
+        let name = Self::name(&definition.name)?;
+
+        let declared = match definition.t {
+            Some(ref t) => self.annotation(t),
+            None        => self.fresh(),
+        };
+
+        let mutable = matches!(definition.t, Some(Type::Mut(_)));
+
+        if let Some(Type::Mut(None)) = definition.t {
+            self.untyped.insert(name.clone(), definition.position);
+        } else {
+            self.untyped.remove(&name);
+        }
+
+        let right = match definition.right {
+            Some(ref right) => right,
+            None            => {
+                self.define(&name, Scheme::mono(declared));
+                return Ok(())
+            },
+        };
+
+        let scheme = if let Expression::Lambda(_) = **right {
+            // a lambda may call itself, so it is visible while being inferred
+            let own = match self.pending.remove(&name) {
+                Some(t) => t,
+                None    => self.fresh(),
+            };
+
+            self.scopes.push(HashMap::new());
+            self.define(&name, Scheme::mono(own.clone()));
+
+            let t = self.expression(right);
+
+            self.scopes.pop();
+
+            let t = t?;
+
+            self.unify(&declared, &t)?;
+            self.unify(&own, &t)?;
+
+            if mutable {
+                Scheme::mono(t)
+            } else {
+                self.generalize(&t)
+            }
+        } else {
+            let t = self.expression(right)?;
+            self.unify(&declared, &t)?;
+
+            Scheme::mono(t)
+        };
+
+        self.define(&name, scheme);
+
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> SemanticResult<Ty> {
+        let t = self.expression_type(expression)?;
+        self.types.insert(expression as *const Expression, t.clone());
+
+        Ok(t)
+    }
+
+    fn expression_type(&mut self, expression: &Expression) -> SemanticResult<Ty> {
+        match *expression {
+            Expression::Block(ref statements) => {
+                self.scopes.push(HashMap::new());
+
+                let mut last = Ok(Ty::Unit);
+
+                for statement in statements {
+                    if let Statement::Expression(ref e, _) = *statement {
+                        if **e == Expression::EOF {
+                            continue
+                        }
+                    }
+
+                    last = self.statement(statement);
+
+                    if last.is_err() {
+                        break
+                    }
+                }
+
+                self.scopes.pop();
+
+                last
+            },
+
+            Expression::Number(_) => Ok(self.fresh_numeric()),
+            Expression::Bool(_)   => Ok(Ty::Prim(Type::Bool)),
+            Expression::Str(_)    => Ok(Ty::Prim(Type::Str)),
+            Expression::Char(_)   => Ok(Ty::Prim(Type::Char)),
+
+            Expression::Identifier(ref name) => {
+                if let Some(scheme) = self.lookup(name).cloned() {
+                    Ok(self.instantiate(&scheme))
+                } else if let Some(t) = self.pending.get(name) {
+                    Ok(t.clone())
+                } else {
+                    Err(SemanticError::new(&format!("unknown name: {}", name)))
+                }
+            },
+
+            Expression::Operation(ref operation) => {
+                let left  = self.expression(&operation.left)?;
+                let right = self.expression(&operation.right)?;
+
+                match operation.op {
+                    Operand::Equal | Operand::NEqual => {
+                        self.unify(&left, &right)?;
+                        Ok(Ty::Prim(Type::Bool))
+                    },
+
+                    Operand::Lt | Operand::Gt | Operand::LtEqual | Operand::GtEqual => {
+                        let t = self.fresh_numeric();
+                        self.unify(&t, &left)?;
+                        self.unify(&t, &right)?;
+                        Ok(Ty::Prim(Type::Bool))
+                    },
+
+                    // `+` also joins strings
+                    Operand::Add if self.prune(&left) == Ty::Prim(Type::Str) || self.prune(&right) == Ty::Prim(Type::Str) => {
+                        let t = Ty::Prim(Type::Str);
+                        self.unify(&t, &left)?;
+                        self.unify(&t, &right)?;
+                        Ok(t)
+                    },
+
+                    _ => {
+                        let t = self.fresh_numeric();
+                        self.unify(&t, &left)?;
+                        self.unify(&t, &right)?;
+                        Ok(t)
+                    },
+                }
+            },
+
+            Expression::Call(ref call) => {
+                let callee = self.expression(&call.callee)?;
+
+                let mut args = Vec::new();
+
+                for arg in &call.args {
+                    args.push(self.expression(arg)?)
+                }
+
+                if let Ty::Any = self.prune(&callee) {
+                    return Ok(Ty::Any)
+                }
+
+                let ret = self.fresh();
+
+                self.unify(&callee, &Ty::Function(args, Rc::new(ret.clone())))?;
+
+                Ok(ret)
+            },
+
+            Expression::Lambda(ref lambda) => {
+                self.scopes.push(HashMap::new());
+
+                let mut params = Vec::new();
+
+                for (t, name) in &lambda.params {
+                    let t = match *t {
+                        Some(ref t) => self.annotation(t),
+                        None        => self.fresh(),
+                    };
+
+                    self.define(name, Scheme::mono(t.clone()));
+                    params.push(t)
+                }
+
+                let body = self.expression(&lambda.body);
+
+                self.scopes.pop();
+
+                let body = body?;
+
+                if let Some(ref t) = lambda.t {
+                    let t = self.annotation(t);
+                    self.unify(&t, &body)?
+                }
+
+                Ok(Ty::Function(params, Rc::new(body)))
+            },
+
+            Expression::Array(ref content) => {
+                let t = self.fresh();
+
+                for element in content {
+                    let element = self.expression(element)?;
+                    self.unify(&t, &element)?
+                }
+
+                Ok(Ty::Array(Rc::new(t)))
+            },
+
+            Expression::Index(ref index) => {
+                let id = self.expression(&index.id)?;
+                let t  = self.fresh();
+
+                self.unify(&Ty::Array(Rc::new(t.clone())), &id)?;
+
+                let i = self.expression(&index.index)?;
+                let n = self.fresh_numeric();
+
+                self.unify(&n, &i)?;
+
+                Ok(t)
+            },
+
+            Expression::Field(ref field) => {
+                let object = self.expression(&field.object)?;
+
+                match self.prune(&object) {
+                    Ty::Any => Ok(Ty::Any),
+
+                    Ty::Module(ref module) => match self.lookup(&format!("{}.{}", module, field.name)).cloned() {
+                        Some(scheme) => Ok(self.instantiate(&scheme)),
+                        None         => Err(SemanticError::new(&format!("module {} has no member {}", module, field.name))),
+                    },
+
+                    t => Err(SemanticError::new(&format!("no field {} on `{}`", field.name, self.resolve(&t)))),
+                }
+            },
+
+            Expression::If(ref branch) => {
+                let condition = self.expression(&branch.condition)?;
+                self.unify(&Ty::Prim(Type::Bool), &condition)?;
+
+                let body = self.expression(&branch.body)?;
+
+                match branch.otherwise {
+                    Some(ref otherwise) => {
+                        let otherwise = self.expression(otherwise)?;
+                        self.unify(&body, &otherwise)?;
+
+                        Ok(body)
+                    },
+
+                    None => Ok(Ty::Unit),
+                }
+            },
+
+            Expression::Match(ref subject) => {
+                let t    = self.expression(&subject.subject)?;
+                let body = self.fresh();
+
+                for arm in &subject.arms {
+                    self.scopes.push(HashMap::new());
+                    let result = self.arm(arm, &t, &body);
+                    self.scopes.pop();
+
+                    result.map_err(|e| Self::locate(e, arm.position))?
+                }
+
+                if let Some(witness) = exhaustive::missing(&subject.arms) {
+                    return Err(SemanticError::new_pos(subject.position, &format!("non-exhaustive match, `{}` not covered", witness)))
+                }
+
+                Ok(body)
+            },
+
+            Expression::EOF => Ok(Ty::Unit),
+        }
+    }
+
+    fn arm(&mut self, arm: &Arm, subject: &Ty, body: &Ty) -> SemanticResult<()> {
+        self.pattern(&arm.pattern, subject)?;
+
+        if let Some(ref guard) = arm.guard {
+            let guard = self.expression(guard)?;
+            self.unify(&Ty::Prim(Type::Bool), &guard)?
+        }
+
+        let t = self.expression(&arm.body)?;
+        self.unify(body, &t)
+    }
+
+    fn pattern(&mut self, pattern: &Pattern, t: &Ty) -> SemanticResult<()> {
+        match *pattern {
+            Pattern::Wildcard => Ok(()),
+
+            Pattern::Binding(ref name) => {
+                self.define(name, Scheme::mono(t.clone()));
+                Ok(())
+            },
+
+            Pattern::Number(_) => {
+                let n = self.fresh_numeric();
+                self.unify(&n, t)
+            },
+
+            Pattern::Bool(_) => self.unify(&Ty::Prim(Type::Bool), t),
+            Pattern::Str(_)  => self.unify(&Ty::Prim(Type::Str), t),
+            Pattern::Char(_) => self.unify(&Ty::Prim(Type::Char), t),
+
+            Pattern::Array(ref elements, ref rest) => {
+                let element = self.fresh();
+                self.unify(&Ty::Array(Rc::new(element.clone())), t)?;
+
+                for pattern in elements {
+                    self.pattern(pattern, &element)?
+                }
+
+                match *rest {
+                    Some(ref rest) => self.pattern(rest, t),
+                    None           => Ok(()),
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,14 @@
+pub mod error;
+pub mod exhaustive;
+pub mod infer;
+pub mod mutability;
+pub mod resolver;
+
+pub use super::*;
+
+pub use self::error::*;
+pub use self::infer::*;
+pub use self::mutability::*;
+pub use self::resolver::*;
+
+pub type SemanticResult<T> = Result<T, SemanticError>;
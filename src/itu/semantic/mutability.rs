@@ -0,0 +1,241 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutability {
+    Immutable,
+    Mutable,
+    Parameter,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    mutability: Mutability,
+    position:   Option<TokenPosition>,
+}
+
+pub struct MutabilityChecker {
+    scopes: Vec<HashMap<Rc<String>, Binding>>,
+}
+
+#[allow(dead_code)]
+impl MutabilityChecker {
+    pub fn new() -> MutabilityChecker {
+        MutabilityChecker {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn check(&mut self, ast: &[Statement]) -> SemanticResult<()> {
+        for statement in ast {
+            self.statement(statement)?
+        }
+
+        Ok(())
+    }
+
+    fn bind(&mut self, name: Rc<String>, mutability: Mutability, position: Option<TokenPosition>) {
+        self.scopes.last_mut().unwrap().insert(name, Binding { mutability, position });
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(binding) = scope.get(&name.to_owned()) {
+                return Some(binding)
+            }
+        }
+        None
+    }
+
+    fn statement(&mut self, statement: &Statement) -> SemanticResult<()> {
+        match *statement {
+            Statement::Expression(ref expression, _) => self.expression(expression),
+
+            Statement::Assignment(ref assignment) => {
+                self.expression(&assignment.right)?;
+                self.target(&assignment.left, false, assignment.position)
+            },
+
+            Statement::Definition(ref definition) => {
+                let name = match *definition.name {
+                    Expression::Identifier(ref name) => name.clone(),
+                    _ => return Err(SemanticError::new_pos(definition.position, "expected name")),
+                };
+
+                let mutability = match definition.t {
+                    Some(Type::Mut(_)) => Mutability::Mutable,
+                    _                  => Mutability::Immutable,
+                };
+
+                if let Some(ref right) = definition.right {
+                    match **right {
+                        Expression::Lambda(ref lambda) => self.lambda(lambda, Some(definition.position))?,
+                        ref right                      => self.expression(right)?,
+                    }
+                }
+
+                self.bind(name, mutability, Some(definition.position));
+
+                Ok(())
+            },
+
+            Statement::Test(ref test) => self.expression(&test.body),
+            Statement::Import(_)      => Ok(()),
+        }
+    }
+
+    // checks that whatever `left` ends up naming may be written to
+    fn target(&self, left: &Expression, element: bool, position: TokenPosition) -> SemanticResult<()> {
+        match *left {
+            Expression::Identifier(ref name) => {
+                let binding = match self.lookup(name) {
+                    Some(binding) => binding,
+                    None          => return Ok(()),
+                };
+
+                let error = match binding.mutability {
+                    Mutability::Mutable   => return Ok(()),
+                    Mutability::Immutable => {
+                        let error = if element {
+                            SemanticError::new_pos(position, &format!("cannot mutate element of immutable array `{}`", name))
+                        } else {
+                            SemanticError::new_pos(position, &format!("cannot assign twice to immutable binding `{}`", name))
+                        };
+
+                        match binding.position {
+                            Some(p) => error.with_note(p, &format!("`{}` defined here, consider declaring it `mut`", name)),
+                            None    => error,
+                        }
+                    },
+                    Mutability::Parameter => {
+                        let error = SemanticError::new_pos(position, &format!("cannot assign to parameter `{}`", name));
+
+                        match binding.position {
+                            Some(p) => error.with_note(p, &format!("`{}` is a parameter of the lambda defined here", name)),
+                            None    => error,
+                        }
+                    },
+                };
+
+                Err(error)
+            },
+
+            Expression::Index(ref index) => {
+                self.target(&index.id, true, position)
+            },
+
+            _ => Err(SemanticError::new_pos(position, "invalid left-hand side of assignment")),
+        }
+    }
+
+    fn lambda(&mut self, lambda: &Lambda, position: Option<TokenPosition>) -> SemanticResult<()> {
+        self.scopes.push(HashMap::new());
+
+        for (_, name) in &lambda.params {
+            self.bind(name.clone(), Mutability::Parameter, position)
+        }
+
+        let result = self.expression(&lambda.body);
+
+        self.scopes.pop();
+
+        result
+    }
+
+    fn expression(&mut self, expression: &Expression) -> SemanticResult<()> {
+        match *expression {
+            Expression::Block(ref statements) => {
+                self.scopes.push(HashMap::new());
+
+                let result = self.check(statements);
+
+                self.scopes.pop();
+
+                result
+            },
+
+            Expression::Operation(ref operation) => {
+                self.expression(&operation.left)?;
+                self.expression(&operation.right)
+            },
+
+            Expression::Call(ref call) => {
+                self.expression(&call.callee)?;
+
+                for arg in &call.args {
+                    self.expression(arg)?
+                }
+
+                Ok(())
+            },
+
+            Expression::Lambda(ref lambda) => self.lambda(lambda, None),
+
+            Expression::Array(ref content) => {
+                for element in content {
+                    self.expression(element)?
+                }
+
+                Ok(())
+            },
+
+            Expression::Index(ref index) => {
+                self.expression(&index.id)?;
+                self.expression(&index.index)
+            },
+
+            Expression::Field(ref field) => self.expression(&field.object),
+
+            Expression::If(ref branch) => {
+                self.expression(&branch.condition)?;
+                self.expression(&branch.body)?;
+
+                match branch.otherwise {
+                    Some(ref otherwise) => self.expression(otherwise),
+                    None                => Ok(()),
+                }
+            },
+
+            Expression::Match(ref subject) => {
+                self.expression(&subject.subject)?;
+
+                for arm in &subject.arms {
+                    self.scopes.push(HashMap::new());
+                    self.pattern(&arm.pattern, arm.position);
+
+                    let result = arm.guard.as_ref().map_or(Ok(()), |guard| self.expression(guard))
+                        .and_then(|_| self.expression(&arm.body));
+
+                    self.scopes.pop();
+
+                    result?
+                }
+
+                Ok(())
+            },
+
+            _ => Ok(()),
+        }
+    }
+
+    // what a pattern binds is as immutable as a definition without `mut`
+    fn pattern(&mut self, pattern: &Pattern, position: TokenPosition) {
+        match *pattern {
+            Pattern::Binding(ref name) => self.bind(name.clone(), Mutability::Immutable, Some(position)),
+
+            Pattern::Array(ref elements, ref rest) => {
+                for element in elements {
+                    self.pattern(element, position)
+                }
+
+                if let Some(ref rest) = *rest {
+                    self.pattern(rest, position)
+                }
+            },
+
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Builtin,
+    Definition,
+    Parameter,
+    // a name bound by a pattern of a match arm
+    Binding,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name:     Rc<String>,
+    pub kind:     SymbolKind,
+    pub position: Option<TokenPosition>,
+    pub uses:     usize,
+}
+
+// what the resolver found, identifiers are keyed by the address of their
+// node so the map stays valid for as long as the ast it was built from
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub symbols:      Vec<Symbol>,
+    pub references:   HashMap<*const Expression, usize>,
+    pub declarations: HashMap<*const Expression, usize>,
+    pub parameters:   HashMap<*const Rc<String>, usize>,
+    // a binding and the earlier one it hides
+    pub shadows:      Vec<(usize, usize)>,
+    pub errors:       Vec<SemanticError>,
+    pub warnings:     Vec<SemanticError>,
+}
+
+#[allow(dead_code)]
+impl Resolution {
+    pub fn symbol(&self, expression: &Expression) -> Option<&Symbol> {
+        let key = expression as *const Expression;
+
+        self.references.get(&key)
+            .or_else(|| self.declarations.get(&key))
+            .map(|&id| &self.symbols[id])
+    }
+}
+
+pub struct Resolver {
+    resolution: Resolution,
+    scopes:     Vec<HashMap<Rc<String>, usize>>,
+    position:   Option<TokenPosition>,
+}
+
+#[allow(dead_code)]
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver {
+            resolution: Resolution::default(),
+            scopes:     vec![HashMap::new()],
+            position:   None,
+        }
+    }
+
+    pub fn define(&mut self, name: &str) {
+        self.declare(Rc::new(name.to_owned()), SymbolKind::Builtin, None);
+    }
+
+    pub fn resolve(mut self, ast: &[Statement]) -> Resolution {
+        // builtins live in a scope of their own so definitions may shadow them quietly
+        self.scopes.push(HashMap::new());
+
+        // top-level functions may refer to each other regardless of order
+        for statement in ast {
+            if let Statement::Definition(ref definition) = *statement {
+                if let Some(ref right) = definition.right {
+                    if let (Expression::Identifier(name), Expression::Lambda(_)) = (&*definition.name, &**right) {
+                        if !self.scopes.last().unwrap().contains_key(name) {
+                            self.declare(name.clone(), SymbolKind::Definition, Some(definition.position));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.statements(ast);
+
+        self.resolution
+    }
+
+    fn declare(&mut self, name: Rc<String>, kind: SymbolKind, position: Option<TokenPosition>) -> usize {
+        let id = self.resolution.symbols.len();
+
+        self.resolution.symbols.push(Symbol { name: name.clone(), kind, position, uses: 0 });
+        self.scopes.last_mut().unwrap().insert(name, id);
+
+        id
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(&id) = scope.get(&name.to_owned()) {
+                return Some(id)
+            }
+        }
+        None
+    }
+
+    fn warn(&mut self, position: Option<TokenPosition>, message: &str, note: Option<(TokenPosition, String)>) {
+        let warning = match position {
+            Some(p) => SemanticError::new_pos(p, message),
+            None    => SemanticError::new(message),
+        };
+
+        self.resolution.warnings.push(match note {
+            Some((p, ref note)) => warning.with_note(p, note),
+            None                => warning,
+        })
+    }
+
+    fn bind(&mut self, node: &Expression, name: Rc<String>, kind: SymbolKind, position: Option<TokenPosition>) -> usize {
+        // the pre-declared top-level functions are bound as they are reached
+        if let Some(&id) = self.scopes.last().unwrap().get(&name) {
+            let symbol = &self.resolution.symbols[id];
+
+            if self.scopes.len() == 2 && symbol.position == position && symbol.kind == kind {
+                self.resolution.declarations.insert(node as *const Expression, id);
+                return id
+            }
+        }
+
+        let shadowed = self.lookup(&name).filter(|&id| self.resolution.symbols[id].kind != SymbolKind::Builtin);
+
+        if let Some(earlier) = shadowed {
+            let note = self.resolution.symbols[earlier].position.map(|p| (p, format!("previous binding of `{}` here", name)));
+            self.warn(position, &format!("`{}` shadows an earlier binding", name), note)
+        }
+
+        let id = self.declare(name, kind, position);
+        self.resolution.declarations.insert(node as *const Expression, id);
+
+        if let Some(earlier) = shadowed {
+            self.resolution.shadows.push((id, earlier))
+        }
+
+        id
+    }
+
+    fn enter(&mut self) {
+        self.scopes.push(HashMap::new())
+    }
+
+    fn leave(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+
+        let mut unused = scope.values()
+            .map(|&id| self.resolution.symbols[id].clone())
+            .filter(|symbol| symbol.uses == 0 && !symbol.name.starts_with('_'))
+            .collect::<Vec<_>>();
+
+        unused.sort_by_key(|symbol| symbol.position.map(|p| (p.line, p.col)));
+
+        for symbol in unused {
+            let message = match symbol.kind {
+                SymbolKind::Parameter => format!("unused parameter `{}`", symbol.name),
+                _                     => format!("unused binding `{}`", symbol.name),
+            };
+
+            self.warn(symbol.position, &message, None)
+        }
+    }
+
+    fn reference(&mut self, node: &Expression, name: &Rc<String>) {
+        match self.lookup(name) {
+            Some(id) => {
+                self.resolution.symbols[id].uses += 1;
+                self.resolution.references.insert(node as *const Expression, id);
+            },
+
+            None => {
+                let mut message = format!("undefined name `{}`", name);
+
+                if let Some(suggestion) = self.suggest(name) {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion))
+                }
+
+                self.resolution.errors.push(match self.position {
+                    Some(p) => SemanticError::new_pos(p, &message),
+                    None    => SemanticError::new(&message),
+                })
+            },
+        }
+    }
+
+    fn suggest(&self, name: &str) -> Option<Rc<String>> {
+        let limit = ::std::cmp::max(1, name.chars().count().div_ceil(3));
+
+        self.scopes.iter()
+            .flat_map(|scope| scope.keys())
+            .map(|candidate| (distance(name, candidate), candidate))
+            .filter(|&(d, _)| d <= limit)
+            .min_by_key(|&(d, _)| d)
+            .map(|(_, candidate)| candidate.clone())
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement)
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let outer = self.position.replace(statement.position());
+
+        match *statement {
+            Statement::Expression(ref expression, _) => self.expression(expression),
+
+            Statement::Assignment(ref assignment) => {
+                self.expression(&assignment.right);
+                self.expression(&assignment.left);
+            },
+
+            Statement::Definition(ref definition) => if let Expression::Identifier(ref name) = *definition.name {
+                let name = name.clone();
+
+                match definition.right {
+                    Some(ref right) => if let Expression::Lambda(ref lambda) = **right {
+                        // bound first, a lambda may call itself
+                        self.bind(&definition.name, name, SymbolKind::Definition, Some(definition.position));
+                        self.lambda(lambda)
+                    } else {
+                        self.expression(right);
+                        self.bind(&definition.name, name, SymbolKind::Definition, Some(definition.position));
+                    },
+
+                    None => {
+                        self.bind(&definition.name, name, SymbolKind::Definition, Some(definition.position));
+                    },
+                }
+            },
+
+            Statement::Test(ref test) => self.expression(&test.body),
+
+            // the module was defined along with the builtins
+            Statement::Import(_) => (),
+        }
+
+        self.position = outer;
+    }
+
+    fn lambda(&mut self, lambda: &Lambda) {
+        self.enter();
+
+        for (_, name) in &lambda.params {
+            let id = self.resolution.symbols.len();
+            self.resolution.symbols.push(Symbol { name: name.clone(), kind: SymbolKind::Parameter, position: self.position, uses: 0 });
+            self.resolution.parameters.insert(name as *const Rc<String>, id);
+
+            if self.scopes.last_mut().unwrap().insert(name.clone(), id).is_some() {
+                let message = format!("parameter `{}` is bound more than once", name);
+
+                self.resolution.errors.push(match self.position {
+                    Some(p) => SemanticError::new_pos(p, &message),
+                    None    => SemanticError::new(&message),
+                })
+            }
+        }
+
+        self.expression(&lambda.body);
+
+        self.leave()
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match *expression {
+            Expression::Block(ref statements) => {
+                self.enter();
+                self.statements(statements);
+                self.leave()
+            },
+
+            Expression::Identifier(ref name) => self.reference(expression, name),
+
+            Expression::Operation(ref operation) => {
+                self.expression(&operation.left);
+                self.expression(&operation.right)
+            },
+
+            Expression::Call(ref call) => {
+                self.expression(&call.callee);
+
+                for arg in &call.args {
+                    self.expression(arg)
+                }
+            },
+
+            Expression::Lambda(ref lambda) => self.lambda(lambda),
+
+            Expression::Array(ref content) => {
+                for element in content {
+                    self.expression(element)
+                }
+            },
+
+            Expression::Index(ref index) => {
+                self.expression(&index.id);
+                self.expression(&index.index)
+            },
+
+            Expression::Field(ref field) => self.expression(&field.object),
+
+            Expression::If(ref branch) => {
+                self.expression(&branch.condition);
+                self.expression(&branch.body);
+
+                if let Some(ref otherwise) = branch.otherwise {
+                    self.expression(otherwise)
+                }
+            },
+
+            Expression::Match(ref subject) => {
+                self.expression(&subject.subject);
+
+                for arm in &subject.arms {
+                    let outer = self.position.replace(arm.position);
+
+                    self.enter();
+                    self.pattern(&arm.pattern);
+
+                    if let Some(ref guard) = arm.guard {
+                        self.expression(guard)
+                    }
+
+                    self.expression(&arm.body);
+                    self.leave();
+
+                    self.position = outer;
+                }
+            },
+
+            _ => (),
+        }
+    }
+
+    // the names of a pattern are bound in the scope of its arm, keyed like
+    // parameters by the address of the name
+    fn pattern(&mut self, pattern: &Pattern) {
+        match *pattern {
+            Pattern::Binding(ref name) => {
+                let id = self.resolution.symbols.len();
+                self.resolution.symbols.push(Symbol { name: name.clone(), kind: SymbolKind::Binding, position: self.position, uses: 0 });
+                self.resolution.parameters.insert(name as *const Rc<String>, id);
+
+                if self.scopes.last_mut().unwrap().insert(name.clone(), id).is_some() {
+                    let message = format!("`{}` is bound more than once in a pattern", name);
+
+                    self.resolution.errors.push(match self.position {
+                        Some(p) => SemanticError::new_pos(p, &message),
+                        None    => SemanticError::new(&message),
+                    })
+                }
+            },
+
+            Pattern::Array(ref elements, ref rest) => {
+                for element in elements {
+                    self.pattern(element)
+                }
+
+                if let Some(ref rest) = *rest {
+                    self.pattern(rest)
+                }
+            },
+
+            _ => (),
+        }
+    }
+}
+
+// levenshtein distance between two names
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut row = (0 ..= b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + ::std::cmp::min(previous, ::std::cmp::min(row[j], row[j + 1]))
+            };
+
+            previous = current
+        }
+    }
+
+    row[b.len()]
+}
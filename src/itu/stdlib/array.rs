@@ -0,0 +1,69 @@
+use super::*;
+
+fn push(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    array(&args[0])?.borrow_mut().push(args[1].clone());
+    Ok(Value::Unit)
+}
+
+fn pop(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    match array(&args[0])?.borrow_mut().pop() {
+        Some(value) => Ok(value),
+        None        => Err(RuntimeError::new("pop from empty array")),
+    }
+}
+
+fn len(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    Ok(Value::Number(array(&args[0])?.borrow().len() as f64))
+}
+
+// the elements are copied out first, `f` may well touch the array itself
+fn map(evaluator: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let content = array(&args[0])?.borrow().clone();
+    let mut result = Vec::new();
+
+    for value in content {
+        result.push(evaluator.call(&args[1], vec![value])?)
+    }
+
+    Ok(Value::array(result))
+}
+
+fn filter(evaluator: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let content = array(&args[0])?.borrow().clone();
+    let mut result = Vec::new();
+
+    for value in content {
+        match evaluator.call(&args[1], vec![value.clone()])? {
+            Value::Bool(true)  => result.push(value),
+            Value::Bool(false) => (),
+            ref v              => return Err(RuntimeError::new(&format!("expected bool from filter, found {}", v.type_name()))),
+        }
+    }
+
+    Ok(Value::array(result))
+}
+
+fn fold(evaluator: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let content = array(&args[0])?.borrow().clone();
+    let mut acc = args[1].clone();
+
+    for value in content {
+        acc = evaluator.call(&args[2], vec![acc, value])?
+    }
+
+    Ok(acc)
+}
+
+pub fn library() -> Library {
+    Library {
+        name: "array",
+        members: vec![
+            function("push",   "<a>([a], a): ()",               2, push),
+            function("pop",    "<a>([a]): a",                   1, pop),
+            function("len",    "<a>([a]): f64",                 1, len),
+            function("map",    "<a, b>([a], (a): b): [b]",      2, map),
+            function("filter", "<a>([a], (a): bool): [a]",      2, filter),
+            function("fold",   "<a, b>([a], b, (b, a): b): b",  3, fold),
+        ],
+    }
+}
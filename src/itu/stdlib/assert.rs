@@ -0,0 +1,77 @@
+use super::*;
+
+fn assert(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    match args[0] {
+        Value::Bool(true)  => Ok(Value::Unit),
+        Value::Bool(false) => Err(RuntimeError::new("assertion failed")),
+        ref v              => Err(RuntimeError::new(&format!("expected bool, found {}", v.type_name()))),
+    }
+}
+
+fn assert_eq(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    if args[0] == args[1] {
+        return Ok(Value::Unit)
+    }
+
+    let message = format!("assertion failed: {} != {}", args[0].repr(), args[1].repr());
+
+    // a difference at the top is the message itself
+    match difference(&args[0], &args[1], String::new()) {
+        Some((ref path, ref found)) if !path.is_empty() => {
+            Err(RuntimeError::new(&format!("{}, first difference at {}: {}", message, path, found)))
+        },
+        _ => Err(RuntimeError::new(&message)),
+    }
+}
+
+// where two unequal values first part ways, as the path of indices down to
+// it and what is found there on either side
+fn difference(left: &Value, right: &Value, path: String) -> Option<(String, String)> {
+    match (left, right) {
+        (Value::Array(a), Value::Array(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+
+            for (i, (left, right)) in a.iter().zip(b.iter()).enumerate() {
+                if let Some(difference) = difference(left, right, format!("{}[{}]", path, i)) {
+                    return Some(difference)
+                }
+            }
+
+            if a.len() != b.len() {
+                return Some((path, format!("length {} != {}", a.len(), b.len())))
+            }
+
+            None
+        },
+
+        // strings by the first character they disagree on
+        (Value::Str(a), Value::Str(b)) if a != b => {
+            let mut a = a.chars();
+            let mut b = b.chars();
+            let mut i = 0;
+
+            loop {
+                match (a.next(), b.next()) {
+                    (Some(x), Some(y)) if x == y => i += 1,
+                    (x, y)                       => {
+                        let show = |c: Option<char>| c.map_or("end of string".to_string(), |c| format!("{:?}", c));
+
+                        return Some((format!("{}[{}]", path, i), format!("{} != {}", show(x), show(y))))
+                    },
+                }
+            }
+        },
+
+        (left, right) if left != right => Some((path, format!("{} != {}", left.repr(), right.repr()))),
+
+        _ => None,
+    }
+}
+
+// functions bound as globals of their own rather than members of a module
+pub fn prelude() -> Vec<Member> {
+    vec![
+        function("assert",    "(bool): ()",   1, assert),
+        function("assert_eq", "<a>(a, a): ()", 2, assert_eq),
+    ]
+}
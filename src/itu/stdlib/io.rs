@@ -0,0 +1,55 @@
+use std::rc::Rc;
+use std::fs;
+use std::io::{self, BufRead};
+
+use super::*;
+
+fn print(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    println!("{}", args[0]);
+    Ok(Value::Unit)
+}
+
+fn read_line(_: &mut dyn Caller, _: Vec<Value>) -> RuntimeResult<Value> {
+    let mut line = String::new();
+
+    if let Err(why) = io::stdin().lock().read_line(&mut line) {
+        return Err(RuntimeError::new(&format!("unable to read line: {}", why)))
+    }
+
+    while line.ends_with('\n') || line.ends_with('\r') {
+        line.pop();
+    }
+
+    Ok(Value::Str(Rc::new(line)))
+}
+
+fn read_file(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let path = string(&args[0])?;
+
+    match fs::read_to_string(path.as_str()) {
+        Ok(content) => Ok(Value::Str(Rc::new(content))),
+        Err(why)    => Err(RuntimeError::new(&format!("unable to read {}: {}", path, why))),
+    }
+}
+
+fn write_file(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let path    = string(&args[0])?;
+    let content = string(&args[1])?;
+
+    match fs::write(path.as_str(), content.as_bytes()) {
+        Ok(_)    => Ok(Value::Unit),
+        Err(why) => Err(RuntimeError::new(&format!("unable to write {}: {}", path, why))),
+    }
+}
+
+pub fn library() -> Library {
+    Library {
+        name: "io",
+        members: vec![
+            function("print",      "<a>(a): ()",      1, print),
+            function("read_line",  "(): str",         0, read_line),
+            function("read_file",  "(str): str",      1, read_file),
+            function("write_file", "(str, str): ()",  2, write_file),
+        ],
+    }
+}
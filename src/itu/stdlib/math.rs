@@ -0,0 +1,41 @@
+use std::f64::consts;
+
+use super::*;
+
+macro_rules! unary {
+    ($name:expr, $f:path) => {
+        function($name, "<a: num>(a): a", 1, |_, args| Ok(Value::Number($f(number(&args[0])?))))
+    };
+}
+
+macro_rules! binary {
+    ($name:expr, $f:path) => {
+        function($name, "<a: num>(a, a): a", 2, |_, args| Ok(Value::Number($f(number(&args[0])?, number(&args[1])?))))
+    };
+}
+
+pub fn library() -> Library {
+    Library {
+        name: "math",
+        members: vec![
+            constant("pi",  "f64", Value::Number(consts::PI)),
+            constant("tau", "f64", Value::Number(consts::TAU)),
+            constant("e",   "f64", Value::Number(consts::E)),
+
+            unary!("sqrt",  f64::sqrt),
+            unary!("abs",   f64::abs),
+            unary!("floor", f64::floor),
+            unary!("ceil",  f64::ceil),
+            unary!("round", f64::round),
+            unary!("sin",   f64::sin),
+            unary!("cos",   f64::cos),
+            unary!("tan",   f64::tan),
+            unary!("exp",   f64::exp),
+            unary!("ln",    f64::ln),
+
+            binary!("pow", f64::powf),
+            binary!("min", f64::min),
+            binary!("max", f64::max),
+        ],
+    }
+}
@@ -0,0 +1,134 @@
+pub mod math;
+pub mod array;
+pub mod string;
+pub mod io;
+pub mod assert;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::*;
+
+// a member of a built-in module, with the signature the type checker sees
+pub struct Member {
+    pub name:      &'static str,
+    pub signature: &'static str,
+    pub value:     Value,
+}
+
+pub struct Library {
+    pub name:    &'static str,
+    pub members: Vec<Member>,
+}
+
+pub fn function(name: &'static str, signature: &'static str, arity: usize, function: BuiltinFunction) -> Member {
+    let builtin = Builtin {
+        name: Rc::new(name.to_owned()),
+        arity,
+        function,
+    };
+
+    Member {
+        name,
+        signature,
+        value: Value::Builtin(Rc::new(builtin)),
+    }
+}
+
+pub fn constant(name: &'static str, signature: &'static str, value: Value) -> Member {
+    Member {
+        name,
+        signature,
+        value,
+    }
+}
+
+pub fn libraries() -> Vec<Library> {
+    vec![
+        math::library(),
+        array::library(),
+        string::library(),
+        io::library(),
+    ]
+}
+
+// every library as a module value, keyed by the global it is bound to
+pub fn modules() -> Vec<(Rc<String>, Value)> {
+    libraries().into_iter()
+        .map(|library| {
+            let name    = Rc::new(library.name.to_owned());
+            let members = library.members.into_iter()
+                .map(|member| (Rc::new(member.name.to_owned()), member.value))
+                .collect::<HashMap<_, _>>();
+
+            (name.clone(), Value::Module(Rc::new(Module { name, members })))
+        })
+        .collect()
+}
+
+// every global a program starts out with, the modules and the prelude
+pub fn globals() -> Vec<(Rc<String>, Value)> {
+    let mut globals = modules();
+
+    for member in assert::prelude() {
+        globals.push((Rc::new(member.name.to_owned()), member.value))
+    }
+
+    globals
+}
+
+// binds every global of the evaluator
+pub fn register(evaluator: &Evaluator) {
+    for (name, value) in globals() {
+        evaluator.globals.define(name, value)
+    }
+}
+
+// tells the type checker about every module and the types of their members
+pub fn declare(inferer: &mut Inferer) {
+    for library in libraries() {
+        inferer.define(library.name, Scheme::mono(Ty::Module(Rc::new(library.name.to_owned()))));
+
+        for member in library.members {
+            let scheme = inferer.signature(member.signature);
+            inferer.define(&format!("{}.{}", library.name, member.name), scheme)
+        }
+    }
+
+    for member in assert::prelude() {
+        let scheme = inferer.signature(member.signature);
+        inferer.define(member.name, scheme)
+    }
+}
+
+pub fn declare_names(resolver: &mut Resolver) {
+    for library in libraries() {
+        resolver.define(library.name)
+    }
+
+    for member in assert::prelude() {
+        resolver.define(member.name)
+    }
+}
+
+pub fn number(value: &Value) -> RuntimeResult<f64> {
+    match *value {
+        Value::Number(n) => Ok(n),
+        ref v            => Err(RuntimeError::new(&format!("expected number, found {}", v.type_name()))),
+    }
+}
+
+pub fn string(value: &Value) -> RuntimeResult<Rc<String>> {
+    match *value {
+        Value::Str(ref s) => Ok(s.clone()),
+        ref v             => Err(RuntimeError::new(&format!("expected str, found {}", v.type_name()))),
+    }
+}
+
+pub fn array(value: &Value) -> RuntimeResult<Rc<RefCell<Vec<Value>>>> {
+    match *value {
+        Value::Array(ref a) => Ok(a.clone()),
+        ref v               => Err(RuntimeError::new(&format!("expected array, found {}", v.type_name()))),
+    }
+}
@@ -0,0 +1,59 @@
+use std::rc::Rc;
+
+use super::*;
+
+fn split(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let s   = string(&args[0])?;
+    let sep = string(&args[1])?;
+
+    Ok(Value::array(s.split(sep.as_str()).map(|part| Value::Str(Rc::new(part.to_owned()))).collect()))
+}
+
+fn join(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let parts = array(&args[0])?.borrow().iter().map(|v| format!("{}", v)).collect::<Vec<_>>();
+    let sep   = string(&args[1])?;
+
+    Ok(Value::Str(Rc::new(parts.join(sep.as_str()))))
+}
+
+fn trim(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    Ok(Value::Str(Rc::new(string(&args[0])?.trim().to_owned())))
+}
+
+// replaces each `{}` in turn with the next value of the array
+fn format(_: &mut dyn Caller, args: Vec<Value>) -> RuntimeResult<Value> {
+    let template = string(&args[0])?;
+    let values   = array(&args[1])?;
+    let values   = values.borrow();
+
+    let mut result = String::new();
+    let mut pieces = template.split("{}");
+    let mut values = values.iter();
+
+    if let Some(piece) = pieces.next() {
+        result.push_str(piece)
+    }
+
+    for piece in pieces {
+        match values.next() {
+            Some(value) => result.push_str(&format!("{}", value)),
+            None        => return Err(RuntimeError::new("too few values for format string")),
+        }
+
+        result.push_str(piece)
+    }
+
+    Ok(Value::Str(Rc::new(result)))
+}
+
+pub fn library() -> Library {
+    Library {
+        name: "str",
+        members: vec![
+            function("split",  "(str, str): [str]",  2, split),
+            function("join",   "([str], str): str",  2, join),
+            function("trim",   "(str): str",         1, trim),
+            function("format", "<a>(str, [a]): str", 2, format),
+        ],
+    }
+}
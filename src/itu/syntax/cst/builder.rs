@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::*;
+
+// the lossless tree of a source, its text given back character for
+// character, statements found by the parser itself and nested as it nested
+// them, what it could not parse left in an error node at the end
+pub fn cst(source: &str) -> SyntaxNode {
+    let chars = source.chars().collect::<Vec<_>>();
+
+    let mut lines = vec![0];
+
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '\n' {
+            lines.push(i + 1)
+        }
+    }
+
+    let offset = |position: TokenPosition| lines[position.line - 1] + position.col;
+
+    let mut lexer  = lexer(&mut source.chars()).with_trivia();
+    let lexed      = lexer.by_ref().collect::<Vec<_>>();
+    let stop       = if lexer.end() { chars.len() } else { offset(lexer.position()) };
+
+    // a token's text runs up to where the next one starts
+    let mut tokens = Vec::new();
+
+    for (i, token) in lexed.iter().enumerate() {
+        let end  = lexed.get(i + 1).map_or(stop, |next| offset(next.position));
+        let text = chars[offset(token.position) .. end].iter().collect::<String>();
+
+        tokens.push(Rc::new(GreenToken::new(token.token_type.clone(), text, token.content().clone())))
+    }
+
+    let significant = lexed.iter().enumerate()
+        .filter(|&(_, token)| !token.token_type.trivia())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let mut parser = Parser::new(Traveler::new(significant.iter().map(|&i| lexed[i].clone()).collect()));
+
+    let failed = loop {
+        let top = parser.top();
+
+        match parser.next_statement() {
+            Ok(Some(_)) => continue,
+            Ok(None)    => break None,
+            Err(_)      => break Some(top),
+        }
+    };
+
+    // the error node takes over from the first token the parser tripped on
+    let boundary = match failed {
+        Some(top) => significant[top ..].iter().cloned()
+            .find(|&i| lexed[i].token_type != TokenType::EOL && lexed[i].token_type != TokenType::Indent)
+            .unwrap_or(lexed.len()),
+        None      => lexed.len(),
+    };
+
+    let index = lexed.iter().enumerate()
+        .map(|(i, token)| ((token.position.line, token.position.col), i))
+        .collect::<HashMap<_, _>>();
+
+    let spans = parser.starts().iter().zip(parser.ends().iter())
+        .map(|(start, end)| {
+            let start = index[&(start.line, start.col)];
+            (start, ::std::cmp::max(start, index[&(end.line, end.col)]))
+        })
+        .filter(|&(start, _)| start < boundary)
+        .collect::<Vec<_>>();
+
+    let mut builder = Builder {
+        tokens,
+        spans,
+        at:   0,
+        next: 0,
+    };
+
+    let mut children = builder.statements(boundary, false);
+
+    let mut rest = builder.tokens[boundary ..].iter().cloned().map(GreenElement::Token).collect::<Vec<_>>();
+
+    if stop < chars.len() {
+        let text = chars[stop ..].iter().collect::<String>();
+        rest.push(GreenElement::Token(Rc::new(GreenToken::new(TokenType::Unknown, text.clone(), text))))
+    }
+
+    if !rest.is_empty() {
+        children.push(GreenElement::Node(Rc::new(GreenNode::new(SyntaxKind::Error, rest))))
+    }
+
+    SyntaxNode::new_root(Rc::new(GreenNode::new(SyntaxKind::Root, children)))
+}
+
+struct Builder {
+    tokens: Vec<Rc<GreenToken>>,
+    // first and last token of every statement, outer ones before the ones
+    // nested in them
+    spans:  Vec<(usize, usize)>,
+    at:     usize,
+    next:   usize,
+}
+
+impl Builder {
+    fn token(&mut self) -> GreenElement {
+        self.at += 1;
+        GreenElement::Token(self.tokens[self.at - 1].clone())
+    }
+
+    fn starts_statement(&mut self, end: usize) -> bool {
+        // a statement the tokens went past is no use anymore
+        while self.spans.get(self.next).is_some_and(|&(start, _)| start < self.at) {
+            self.next += 1
+        }
+
+        self.spans.get(self.next).is_some_and(|&(start, _)| start == self.at && start < end)
+    }
+
+    // the tokens up to `end`, statements made nodes of their own, a run of
+    // them with nothing but line breaks and trivia between made a block
+    fn statements(&mut self, end: usize, block: bool) -> Vec<GreenElement> {
+        let mut children = Vec::new();
+        let mut run      = Vec::new();
+        let mut pending  = Vec::new();
+
+        while self.at < end {
+            if self.starts_statement(end) {
+                run.append(&mut pending);
+                run.push(self.statement());
+
+                continue
+            }
+
+            let green = self.tokens[self.at].clone();
+            let token = self.token();
+
+            if green.trivia() || green.kind == TokenType::EOL || green.kind == TokenType::Indent {
+                pending.push(token)
+            } else {
+                Self::flush(&mut children, &mut run, block);
+                children.append(&mut pending);
+                children.push(token)
+            }
+        }
+
+        Self::flush(&mut children, &mut run, block);
+        children.append(&mut pending);
+
+        children
+    }
+
+    fn flush(children: &mut Vec<GreenElement>, run: &mut Vec<GreenElement>, block: bool) {
+        if run.is_empty() {
+            return
+        }
+
+        if block {
+            children.push(GreenElement::Node(Rc::new(GreenNode::new(SyntaxKind::Block, run.split_off(0)))))
+        } else {
+            children.append(run)
+        }
+    }
+
+    fn statement(&mut self) -> GreenElement {
+        let (_, last) = self.spans[self.next];
+        self.next += 1;
+
+        let children = self.statements(last + 1, true);
+
+        GreenElement::Node(Rc::new(GreenNode::new(SyntaxKind::Statement, Self::groups(children))))
+    }
+
+    // pairs up brackets among a statement's own tokens, an unpaired one is
+    // left as it is
+    fn groups(children: Vec<GreenElement>) -> Vec<GreenElement> {
+        let mut stack: Vec<(&str, Vec<GreenElement>)> = vec![("", Vec::new())];
+
+        for child in children {
+            let content = match child {
+                GreenElement::Token(ref token) if token.kind == TokenType::Symbol => token.content().clone(),
+                _                                                               => String::new(),
+            };
+
+            match content.as_str() {
+                "(" | "{" | "[" => {
+                    let close = match content.as_str() {
+                        "(" => ")",
+                        "{" => "}",
+                        _   => "]",
+                    };
+
+                    stack.push((close, vec![child]))
+                },
+
+                ")" | "}" | "]" if stack.iter().skip(1).any(|&(close, _)| close == content) => {
+                    // brackets opened inside and never closed stay loose
+                    while stack.last().is_some_and(|&(close, _)| close != content) {
+                        let (_, loose) = stack.pop().unwrap();
+                        stack.last_mut().unwrap().1.extend(loose)
+                    }
+
+                    let (_, mut group) = stack.pop().unwrap();
+                    group.push(child);
+
+                    stack.last_mut().unwrap().1.push(GreenElement::Node(Rc::new(GreenNode::new(SyntaxKind::Group, group))))
+                },
+
+                _ => stack.last_mut().unwrap().1.push(child),
+            }
+        }
+
+        while stack.len() > 1 {
+            let (_, loose) = stack.pop().unwrap();
+            stack.last_mut().unwrap().1.extend(loose)
+        }
+
+        stack.pop().unwrap().1
+    }
+}
+
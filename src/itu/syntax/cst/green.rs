@@ -0,0 +1,152 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::*;
+
+// what a node of the lossless tree stands for, its tokens say the rest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxKind {
+    Root,
+    Statement,
+    // statements indented under the one they belong to
+    Block,
+    // brackets and what is between them
+    Group,
+    // whatever comes from the point the parser gave up
+    Error,
+}
+
+// how much source a piece of the tree covers, in characters and in lines,
+// `tail` being the characters after its last line break
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub chars: usize,
+    pub lines: usize,
+    pub tail:  usize,
+}
+
+impl Span {
+    pub fn of(text: &str) -> Span {
+        let lines = text.matches('\n').count();
+        let tail  = text.rsplit('\n').next().map_or(0, |line| line.chars().count());
+
+        Span {
+            chars: text.chars().count(),
+            lines,
+            tail,
+        }
+    }
+
+    pub fn join(self, other: Span) -> Span {
+        Span {
+            chars: self.chars + other.chars,
+            lines: self.lines + other.lines,
+            tail:  if other.lines == 0 { self.tail + other.tail } else { other.tail },
+        }
+    }
+
+    // where something starting at `position` and covering this much ends
+    pub fn advance(&self, position: TokenPosition) -> TokenPosition {
+        if self.lines == 0 {
+            TokenPosition::new(position.line, position.col + self.tail)
+        } else {
+            TokenPosition::new(position.line + self.lines, self.tail)
+        }
+    }
+}
+
+// a token as it was written, `content` being what the lexer made of `text`,
+// green tokens know nothing of where they are and are shared freely
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenToken {
+    pub kind: TokenType,
+    text:     String,
+    content:  String,
+    span:     Span,
+}
+
+#[allow(dead_code)]
+impl GreenToken {
+    pub fn new(kind: TokenType, text: String, content: String) -> GreenToken {
+        GreenToken {
+            kind,
+            span: Span::of(&text),
+            text,
+            content,
+        }
+    }
+
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+
+    pub fn content(&self) -> &String {
+        &self.content
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    // whitespace and comments, which the parser never sees
+    pub fn trivia(&self) -> bool {
+        self.kind.trivia()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn span(&self) -> Span {
+        match *self {
+            GreenElement::Node(ref node)   => node.span(),
+            GreenElement::Token(ref token) => token.span(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenNode {
+    pub kind: SyntaxKind,
+    children: Vec<GreenElement>,
+    span:     Span,
+}
+
+#[allow(dead_code)]
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let span = children.iter().fold(Span::default(), |span, child| span.join(child.span()));
+
+        GreenNode {
+            kind,
+            children,
+            span,
+        }
+    }
+
+    pub fn children(&self) -> &Vec<GreenElement> {
+        &self.children
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+// the exact source the node was built from
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            match *child {
+                GreenElement::Node(ref node)   => write!(f, "{}", node)?,
+                GreenElement::Token(ref token) => write!(f, "{}", token.text)?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+pub mod green;
+pub mod red;
+pub mod builder;
+
+pub use super::*;
+
+pub use self::green::*;
+pub use self::red::*;
+pub use self::builder::*;
@@ -0,0 +1,260 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::*;
+
+// a green node put in place, knowing its parent and where it starts, made
+// on the way down and thrown away again as cheaply
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green:    Rc<GreenNode>,
+    parent:   Option<SyntaxNode>,
+    offset:   usize,
+    position: TokenPosition,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green:    Rc<GreenToken>,
+    parent:   SyntaxNode,
+    offset:   usize,
+    position: TokenPosition,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[allow(dead_code)]
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent:   None,
+            offset:   0,
+            position: TokenPosition::default(),
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    pub fn ancestors(&self) -> Vec<SyntaxNode> {
+        let mut ancestors = Vec::new();
+        let mut node      = self.parent();
+
+        while let Some(parent) = node {
+            ancestors.push(parent.clone());
+            node = parent.parent()
+        }
+
+        ancestors
+    }
+
+    // characters into the source, the end excluded
+    pub fn offset(&self) -> usize {
+        self.0.offset
+    }
+
+    pub fn end(&self) -> usize {
+        self.0.offset + self.0.green.span().chars
+    }
+
+    pub fn position(&self) -> TokenPosition {
+        self.0.position
+    }
+
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn elements(&self) -> Vec<SyntaxElement> {
+        let mut offset   = self.0.offset;
+        let mut position = self.0.position;
+
+        let mut elements = Vec::new();
+
+        for child in self.0.green.children() {
+            elements.push(match *child {
+                GreenElement::Node(ref green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green:  green.clone(),
+                    parent: Some(self.clone()),
+                    offset,
+                    position,
+                }))),
+
+                GreenElement::Token(ref green) => SyntaxElement::Token(SyntaxToken {
+                    green:  green.clone(),
+                    parent: self.clone(),
+                    offset,
+                    position,
+                }),
+            });
+
+            offset  += child.span().chars;
+            position = child.span().advance(position)
+        }
+
+        elements
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.elements().into_iter().filter_map(|element| match element {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_)   => None,
+        }).collect()
+    }
+
+    // every token under the node in source order, trivia included
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+
+        for element in self.elements() {
+            match element {
+                SyntaxElement::Node(node)   => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+
+    // the innermost node around the character at `offset`
+    pub fn covering(&self, offset: usize) -> SyntaxNode {
+        let inner = self.children().into_iter().find(|child| child.offset() <= offset && offset < child.end());
+
+        match inner {
+            Some(child) => child.covering(offset),
+            None        => self.clone(),
+        }
+    }
+
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken> {
+        self.covering(offset).elements().into_iter().find_map(|element| match element {
+            SyntaxElement::Token(token) if token.offset() <= offset && offset < token.end() => Some(token),
+            _                                                                              => None,
+        })
+    }
+
+    // what the parser makes of the tokens under the node, lines losing the
+    // indentation of the blocks around it as they do when parsed in place
+    pub fn ast(&self) -> ParserResult<Vec<Statement>> {
+        let mut depth = self.ancestors().iter().filter(|node| node.kind() == SyntaxKind::Block).count();
+
+        if self.kind() == SyntaxKind::Block {
+            depth += 1
+        }
+
+        let mut tokens   = Vec::new();
+        let mut indented = depth;
+
+        for token in self.tokens() {
+            match token.kind() {
+                ref kind if kind.trivia()             => continue,
+                TokenType::Unknown                    => continue,
+                TokenType::Indent if indented < depth => {
+                    indented += 1;
+                    continue
+                },
+                TokenType::EOL => indented = 0,
+                _              => indented = depth,
+            }
+
+            tokens.push(token.token())
+        }
+
+        Parser::new(Traveler::new(tokens)).parse()
+    }
+}
+
+// the source the node was built from, as it was written
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+// `{:#?}` shows the whole tree, a node a line, otherwise just the node
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}@{}..{}", self.kind(), self.offset(), self.end())?;
+
+        if !f.alternate() {
+            return Ok(())
+        }
+
+        writeln!(f)?;
+
+        let depth = self.ancestors().len() + 1;
+
+        for element in self.elements() {
+            write!(f, "{}", "  ".repeat(depth))?;
+
+            match element {
+                SyntaxElement::Node(node)   => write!(f, "{:#?}", node)?,
+                SyntaxElement::Token(token) => writeln!(f, "{:?}", token)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+impl SyntaxToken {
+    pub fn kind(&self) -> TokenType {
+        self.green.kind.clone()
+    }
+
+    pub fn green(&self) -> &Rc<GreenToken> {
+        &self.green
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn end(&self) -> usize {
+        self.offset + self.green.span().chars
+    }
+
+    pub fn position(&self) -> TokenPosition {
+        self.position
+    }
+
+    pub fn text(&self) -> &String {
+        self.green.text()
+    }
+
+    pub fn content(&self) -> &String {
+        self.green.content()
+    }
+
+    // the token the lexer handed out in the first place
+    pub fn token(&self) -> Token {
+        Token::new(self.kind(), self.position, self.content().clone())
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}@{}..{} {:?}", self.green.kind, self.offset, self.end(), self.green.text())
+    }
+}
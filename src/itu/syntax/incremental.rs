@@ -0,0 +1,500 @@
+use super::*;
+
+// replaces the text from `start` up to `end` with `text`, positions
+// counting lines from 1 and characters from 0 like the lexer's
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub start: TokenPosition,
+    pub end:   TokenPosition,
+    pub text:  String,
+}
+
+impl Edit {
+    pub fn apply(&self, source: &mut String) {
+        let from = offset(source, self.start);
+        let to   = ::std::cmp::max(from, offset(source, self.end));
+
+        source.replace_range(from .. to, &self.text)
+    }
+}
+
+// a top-level statement, `start` being the index of its first token
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub start:     usize,
+    pub statement: Statement,
+    pub starts:    Vec<TokenPosition>,
+}
+
+// a source kept lexed and parsed across edits, only the lines an edit
+// touches are lexed again and only the statements around them parsed again,
+// the rest moved along and reused as is
+pub struct Incremental {
+    source:   String,
+    tokens:   Vec<Token>,
+    comments: Vec<Token>,
+    stopped:  Option<TokenPosition>,
+    chunks:   Vec<Chunk>,
+    error:    Option<ParserError>,
+}
+
+#[allow(dead_code)]
+impl Incremental {
+    pub fn new(source: &str) -> Incremental {
+        let mut incremental = Incremental {
+            source:   source.to_string(),
+            tokens:   Vec::new(),
+            comments: Vec::new(),
+            stopped:  None,
+            chunks:   Vec::new(),
+            error:    None,
+        };
+
+        let everything = Edit { start: TokenPosition::default(), end: TokenPosition::default(), text: String::new() };
+
+        let relexed = incremental.relex(1, &everything);
+        incremental.reparse(0, relexed);
+
+        incremental
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn tokens(&self) -> &Vec<Token> {
+        &self.tokens
+    }
+
+    pub fn comments(&self) -> &Vec<Token> {
+        &self.comments
+    }
+
+    // where lexing gave up on a character no matcher takes, if it did
+    pub fn stopped(&self) -> Option<TokenPosition> {
+        self.stopped
+    }
+
+    pub fn chunks(&self) -> &Vec<Chunk> {
+        &self.chunks
+    }
+
+    pub fn error(&self) -> Option<&ParserError> {
+        self.error.as_ref()
+    }
+
+    // the statements parsed before any error, as `Parser::parse` would
+    // have them
+    pub fn ast(&self) -> Vec<Statement> {
+        self.chunks.iter().map(|chunk| chunk.statement.clone()).collect()
+    }
+
+    pub fn starts(&self) -> Vec<TokenPosition> {
+        self.chunks.iter().flat_map(|chunk| chunk.starts.iter().cloned()).collect()
+    }
+
+    pub fn edit(&mut self, edit: &Edit) {
+        edit.apply(&mut self.source);
+
+        // lexing starts over at the first line the lexer began afresh,
+        // which a string running over several lines may push back
+        let mut line = edit.start.line;
+
+        while line > 1 {
+            let first = self.tokens.partition_point(|token| token.position.line < line);
+
+            if first > 0 && self.tokens[first - 1].token_type == TokenType::EOL && self.tokens[first - 1].position.line == line - 1 {
+                break
+            }
+
+            line -= 1
+        }
+
+        let first = self.tokens.partition_point(|token| token.position.line < line);
+
+        // the statement before the edit is parsed again too, it may well
+        // have looked ahead into what changed
+        let chunk = self.chunks.iter().rposition(|chunk| chunk.start < first);
+
+        let old = self.relex(line, edit);
+
+        self.reparse(chunk.unwrap_or(0), old)
+    }
+
+    // lexes again from the start of `line` until the tokens fall back in
+    // step with the old ones past the edit, returning what was replaced
+    fn relex(&mut self, line: usize, edit: &Edit) -> Relexed {
+        let delta = edit.text.matches('\n').count() as isize - (edit.end.line as isize - edit.start.line as isize);
+        let last  = edit.start.line + edit.text.matches('\n').count();
+
+        let first = self.tokens.partition_point(|token| token.position.line < line);
+
+        let start = offset(&self.source, TokenPosition::new(line, 0));
+        let mut lexer = lexer(&mut self.source[start ..].chars());
+
+        let mut fresh = Vec::new();
+        let mut tail  = None;
+
+        for mut token in lexer.by_ref() {
+            token.position.line += line - 1;
+
+            let eol = token.token_type == TokenType::EOL && token.position.line >= last;
+            let end = token.position.line;
+
+            fresh.push(token);
+
+            if !eol {
+                continue
+            }
+
+            // the old tokens had the same line ending, everything after is theirs
+            let old = end as isize - delta;
+
+            if old < 1 {
+                continue
+            }
+
+            let next = self.tokens.partition_point(|token| token.position.line <= old as usize);
+
+            if next > first && self.tokens[next - 1].token_type == TokenType::EOL && self.tokens[next - 1].position.line == old as usize {
+                tail = Some((next, old as usize));
+                break
+            }
+        }
+
+        let mut lexed = lexer.comments().clone();
+
+        for comment in &mut lexed {
+            comment.position.line += line - 1
+        }
+
+        let old_len = self.tokens.len();
+
+        let (tokens, comments) = match tail {
+            Some((next, old)) => {
+                let after = self.comments.partition_point(|token| token.position.line <= old);
+
+                let tokens   = self.tokens.split_off(next).into_iter().map(|token| moved(token, delta)).collect::<Vec<_>>();
+                let comments = self.comments.split_off(after).into_iter().map(|token| moved(token, delta)).collect::<Vec<_>>();
+
+                self.stopped = self.stopped.map(|position| shift(position, delta));
+
+                (tokens, comments)
+            },
+
+            None => {
+                self.stopped = if lexer.end() {
+                    None
+                } else {
+                    let mut position = lexer.position();
+                    position.line += line - 1;
+
+                    Some(position)
+                };
+
+                (Vec::new(), Vec::new())
+            },
+        };
+
+        self.tokens.truncate(first);
+        self.tokens.extend(fresh);
+
+        let reused = self.tokens.len();
+
+        self.tokens.extend(tokens);
+
+        let kept = self.comments.partition_point(|token| token.position.line < line);
+
+        self.comments.truncate(kept);
+        self.comments.extend(lexed);
+        self.comments.extend(comments);
+
+        Relexed {
+            reused,
+            shift: self.tokens.len() as isize - old_len as isize,
+            delta,
+        }
+    }
+
+    // parses statement after statement from chunk `from` on, until one
+    // starts where an old one did in the tokens that were kept
+    fn reparse(&mut self, from: usize, relexed: Relexed) {
+        // anything before the first statement is whitespace the parser skips
+        let start = if from == 0 { 0 } else { self.chunks[from].start };
+        let old   = self.chunks.split_off(from);
+        let error = self.error.take();
+
+        let mut parser = Parser::new(Traveler::new(self.tokens[start ..].to_vec()));
+
+        loop {
+            let mut top = start + parser.top();
+
+            while top < self.tokens.len() && (self.tokens[top].token_type == TokenType::EOL || self.tokens[top].token_type == TokenType::Indent) {
+                top += 1
+            }
+
+            if top >= relexed.reused {
+                let before = top as isize - relexed.shift;
+
+                if let Some(k) = old.iter().position(|chunk| chunk.start as isize == before) {
+                    self.chunks.extend(old.into_iter().skip(k).map(|chunk| chunk.moved(relexed.shift, relexed.delta)));
+                    self.error = error.map(|error| ParserError { position: error.position.map(|p| shift(p, relexed.delta)), .. error });
+
+                    return
+                }
+            }
+
+            let count = parser.starts().len();
+
+            match parser.next_statement() {
+                Ok(Some(statement)) => self.chunks.push(Chunk {
+                    start:  top,
+                    statement,
+                    starts: parser.starts()[count ..].to_vec(),
+                }),
+
+                Ok(None) => return,
+
+                Err(err) => {
+                    self.error = Some(err);
+                    return
+                },
+            }
+        }
+    }
+}
+
+struct Relexed {
+    // index of the first old token kept
+    reused: usize,
+    // how many tokens were added, negative when fewer came back
+    shift:  isize,
+    // and how many lines
+    delta:  isize,
+}
+
+impl Chunk {
+    fn moved(mut self, tokens: isize, delta: isize) -> Chunk {
+        self.start = (self.start as isize + tokens) as usize;
+
+        if delta != 0 {
+            Shift(delta).visit_statement_mut(&mut self.statement);
+
+            for start in &mut self.starts {
+                *start = shift(*start, delta)
+            }
+        }
+
+        self
+    }
+}
+
+// moves every position in a statement down by as many lines
+struct Shift(isize);
+
+impl VisitorMut for Shift {
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        if let Statement::Expression(_, ref mut position) = *statement {
+            *position = shift(*position, self.0)
+        }
+
+        walk_statement_mut(self, statement)
+    }
+
+    fn visit_assignment_mut(&mut self, assignment: &mut Assignment) {
+        assignment.position = shift(assignment.position, self.0);
+        walk_assignment_mut(self, assignment)
+    }
+
+    fn visit_definition_mut(&mut self, definition: &mut Definition) {
+        definition.position = shift(definition.position, self.0);
+        walk_definition_mut(self, definition)
+    }
+
+    fn visit_test_mut(&mut self, test: &mut Test) {
+        test.position = shift(test.position, self.0);
+        walk_test_mut(self, test)
+    }
+
+    fn visit_match_mut(&mut self, subject: &mut Match) {
+        subject.position = shift(subject.position, self.0);
+        walk_match_mut(self, subject)
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut Arm) {
+        arm.position = shift(arm.position, self.0);
+        walk_arm_mut(self, arm)
+    }
+
+    fn visit_import_mut(&mut self, import: &mut Import) {
+        import.position = shift(import.position, self.0)
+    }
+}
+
+fn shift(position: TokenPosition, delta: isize) -> TokenPosition {
+    TokenPosition::new((position.line as isize + delta) as usize, position.col)
+}
+
+fn moved(mut token: Token, delta: isize) -> Token {
+    token.position = shift(token.position, delta);
+    token
+}
+
+// byte offset of a position, clamped to the line and the source
+fn offset(source: &str, position: TokenPosition) -> usize {
+    let mut start = 0;
+
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        if i + 1 == position.line {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+
+            return start + text.char_indices().nth(position.col).map_or(text.len(), |(i, _)| i)
+        }
+
+        start += line.len()
+    }
+
+    source.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "-- numbers
+import math/vector
+add := (a: i32, b: i32): i32 -> a + b
+twice := (f, x) ->
+  y := f x
+  f y
+
+total: mut = 0
+total = add 1, 2
+
+-- strings and arrays
+names := {\"ada\", \"grace\"}
+first := names[0]
+
+pick := (n) ->
+  if n > 1
+    \"many\"
+  else
+    \"one\"
+
+io.print (pick total), first
+
+size := (xs) ->
+  match xs
+    {} -> 0
+    {_, ...rest} -> 1 + (size rest)
+
+test \"pick says one\" ->
+  assert (pick 1) == \"one\"
+";
+
+    // text an edit may put in, enough to break statements and mend them
+    const PIECES: &[&str] = &[
+        "x", "1", " + 2", "\n", "\n\n", "\n  z := 3\n", "  ", ":= ", "(", ")", "{", "}", "->", "-- note\n", "if ", "\"s\"",
+    ];
+
+    // xorshift, so that every run makes the same edits
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    // the position of a byte offset, which has to be on a char boundary
+    fn position(source: &str, offset: usize) -> TokenPosition {
+        let before = &source[.. offset];
+        let line   = before.matches('\n').count() + 1;
+        let col    = before.rsplit('\n').next().unwrap().chars().count();
+
+        TokenPosition::new(line, col)
+    }
+
+    fn edit(random: &mut Random, source: &str) -> Edit {
+        let start = random.below(source.len() + 1);
+        let end   = if random.below(2) == 0 { start } else { ::std::cmp::min(source.len(), start + random.below(8)) };
+        let text  = if start == end || random.below(2) == 0 { PIECES[random.below(PIECES.len())] } else { "" };
+
+        Edit { start: position(source, start), end: position(source, end), text: text.to_string() }
+    }
+
+    fn tokens(tokens: &[Token]) -> Vec<(TokenType, String, TokenPosition)> {
+        tokens.iter().map(|token| (token.token_type.clone(), token.content().clone(), token.position)).collect()
+    }
+
+    fn same(incremental: &Incremental, source: &str) {
+        let fresh = Incremental::new(source);
+
+        assert_eq!(incremental.source(), source);
+        assert_eq!(tokens(incremental.tokens()), tokens(fresh.tokens()), "tokens of:\n{}", source);
+        assert_eq!(tokens(incremental.comments()), tokens(fresh.comments()), "comments of:\n{}", source);
+        assert_eq!(incremental.stopped(), fresh.stopped(), "stopped in:\n{}", source);
+        assert_eq!(incremental.ast(), fresh.ast(), "tree of:\n{}", source);
+        assert_eq!(incremental.starts(), fresh.starts(), "starts of:\n{}", source);
+        assert_eq!(format!("{:?}", incremental.error()), format!("{:?}", fresh.error()), "error in:\n{}", source);
+    }
+
+    #[test]
+    fn edits_reparse_like_the_whole_source() {
+        for seed in 1 .. 40u64 {
+            let mut random      = Random(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut source      = SOURCE.to_string();
+            let mut incremental = Incremental::new(&source);
+
+            for _ in 0 .. 25 {
+                let edit = edit(&mut random, &source);
+
+                edit.apply(&mut source);
+                incremental.edit(&edit);
+
+                same(&incremental, &source)
+            }
+        }
+    }
+
+    #[test]
+    fn typing_a_line_reparses_like_the_whole_source() {
+        let line = "scaled := add total, (twice (n) -> n * 2, 3)\n";
+
+        let mut source      = SOURCE.to_string();
+        let mut incremental = Incremental::new(&source);
+
+        let at = source.find("\n\n-- strings").unwrap() + 1;
+
+        for (i, c) in line.char_indices() {
+            let position = position(&source, at + i);
+            let edit     = Edit { start: position, end: position, text: c.to_string() };
+
+            edit.apply(&mut source);
+            incremental.edit(&edit);
+
+            same(&incremental, &source)
+        }
+    }
+
+    #[test]
+    fn moved_statements_keep_their_positions() {
+        let mut incremental = Incremental::new(SOURCE);
+
+        incremental.edit(&Edit { start: TokenPosition::new(1, 0), end: TokenPosition::new(1, 0), text: "\n\n".to_string() });
+
+        let positions = incremental.ast().iter().filter_map(|statement| match *statement {
+            Statement::Definition(ref definition) => Some(definition.position.line),
+            Statement::Assignment(ref assignment) => Some(assignment.position.line),
+            Statement::Import(ref import)         => Some(import.position.line),
+            Statement::Test(ref test)             => Some(test.position.line),
+            _                                     => None,
+        }).collect::<Vec<_>>();
+
+        assert_eq!(positions, vec![4, 5, 6, 10, 11, 14, 15, 17, 25, 30]);
+    }
+}
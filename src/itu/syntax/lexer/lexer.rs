@@ -1,92 +1,101 @@
 use super::Tokenizer;
 use super::matcher::*;
-use super::token::{Token, TokenType};
+use super::token::{Token, TokenPosition, TokenType};
 
 use std::str::Chars;
 use std::rc::Rc;
 
+pub const SYMBOLS: &[&str] = &[
+    "(",
+    ")",
+    "[",
+    "]",
+    ",",
+    ":",
+    ";",
+    "{",
+    "}",
+    "!",
+    "|",
+    "=",
+    ".",
+];
+
+// longest first, `>` would otherwise cut `>=` short
+pub const OPERATORS: &[&str] = &[
+    ">=",
+    "<=",
+    "==",
+    "!=",
+    "+",
+    "-",
+    "*",
+    "/",
+    "%",
+    "^",
+    ">",
+    "<",
+];
+
+pub const TYPES: &[&str] = &[
+    "i08",
+    "i32",
+    "i64",
+    "i128",
+    "f32",
+    "f64",
+    "u08",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "char",
+    "str",
+    "any",
+    "bool",
+    "mut",
+];
+
+pub const KEYWORDS: &[&str] = &[
+    "->",
+    "if",
+    "then",
+    "elif",
+    "else",
+    "match",
+    "import",
+    "pub",
+];
+
+pub const BOOLEANS: &[&str] = &[
+    "true",
+    "false",
+];
+
+fn strings(constants: &[&str]) -> Vec<String> {
+    constants.iter().map(|&x| x.to_string()).collect()
+}
+
 pub fn lexer(data: &mut Chars) -> Lexer {
     let tokenizer = Tokenizer::new(data);
     let mut lexer = Lexer::new(tokenizer);
 
-    let eol = vec!["\n"].iter().map(|&x| x.to_string()).collect();
-
-    let symbols = vec![
-        "(",
-        ")",
-        "[",
-        "]",
-        ",",
-        ":",
-        ";",
-        "{",
-        "}",
-        "!",
-        "|",
-        "=",
-        ".",
-    ].iter().map(|&x| x.to_string()).collect();
-
-    let operators = vec![
-        "+",
-        "-",
-        "*",
-        "/",
-        "/",
-        "%",
-        "^",
-        ">",
-        "<",
-        ">=",
-        "<=",
-        "==",
-        "!=",
-    ].iter().map(|&x| x.to_string()).collect();
+    let eol = ["\n"].iter().map(|&x| x.to_string()).collect();
 
-    let indent = vec![
+    let indent = [
         "  ", "\t",
     ].iter().map(|&x| x.to_string()).collect();
 
-    let types = vec![
-        "i08",
-        "i32",
-        "i64",
-        "i128",
-        "f32",
-        "f64",
-        "u08",
-        "u16",
-        "u32",
-        "u64",
-        "u128",
-        "char",
-        "str",
-        "any",
-        "bool",
-        "mut",
-    ].iter().map(|&x| x.to_string()).collect();
-
-    let keywords = vec![
-        "->",
-        "if",
-        "then",
-        "elif",
-        "else",
-    ].iter().map(|&x| x.to_string()).collect();
-
-    let boolean = vec![
-        "true",
-        "false",
-    ].iter().map(|&x| x.to_string()).collect();
-
     let matcher_eol            = ConstantMatcher::new(TokenType::EOL, eol);
-    let matcher_indent         = ConstantMatcher::new(TokenType::Indent, indent);
-    let matcher_keywords       = KeyMatcher::new(TokenType::Keyword, keywords);
-    let matcher_operator       = ConstantMatcher::new(TokenType::Operator, operators);
-    let matcher_symbol         = ConstantMatcher::new(TokenType::Symbol, symbols);
-    let matcher_boolean        = KeyMatcher::new(TokenType::BoolLiteral, boolean);
-    let matcher_types          = KeyMatcher::new(TokenType::Type, types);
+    let matcher_indent         = IndentMatcher::new(indent);
+    let matcher_keywords       = KeyMatcher::new(TokenType::Keyword, strings(KEYWORDS));
+    let matcher_operator       = ConstantMatcher::new(TokenType::Operator, strings(OPERATORS));
+    let matcher_symbol         = ConstantMatcher::new(TokenType::Symbol, strings(SYMBOLS));
+    let matcher_boolean        = KeyMatcher::new(TokenType::BoolLiteral, strings(BOOLEANS));
+    let matcher_types          = KeyMatcher::new(TokenType::Type, strings(TYPES));
     let matcher_whitespace     = WhitespaceMatcher {};
+    let matcher_comment        = CommentMatcher {};
+    let matcher_doc_comment    = DocCommentMatcher {};
     let matcher_int_literal    = IntLiteralMatcher {};
     let matcher_float_literal  = FloatLiteralMatcher {};
     let matcher_identifier     = IdentifierMatcher {};
@@ -95,6 +104,8 @@ pub fn lexer(data: &mut Chars) -> Lexer {
     lexer.matchers_mut().push(Rc::new(matcher_eol));
     lexer.matchers_mut().push(Rc::new(matcher_indent));
     lexer.matchers_mut().push(Rc::new(matcher_whitespace));
+    lexer.matchers_mut().push(Rc::new(matcher_doc_comment));
+    lexer.matchers_mut().push(Rc::new(matcher_comment));
     lexer.matchers_mut().push(Rc::new(matcher_keywords));
     lexer.matchers_mut().push(Rc::new(matcher_operator));
     lexer.matchers_mut().push(Rc::new(matcher_symbol));
@@ -109,7 +120,9 @@ pub fn lexer(data: &mut Chars) -> Lexer {
 
 pub struct Lexer {
     tokenizer: Tokenizer,
-    matchers: Vec<Rc<Matcher>>,
+    matchers: Vec<Rc<dyn Matcher>>,
+    comments: Vec<Token>,
+    trivia:   bool,
 }
 
 #[allow(dead_code)]
@@ -118,9 +131,18 @@ impl Lexer {
         Lexer {
             tokenizer,
             matchers: Vec::new(),
+            comments: Vec::new(),
+            trivia:   false,
         }
     }
 
+    // hands whitespace and comments out with the other tokens, for trees
+    // that have to give back the source as it was written
+    pub fn with_trivia(mut self) -> Lexer {
+        self.trivia = true;
+        self
+    }
+
     pub fn match_token(&mut self) -> Option<Token> {
         for matcher in &mut self.matchers {
             match self.tokenizer.try_match_token(matcher.as_ref()) {
@@ -131,32 +153,92 @@ impl Lexer {
         None
     }
 
-    pub fn matchers(&self) -> &Vec<Rc<Matcher>> {
+    pub fn matchers(&self) -> &Vec<Rc<dyn Matcher>> {
         &self.matchers
     }
 
-    pub fn matchers_mut(&mut self) -> &mut Vec<Rc<Matcher>> {
+    pub fn matchers_mut(&mut self) -> &mut Vec<Rc<dyn Matcher>> {
         &mut self.matchers
     }
+
+    // lexing stops short of the end when no matcher accepts what comes next
+    pub fn end(&self) -> bool {
+        self.tokenizer.end()
+    }
+
+    pub fn position(&self) -> TokenPosition {
+        self.tokenizer.pos
+    }
+
+    // comments are trivia, the parser never sees them but they are kept here for the formatter
+    pub fn comments(&self) -> &Vec<Token> {
+        &self.comments
+    }
 }
 
 impl Iterator for Lexer {
     type Item = Token;
 
     fn next(&mut self) -> Option<Token> {
-        let token = match self.match_token() {
-            Some(n) => n,
-            None    => return None,
-        };
+        let token = self.match_token()?;
         match token.token_type {
             TokenType::EOF => None,
-            TokenType::Whitespace => {
-                match self.next() {
-                    Some(t) => Some(t),
-                    None => None,
-                }
-            }
+            ref kind if kind.trivia() && self.trivia => Some(token),
+            TokenType::Whitespace => self.next(),
+            TokenType::Comment | TokenType::DocComment => {
+                self.comments.push(token);
+                self.next()
+            },
             _ => Some(token),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<(TokenType, String)> {
+        lexer(&mut source.chars()).map(|token| (token.token_type.clone(), token.content().clone())).collect()
+    }
+
+    #[test]
+    fn two_character_operators_lex_whole() {
+        let operators = tokens("a >= b <= c == d != e > f")
+            .into_iter()
+            .filter(|(kind, _)| *kind == TokenType::Operator)
+            .map(|(_, content)| content)
+            .collect::<Vec<_>>();
+
+        assert_eq!(operators, vec![">=", "<=", "==", "!=", ">"]);
+    }
+
+    #[test]
+    fn trailing_spaces_keep_the_line_break() {
+        let kinds = tokens("a  \nb").into_iter().map(|(kind, _)| kind).collect::<Vec<_>>();
+
+        assert_eq!(kinds, vec![TokenType::Identifier, TokenType::EOL, TokenType::Identifier]);
+    }
+
+    #[test]
+    fn indentation_only_counts_at_line_starts() {
+        let indents = |source: &str| tokens(source).into_iter().filter(|(kind, _)| *kind == TokenType::Indent).count();
+
+        assert_eq!(indents("a :=  b    c\n"), 0);
+        assert_eq!(indents("f ->\n    b  c\n"), 2);
+    }
+
+    #[test]
+    fn malformed_literals_are_unknown() {
+        let unknown = |source: &str| tokens(source).into_iter()
+            .filter(|(kind, _)| *kind == TokenType::Unknown)
+            .map(|(_, content)| content)
+            .collect::<Vec<_>>();
+
+        assert_eq!(unknown("x := 99999999999999999999999"), vec!["99999999999999999999999"]);
+        assert_eq!(unknown("x := 1.2.3 + 1"), vec!["1.2.3"]);
+        assert_eq!(unknown("x := \"a\\qb\""), vec!["\"a\\qb\""]);
+        assert_eq!(unknown("x := 'ab'"), vec!["'ab'"]);
+        assert!(unknown("x := 'é' + \"a\\tb\" + 18446744073709551615").is_empty());
+    }
+}
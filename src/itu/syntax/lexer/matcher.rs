@@ -22,7 +22,8 @@ pub struct WhitespaceMatcher;
 impl Matcher for WhitespaceMatcher {
     fn try_match(&self, tokenizer: &mut Tokenizer) -> Option<Token> {
         let mut found = false;
-        while !tokenizer.end() && tokenizer.peek().unwrap().is_whitespace() {
+        // line breaks are tokens of their own, blocks depend on them
+        while !tokenizer.end() && tokenizer.peek().unwrap().is_whitespace() && *tokenizer.peek().unwrap() != '\n' {
             found = true;
             tokenizer.next();
         }
@@ -34,6 +35,72 @@ impl Matcher for WhitespaceMatcher {
     }
 }
 
+pub struct CommentMatcher;
+
+impl Matcher for CommentMatcher {
+    fn try_match(&self, tokenizer: &mut Tokenizer) -> Option<Token> {
+        if tokenizer.peek() != Some(&'-') || tokenizer.peek_n(1) != Some(&'-') {
+            return None
+        }
+
+        let mut comment = String::new();
+        while !tokenizer.end() && *tokenizer.peek().unwrap() != '\n' {
+            comment.push(tokenizer.next().unwrap())
+        }
+
+        token!(tokenizer, Comment, comment.trim_end().to_string())
+    }
+}
+
+// `---` documents the definition below it, a longer run of dashes is
+// still a plain comment
+pub struct DocCommentMatcher;
+
+impl Matcher for DocCommentMatcher {
+    fn try_match(&self, tokenizer: &mut Tokenizer) -> Option<Token> {
+        for n in 0 .. 3 {
+            if tokenizer.peek_n(n) != Some(&'-') {
+                return None
+            }
+        }
+
+        if tokenizer.peek_n(3) == Some(&'-') {
+            return None
+        }
+
+        let mut comment = String::new();
+        while !tokenizer.end() && *tokenizer.peek().unwrap() != '\n' {
+            comment.push(tokenizer.next().unwrap())
+        }
+
+        token!(tokenizer, DocComment, comment.trim_end().to_string())
+    }
+}
+
+// indentation only counts at the start of a line, anywhere else it is
+// plain whitespace and free to line things up
+pub struct IndentMatcher {
+    indent: ConstantMatcher,
+}
+
+impl IndentMatcher {
+    pub fn new(constants: Vec<String>) -> Self {
+        IndentMatcher {
+            indent: ConstantMatcher::new(TokenType::Indent, constants),
+        }
+    }
+}
+
+impl Matcher for IndentMatcher {
+    fn try_match(&self, tokenizer: &mut Tokenizer) -> Option<Token> {
+        if !tokenizer.line_start() {
+            return None
+        }
+
+        self.indent.try_match(tokenizer)
+    }
+}
+
 pub struct IntLiteralMatcher {}
 
 impl Matcher for IntLiteralMatcher {
@@ -44,31 +111,43 @@ impl Matcher for IntLiteralMatcher {
             Some(&'+') => Some(true),
             _          => None,
         };
-        if let Some(_) = prefix {
+        if prefix.is_some() {
             tokenizer.advance(1)
         };
-        while !tokenizer.end() && tokenizer.peek().unwrap().is_digit(10) {
+        while !tokenizer.end() && tokenizer.peek().unwrap().is_ascii_digit() {
             accum.push(tokenizer.next().unwrap());
         }
         if !accum.is_empty() {
-            let literal: String = if Some(false) == prefix {
-                match i64::from_str_radix(accum.as_str(), 10) {
-                    Ok(result) => format!("-{}", result),
-                    Err(error) => panic!("unable to parse int: {}", error)
-                }
-            } else {
-                match u64::from_str_radix(accum.as_str(), 10) {
-                    Ok(result) => result.to_string(),
-                    Err(error) => panic!("unable to parse int: {}", error)
-                }
-            };
-            token!(tokenizer, IntLiteral, literal)
+            integer(tokenizer, prefix, accum)
         } else {
             None
         }
     }
 }
 
+// the sign is kept on the literal, a number too large for any integer being
+// left unknown for the parser to point at
+fn integer(tokenizer: &Tokenizer, prefix: Option<bool>, accum: String) -> Option<Token> {
+    let literal = if Some(false) == prefix {
+        accum.parse::<i64>().map(|result| format!("-{}", result))
+    } else {
+        accum.parse::<u64>().map(|result| result.to_string())
+    };
+
+    match literal {
+        Ok(literal) => token!(tokenizer, IntLiteral, literal),
+        Err(_)      => token!(tokenizer, Unknown, signed(prefix, accum)),
+    }
+}
+
+fn signed(prefix: Option<bool>, accum: String) -> String {
+    match prefix {
+        Some(false) => format!("-{}", accum),
+        Some(true)  => format!("+{}", accum),
+        None        => accum,
+    }
+}
+
 pub struct FloatLiteralMatcher;
 
 impl Matcher for FloatLiteralMatcher {
@@ -80,23 +159,26 @@ impl Matcher for FloatLiteralMatcher {
             Some(&'+') => Some(true),
             _          => None,
         };
-        if let Some(_) = prefix {
+        if prefix.is_some() {
             tokenizer.advance(1)
         };
 
         let curr = tokenizer.next().unwrap();
-        if curr.is_digit(10) {
+        if curr.is_ascii_digit() {
             accum.push(curr)
         } else if curr == '.' {
             accum.push_str("0.")
         } else {
             return None
         }
+        // a second decimal point makes the whole run of digits unknown
+        let mut points = accum.matches('.').count();
+
         while !tokenizer.end() {
             let current = *tokenizer.peek().unwrap();
-            if !current.is_whitespace() && current.is_digit(10) || current == '.' {
-                if current == '.' && accum.contains('.') {
-                    panic!("illegal decimal point")
+            if !current.is_whitespace() && current.is_ascii_digit() || current == '.' {
+                if current == '.' {
+                    points += 1
                 }
                 accum.push(tokenizer.next().unwrap())
             } else {
@@ -106,35 +188,16 @@ impl Matcher for FloatLiteralMatcher {
 
         if accum == "0." {
             None
-        } else if accum.contains('.') {
-
-            let literal: String = if Some(false) == prefix {
-                match accum.parse::<f64>() {
-                    Ok(result) => format!("-{}", result),
-                    Err(error) => panic!("unable to parse float: {}", error)
-                }
-            } else {
-                match accum.parse::<f64>() {
-                    Ok(result) => result.to_string(),
-                    Err(error) => panic!("unable to parse float: {}", error)
-                }
-            };
-
-            token!(tokenizer, FloatLiteral, literal)
+        } else if points > 1 {
+            token!(tokenizer, Unknown, signed(prefix, accum))
+        } else if points == 1 {
+            match accum.parse::<f64>() {
+                Ok(result) if Some(false) == prefix => token!(tokenizer, FloatLiteral, format!("-{}", result)),
+                Ok(result)                          => token!(tokenizer, FloatLiteral, result.to_string()),
+                Err(_)                              => token!(tokenizer, Unknown, signed(prefix, accum)),
+            }
         } else {
-            let literal: String = if Some(false) == prefix {
-                match i64::from_str_radix(accum.as_str(), 10) {
-                    Ok(result) => format!("-{}", result),
-                    Err(error) => panic!("unable to parse int: {}", error)
-                }
-            } else {
-                match u64::from_str_radix(accum.as_str(), 10) {
-                    Ok(result) => result.to_string(),
-                    Err(error) => panic!("unable to parse int: {}", error)
-                }
-            };
-
-            token!(tokenizer, IntLiteral, literal)
+            integer(tokenizer, prefix, accum)
         }
     }
 }
@@ -145,19 +208,22 @@ impl Matcher for StringLiteralMatcher {
     fn try_match(&self, tokenizer: &mut Tokenizer) -> Option<Token> {
         let mut raw_marker = false;
         let delimeter  = match *tokenizer.peek().unwrap() {
-            '"'  => Some('"'),
-            '\'' => Some('\''),
+            '"'  => '"',
+            '\'' => '\'',
             'r' if tokenizer.peek_n(1) == Some(&'"') => {
                 raw_marker = true;
                 tokenizer.advance(1);
 
-                Some('"')
+                '"'
             },
             _ => return None,
         };
         tokenizer.advance(1);
         let mut string       = String::new();
         let mut found_escape = false;
+        // the literal as written, what is left of it when it is unknown
+        let mut text    = if raw_marker { String::from("r\"") } else { delimeter.to_string() };
+        let mut invalid = false;
         while !tokenizer.end() {
             if raw_marker {
                 if tokenizer.peek().unwrap() == &'"' {
@@ -165,39 +231,45 @@ impl Matcher for StringLiteralMatcher {
                 }
                 string.push(tokenizer.next().unwrap())
             } else if found_escape {
-                string.push(
-                    match tokenizer.next().unwrap() {
-                        c @ '\\' | c @ '\'' | c @ '"' => c,
-                        'n' => '\n',
-                        'r' => '\r',
-                        't' => '\t',
-                        s => panic!("invalid character escape: {}", s),
-                    }
-                );
+                let escaped = tokenizer.next().unwrap();
+                text.push(escaped);
+                match escaped {
+                    c @ '\\' | c @ '\'' | c @ '"' => string.push(c),
+                    'n' => string.push('\n'),
+                    'r' => string.push('\r'),
+                    't' => string.push('\t'),
+                    _   => invalid = true,
+                }
                 found_escape = false
             } else {
                 match *tokenizer.peek().unwrap() {
                     '\\' => {
                         tokenizer.next();
+                        text.push('\\');
                         found_escape = true
                     },
-                    c if c == delimeter.unwrap() => break,
-                    _ => string.push(tokenizer.next().unwrap()),
+                    c if c == delimeter => break,
+                    c => {
+                        tokenizer.next();
+                        text.push(c);
+                        string.push(c)
+                    },
                 }
             }
         }
         tokenizer.advance(1);
-        match delimeter.unwrap() {
-            '"'  => {
-                token!(tokenizer, StringLiteral, string)
-            },
-            _ => {
-                if string.len() == 1 {
-                    token!(tokenizer, CharLiteral, string)
-                } else {
-                    panic!("invalid char literal")
-                }
-            },
+        if raw_marker {
+            text.push_str(&string);
+        }
+        text.push(delimeter);
+        if invalid {
+            token!(tokenizer, Unknown, text)
+        } else if delimeter == '"' {
+            token!(tokenizer, StringLiteral, string)
+        } else if string.chars().count() == 1 {
+            token!(tokenizer, CharLiteral, string)
+        } else {
+            token!(tokenizer, Unknown, text)
         }
     }
 }
@@ -210,8 +282,8 @@ pub struct ConstantMatcher {
 impl ConstantMatcher {
     pub fn new(token_type: TokenType, constants: Vec<String>) -> Self {
         ConstantMatcher {
-            token_type: token_type,
-            constants: constants,
+            token_type,
+            constants,
         }
     }
 }
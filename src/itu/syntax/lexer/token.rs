@@ -13,12 +13,17 @@ pub enum TokenType {
     Type,
     Keyword,
     Whitespace,
+    Comment,
+    DocComment,
     Indent,
     EOL,
     EOF,
+    // what is left when lexing stops short, never handed to the parser
+    Unknown,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TokenPosition {
     pub line: usize,
     pub col:  usize,
@@ -69,6 +74,14 @@ impl Token {
     }
 }
 
+#[allow(dead_code)]
+impl TokenType {
+    // what the parser never sees
+    pub fn trivia(&self) -> bool {
+        matches!(*self, TokenType::Whitespace | TokenType::Comment | TokenType::DocComment)
+    }
+}
+
 impl PartialEq for Token {
     fn eq(&self, other: &Token) -> bool {
         self.token_type == other.token_type
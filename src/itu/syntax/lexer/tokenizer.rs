@@ -33,7 +33,7 @@ impl Iterator for Tokenizer {
 
 #[allow(dead_code)]
 impl Tokenizer {
-    pub fn new(items: &mut Iterator<Item = char>) -> Tokenizer {
+    pub fn new(items: &mut dyn Iterator<Item = char>) -> Tokenizer {
         Tokenizer {
             index:     0,
             pos:       TokenPosition::default(),
@@ -84,6 +84,13 @@ impl Tokenizer {
         }
     }
 
+    // whether nothing but indentation comes before the current position on its line
+    pub fn line_start(&self) -> bool {
+        self.items[.. self.index].iter().rev()
+            .take_while(|c| **c != '\n')
+            .all(|c| *c == ' ' || *c == '\t')
+    }
+
     pub fn take_snapshot(&mut self) {
         self.snapshots.push(Snapshot::new(self.index, self.pos));
     }
@@ -106,7 +113,7 @@ impl Tokenizer {
         self.peek_snapshot().unwrap().pos
     }
 
-    pub fn try_match_token(&mut self, matcher: &Matcher) -> Option<Token> {
+    pub fn try_match_token(&mut self, matcher: &dyn Matcher) -> Option<Token> {
         if self.end() {
             return Some(Token::new(TokenType::EOF,
                                    TokenPosition::new(self.index, self.index),
@@ -1,5 +1,11 @@
 pub mod lexer;
 pub mod parser;
+pub mod cst;
+#[cfg(feature = "lsp")]
+pub mod incremental;
 
 pub use self::lexer::*;
 pub use self::parser::*;
+pub use self::cst::*;
+#[cfg(feature = "lsp")]
+pub use self::incremental::*;
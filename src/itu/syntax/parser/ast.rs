@@ -3,6 +3,7 @@ use std::rc::Rc;
 use super::*;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Expression {
     Block(Vec<Statement>),
     Number(f64),
@@ -15,10 +16,14 @@ pub enum Expression {
     Lambda(Lambda),
     Array(Vec<Rc<Expression>>),
     Index(Index),
+    Field(Field),
+    If(If),
+    Match(Match),
     EOF,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Operation {
     pub left:  Rc<Expression>,
     pub op:    Operand,
@@ -26,45 +31,143 @@ pub struct Operation {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Call {
     pub callee: Rc<Expression>,
     pub args:   Vec<Rc<Expression>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Lambda {
-    pub t:      Rc<Type>,
+    pub t:      Option<Rc<Type>>,
     pub params: Vec<(Option<Type>, Rc<String>)>,
     pub body:   Rc<Expression>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Index {
     pub id:    Rc<Expression>,
     pub index: Rc<Expression>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Field {
+    pub object: Rc<Expression>,
+    pub name:   Rc<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct If {
+    pub condition: Rc<Expression>,
+    pub body:      Rc<Expression>,
+    pub otherwise: Option<Rc<Expression>>,
+}
+
+// `match subject` and its arms a line each, the first whose pattern fits
+// and whose guard holds being taken
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Match {
+    pub subject:  Rc<Expression>,
+    pub arms:     Vec<Arm>,
+    pub position: TokenPosition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Arm {
+    pub pattern:  Pattern,
+    pub guard:    Option<Rc<Expression>>,
+    pub body:     Rc<Expression>,
+    pub position: TokenPosition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Pattern {
+    Wildcard,
+    Binding(Rc<String>),
+    Number(f64),
+    Bool(bool),
+    Str(Rc<String>),
+    Char(char),
+    // `{a, b}`, or `{a, ...rest}` for arrays at least as long, the rest
+    // being a binding or `_`
+    Array(Vec<Pattern>, Option<Rc<Pattern>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Statement {
-    Expression(Rc<Expression>),
+    // where the expression starts, errors in it being reported there
+    Expression(Rc<Expression>, TokenPosition),
     Assignment(Assignment),
     Definition(Definition),
+    Test(Test),
+    Import(Import),
+}
+
+impl Statement {
+    // where the statement starts
+    pub fn position(&self) -> TokenPosition {
+        match *self {
+            Statement::Expression(_, position)     => position,
+            Statement::Assignment(ref assignment) => assignment.position,
+            Statement::Definition(ref definition) => definition.position,
+            Statement::Test(ref test)             => test.position,
+            Statement::Import(ref import)         => import.position,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Assignment {
-    pub left:  Rc<Expression>,
-    pub right: Rc<Expression>,
+    pub left:     Rc<Expression>,
+    pub right:    Rc<Expression>,
+    pub position: TokenPosition,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Definition {
-    pub t:     Option<Type>,
-    pub name:  Rc<Expression>,
-    pub right: Option<Rc<Expression>>,
+    pub t:        Option<Type>,
+    pub name:     Rc<Expression>,
+    pub right:    Option<Rc<Expression>>,
+    pub position: TokenPosition,
+    // `pub`, seen by files importing this one
+    pub public:   bool,
 }
 
+// `test "name" ->` and its body, run by `itu test` and skipped otherwise
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Test {
+    pub name:     Rc<String>,
+    pub body:     Rc<Expression>,
+    pub position: TokenPosition,
+}
+
+// `import math/vector`, the module bound to the last name of its path
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Import {
+    pub path:     Vec<Rc<String>>,
+    pub position: TokenPosition,
+}
+
+impl Import {
+    pub fn name(&self) -> &Rc<String> {
+        self.path.last().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Operand {
     Pow,
     Mul, Div, Mod,
@@ -91,9 +194,32 @@ impl Operand {
             _     => None,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Operand::Pow     => "^",
+            Operand::Mul     => "*",
+            Operand::Div     => "/",
+            Operand::Mod     => "%",
+            Operand::Add     => "+",
+            Operand::Sub     => "-",
+            Operand::Equal   => "==",
+            Operand::NEqual  => "!=",
+            Operand::Lt      => "<",
+            Operand::Gt      => ">",
+            Operand::LtEqual => "<=",
+            Operand::GtEqual => ">=",
+        }
+    }
+
+    // lower binds tighter
+    pub fn precedence(&self) -> u8 {
+        Operand::from_str(self.as_str()).unwrap().1
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Type {
     Mut(Option<Rc<Type>>),
     Array(Rc<Type>, Option<Expression>),
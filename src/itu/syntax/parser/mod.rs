@@ -2,6 +2,7 @@ pub mod error;
 pub mod traveler;
 pub mod ast;
 pub mod parser;
+pub mod visit;
 
 pub use super::*;
 
@@ -9,5 +10,6 @@ pub use self::error::*;
 pub use self::traveler::*;
 pub use self::ast::*;
 pub use self::parser::*;
+pub use self::visit::*;
 
 pub type ParserResult<T> = Result<T, ParserError>;
@@ -4,29 +4,86 @@ use super::*;
 
 pub struct Parser {
     traveler: Traveler,
+    starts:   Vec<TokenPosition>,
+    ends:     Vec<TokenPosition>,
+    // expressions end at the line break, as in match arms where the next
+    // line may open with a `-` of its own
+    inline:   bool,
+    // in a guard the arrow ends the arm, so `(a) ->` there is no lambda
+    guard:    bool,
+    // parsing a block, where tests have no place
+    nested:   bool,
 }
 
 impl Parser {
     pub fn new(traveler: Traveler) -> Parser {
         Parser {
             traveler,
+            starts: Vec::new(),
+            ends:   Vec::new(),
+            inline: false,
+            guard:  false,
+            nested: false,
         }
     }
 
     pub fn parse(&mut self) -> ParserResult<Vec<Statement>> {
         let mut stack = Vec::new();
 
-        while self.traveler.remaining() > 1 {
-            self.skip_whitespace()?;
-            stack.push(self.statement()?);
+        while let Some(statement) = self.next_statement()? {
+            stack.push(statement)
         }
 
         Ok(stack)
     }
 
+    // one top-level statement at a time, none once the tokens run out
+    pub fn next_statement(&mut self) -> ParserResult<Option<Statement>> {
+        if self.traveler.remaining() < 2 {
+            return Ok(None)
+        }
+
+        self.skip_whitespace()?;
+
+        if self.traveler.remaining() < 2 {
+            return Ok(None)
+        }
+
+        let index    = self.starts.len();
+        let position = self.traveler.current().position;
+
+        self.starts.push(position);
+        self.ends.push(position);
+
+        let statement = self.statement()?;
+
+        // the last token the statement used up, nested statements were
+        // pushed after it in the meantime
+        if self.traveler.top() > 0 {
+            self.ends[index] = self.traveler.tokens[self.traveler.top() - 1].position
+        }
+
+        Ok(Some(statement))
+    }
+
+    // index of the token the parser is looking at
+    pub fn top(&self) -> usize {
+        self.traveler.top()
+    }
+
+    // where every statement begins, nested ones included, in the order they
+    // appear in the source
+    pub fn starts(&self) -> &Vec<TokenPosition> {
+        &self.starts
+    }
+
+    // where every statement in `starts` ends, on its last token
+    pub fn ends(&self) -> &Vec<TokenPosition> {
+        &self.ends
+    }
+
     pub fn skip_whitespace(&mut self) -> ParserResult<()> {
-        while self.traveler.current_content() == "\n" ||
-              self.traveler.current().token_type == TokenType::EOL ||
+        while self.traveler.current().token_type == TokenType::EOL ||
               self.traveler.current().token_type == TokenType::Indent {
 
             self.traveler.next();
@@ -49,10 +106,23 @@ impl Parser {
         }
 
         if self.traveler.remaining() > 1 {
-            self.skip_whitespace()?;
+            // an operator may continue the expression on the next line, the
+            // line break is left alone otherwise since blocks depend on it
+            let mut skipped = 0;
+
+            while !self.inline && self.traveler.remaining() > 2 && (self.traveler.current().token_type == TokenType::EOL ||
+                                                                    self.traveler.current().token_type == TokenType::Indent) {
+                self.traveler.next();
+                skipped += 1
+            }
+
             if self.traveler.current().token_type == TokenType::Operator {
                 return self.operation(expr)
             }
+
+            for _ in 0 .. skipped {
+                self.traveler.prev();
+            }
         }
 
         Ok(expr)
@@ -61,7 +131,7 @@ impl Parser {
     fn array_type(&mut self) -> ParserResult<Type> {
         self.traveler.next();
 
-        let t = Rc::new(Type::from(&self.traveler.current()).unwrap_or(Type::Any));
+        let t = Rc::new(Type::from(self.traveler.current()).unwrap_or(Type::Any));
         self.traveler.next();
 
         if self.traveler.current_content() == ";" {
@@ -88,7 +158,7 @@ impl Parser {
             let t: Option<Rc<Type>>;
             if self.traveler.current_content() == "[" {
                 t = Some(Rc::new(self.array_type()?));
-            } else if let Some(tt) = Type::from(&self.traveler.current()) {
+            } else if let Some(tt) = Type::from(self.traveler.current()) {
                 self.traveler.next();
                 t = Some(Rc::new(tt));
             } else {
@@ -97,7 +167,7 @@ impl Parser {
 
             Ok(Type::Mut(t))
 
-        } else if let Some(t) = Type::from(&self.traveler.current()) {
+        } else if let Some(t) = Type::from(self.traveler.current()) {
             self.traveler.next();
             Ok(t)
         } else if self.traveler.current_content() == "[" {
@@ -115,8 +185,9 @@ impl Parser {
             TokenType::StringLiteral |
             TokenType::CharLiteral   |
             TokenType::Identifier => self.call(callee),
+            TokenType::Type if self.module_type() => self.call(callee),
             TokenType::Symbol     => match self.traveler.current_content().as_str() {
-                "(" => self.call(callee),
+                "(" | "{" => self.call(callee),
                 _ => Ok(callee),
             },
 
@@ -126,11 +197,14 @@ impl Parser {
 
     fn params(&mut self) -> ParserResult<Vec<(Option<Type>, Rc<String>)>> {
         self.traveler.expect_content("(")?;
+        let open = self.traveler.current().position;
         self.traveler.next();
 
         let mut params = Vec::new();
 
         while self.traveler.current_content() != ")" {
+            self.unfinished(open, "unclosed '('")?;
+
             if self.traveler.current_content() == "," {
                 self.traveler.next();
             }
@@ -140,15 +214,15 @@ impl Parser {
                     let a = Rc::new(self.traveler.current_content());
                     self.traveler.next();
                     
-                    let mut t = Type::Any;
+                    let mut t = None;
                     
                     if self.traveler.current_content() == ":" {
                         self.traveler.next();
                         
-                        t = self.try_type()?
+                        t = Some(self.try_type()?)
                     }
 
-                    params.push((Some(t), a));
+                    params.push((t, a));
                 },
 
                 _ => return Err(ParserError::new_pos(self.traveler.current().position, &format!("expected parameter: {}", self.traveler.current_content()))),
@@ -160,54 +234,347 @@ impl Parser {
         Ok(params)
     }
 
-    fn block(&mut self) -> ParserResult<Expression> {
+    // the tokens of every following line indented one level deeper, blank
+    // lines included as long as another indented line comes after them, each
+    // line after the line break before it
+    fn lines(&mut self) -> Vec<Token> {
         let mut stack = Vec::new();
-        loop {
-            if self.traveler.current().token_type == TokenType::Indent {
-                self.traveler.next();
-                if self.traveler.current_content() == "\n" {
-                    self.traveler.next();
-                    break
+
+        while self.traveler.remaining() > 1 && self.traveler.current().token_type == TokenType::EOL {
+            let tokens = &self.traveler.tokens;
+
+            let mut line  = self.traveler.top() + 1;
+            let mut blank = 0;
+
+            loop {
+                let mut at = line;
+
+                while at < tokens.len() && tokens[at].token_type == TokenType::Indent {
+                    at += 1
                 }
-            } else if self.traveler.current_content() == "\n" {
-                stack.push(self.traveler.current().clone());
-                self.traveler.next();
 
-                if self.traveler.current().token_type == TokenType::Indent {
-                    self.traveler.next();
+                if at < tokens.len() && tokens[at].token_type == TokenType::EOL {
+                    line   = at + 1;
+                    blank += 1;
                 } else {
                     break
                 }
             }
 
-            if self.traveler.remaining() < 2 {
+            if line >= tokens.len() || tokens[line].token_type != TokenType::Indent {
                 break
             }
 
-            stack.push(self.traveler.current().clone());
+            for _ in 0 ..= blank {
+                stack.push(self.traveler.current().clone())
+            }
+
+            while self.traveler.top() < line + 1 {
+                self.traveler.next();
+            }
+
+            while self.traveler.remaining() > 1 && self.traveler.current().token_type != TokenType::EOL {
+                stack.push(self.traveler.current().clone());
+                self.traveler.next();
+            }
+        }
+
+        // the line break ending the block is used up like any other
+        if self.traveler.remaining() > 1 {
             self.traveler.next();
         }
-    
+
+        stack
+    }
+
+    fn block(&mut self) -> ParserResult<Expression> {
+        let stack = self.lines();
+
         let mut parser = Parser::new(Traveler::new(stack));
+        parser.nested = true;
 
-        match parser.parse() {
-            Ok(s)    => Ok(Expression::Block(s)),
-            Err(why) => Err(ParserError::new(&format!("{}", why))),
-        }
+        // errors keep their position, the tokens were never moved
+        let s = parser.parse()?;
+        self.starts.extend(parser.starts);
+        self.ends.extend(parser.ends);
+
+        Ok(Expression::Block(s))
     }
 
     fn body(&mut self) -> ParserResult<Expression> {
-        if self.traveler.current_content() == "\n" {
+        if self.traveler.current().token_type == TokenType::EOL {
             self.block()
         } else {
             self.expression()
         }
     }
 
+    fn if_expression(&mut self) -> ParserResult<Expression> {
+        self.traveler.next();
+
+        let condition = Rc::new(self.expression()?);
+
+        let body = if self.traveler.current_content() == "then" {
+            self.traveler.next();
+            Rc::new(self.expression()?)
+        } else {
+            self.traveler.expect(TokenType::EOL)?;
+            Rc::new(self.block()?)
+        };
+
+        // `elif` and `else` may follow on the next line of a one-line body
+        let mut skipped = false;
+
+        if self.traveler.current().token_type == TokenType::EOL && self.traveler.remaining() > 2 {
+            self.traveler.next();
+            skipped = true
+        }
+
+        let otherwise = match self.traveler.current_content().as_str() {
+            "elif" => Some(Rc::new(self.if_expression()?)),
+            "else" => {
+                self.traveler.next();
+                Some(Rc::new(self.body()?))
+            },
+            _ => {
+                if skipped {
+                    self.traveler.prev();
+                }
+                None
+            },
+        };
+
+        Ok(
+            Expression::If(
+                If {
+                    condition,
+                    body,
+                    otherwise,
+                }
+            )
+        )
+    }
+
+    // `match subject` and its arms on the lines indented below it, each
+    // `pattern -> body` with an optional `if guard` before the arrow
+    fn match_expression(&mut self) -> ParserResult<Expression> {
+        let position = self.traveler.current().position;
+        self.traveler.next();
+
+        let inline  = ::std::mem::replace(&mut self.inline, true);
+        let subject = self.expression();
+
+        self.inline = inline;
+
+        let subject = Rc::new(subject?);
+
+        let stack = match self.traveler.current().token_type {
+            TokenType::EOL => self.lines(),
+            _              => Vec::new(),
+        };
+
+        if stack.is_empty() {
+            return Err(ParserError::new_pos(position, "expected indented arms below `match`"))
+        }
+
+        let mut parser = Parser { inline: true, .. Parser::new(Traveler::new(stack)) };
+
+        let arms = parser.arms()?;
+        self.starts.extend(parser.starts);
+        self.ends.extend(parser.ends);
+
+        Ok(
+            Expression::Match(
+                Match {
+                    subject,
+                    arms,
+                    position,
+                }
+            )
+        )
+    }
+
+    fn arms(&mut self) -> ParserResult<Vec<Arm>> {
+        let mut arms = Vec::new();
+
+        loop {
+            self.skip_whitespace()?;
+
+            if self.traveler.remaining() < 2 {
+                break
+            }
+
+            let position = self.traveler.current().position;
+            let pattern  = self.pattern()?;
+
+            let guard = if self.traveler.current_content() == "if" {
+                self.traveler.next();
+
+                let outer = ::std::mem::replace(&mut self.guard, true);
+                let guard = self.expression();
+
+                self.guard = outer;
+
+                Some(Rc::new(guard?))
+            } else {
+                None
+            };
+
+            self.traveler.expect_content("->")?;
+            self.traveler.next();
+
+            let body = Rc::new(self.body()?);
+
+            arms.push(
+                Arm {
+                    pattern,
+                    guard,
+                    body,
+                    position,
+                }
+            )
+        }
+
+        Ok(arms)
+    }
+
+    fn pattern(&mut self) -> ParserResult<Pattern> {
+        let token   = self.traveler.current().clone();
+        let content = token.content().clone();
+
+        let number = |content: &str| content.parse::<f64>().map_err(|_| ParserError::new_pos(token.position, &format!("invalid number: {}", content)));
+
+        let pattern = match token.token_type {
+            TokenType::IntLiteral | TokenType::FloatLiteral => Pattern::Number(number(&content)?),
+            TokenType::BoolLiteral                          => Pattern::Bool(content == "true"),
+            TokenType::StringLiteral                        => Pattern::Str(Rc::new(content)),
+            TokenType::CharLiteral                          => Pattern::Char(content.chars().next().unwrap_or('\0')),
+
+            // there are no negative literals elsewhere, but a pattern has no other way to say one
+            TokenType::Operator if content == "-" => {
+                self.traveler.next();
+
+                match self.traveler.current().token_type {
+                    TokenType::IntLiteral | TokenType::FloatLiteral => Pattern::Number(-number(&self.traveler.current_content())?),
+                    _ => return Err(ParserError::new_pos(token.position, "expected a number after `-` in pattern")),
+                }
+            },
+
+            TokenType::Identifier if content == "_" => Pattern::Wildcard,
+            TokenType::Identifier                   => Pattern::Binding(Rc::new(content)),
+
+            TokenType::Symbol if content == "{" => return self.array_pattern(),
+
+            _ => return Err(ParserError::new_pos(token.position, &format!("expected pattern, found: {}", content))),
+        };
+
+        self.traveler.next();
+
+        Ok(pattern)
+    }
+
+    // `{a, b}`, with `...rest` or `...` last taking whatever is left
+    fn array_pattern(&mut self) -> ParserResult<Pattern> {
+        let open = self.traveler.current().position;
+        self.traveler.next();
+
+        let mut items = Vec::new();
+        let mut rest  = None;
+
+        while self.traveler.current_content() != "}" {
+            self.unfinished(open, "unclosed '{'")?;
+
+            if self.traveler.current_content() == "." {
+                for _ in 0 .. 3 {
+                    self.traveler.expect_content(".")?;
+                    self.traveler.next();
+                }
+
+                rest = Some(Rc::new(match self.traveler.current().token_type {
+                    TokenType::Identifier => self.pattern()?,
+                    _                     => Pattern::Wildcard,
+                }));
+
+                self.traveler.expect_content("}")?;
+                break
+            }
+
+            items.push(self.pattern()?);
+
+            if self.traveler.current_content() == "," {
+                self.traveler.next();
+            } else {
+                self.traveler.expect_content("}")?;
+            }
+        }
+
+        self.traveler.next();
+
+        Ok(Pattern::Array(items, rest))
+    }
+
+    // type names double as module names, as in `str.split`
+    fn module_type(&self) -> bool {
+        self.traveler.remaining() > 2 && self.traveler.tokens[self.traveler.top() + 1].content() == "."
+    }
+
+    fn identifier(&mut self) -> ParserResult<Expression> {
+        let mut a = Expression::Identifier(Rc::new(self.traveler.current_content().clone()));
+        self.traveler.next();
+
+        while self.traveler.current_content() == "." {
+            self.traveler.next();
+            self.traveler.expect(TokenType::Identifier)?;
+
+            let name = Rc::new(self.traveler.current_content());
+            self.traveler.next();
+
+            a = Expression::Field(
+                Field {
+                    object: Rc::new(a),
+                    name,
+                }
+            )
+        }
+
+        if self.traveler.remaining() > 1 {
+            match self.traveler.current_content().as_str() {
+                "," | ")" => Ok(a),
+                "["       => self.index(Rc::new(a)),
+                // `f!` calls `f` without arguments
+                "!"       => {
+                    self.traveler.next();
+
+                    Ok(
+                        Expression::Call(
+                            Call {
+                                callee: Rc::new(a),
+                                args:   Vec::new(),
+                            }
+                        )
+                    )
+                },
+                _         => self.try_call(a),
+            }
+        } else {
+            Ok(a)
+        }
+    }
+
+    // inside brackets the arrow is a lambda's again, even in a guard
+    fn enclosed<T>(&mut self, parse: impl FnOnce(&mut Parser) -> ParserResult<T>) -> ParserResult<T> {
+        let guard  = ::std::mem::replace(&mut self.guard, false);
+        let result = parse(self);
+
+        self.guard = guard;
+
+        result
+    }
+
     fn index(&mut self, id: Rc<Expression>) -> ParserResult<Expression> {
         self.traveler.next();
 
-        let index = Rc::new(self.expression()?);
+        let index = Rc::new(self.enclosed(Parser::expression)?);
 
         self.traveler.expect_content("]")?;
         self.traveler.next();
@@ -223,6 +590,7 @@ impl Parser {
     }
 
     fn array(&mut self) -> ParserResult<Expression> {
+        let open = self.traveler.current().position;
         self.traveler.next();
 
         let mut content = Vec::new();
@@ -230,6 +598,8 @@ impl Parser {
         let mut acc = 0;
 
         while self.traveler.current_content() != "}" {
+            self.unfinished(open, "unclosed '{'")?;
+
             if self.traveler.current_content() == "," {
                 self.traveler.next();
                 self.skip_whitespace()?;
@@ -297,23 +667,13 @@ impl Parser {
                 a
             }
 
-            TokenType::Identifier => {
-                let a = Expression::Identifier(Rc::new(self.traveler.current_content().clone()));
-                self.traveler.next();
+            TokenType::Identifier => self.identifier(),
 
-                if self.traveler.remaining() > 1 {
-                    match self.traveler.current_content().as_str() {
-                        "," | ")" => Ok(a),
-                        "["       => self.index(Rc::new(a)),
-                        _         => self.try_call(a),
-                    }
-                } else {
-                    Ok(a)
-                }
-            },
+            TokenType::Type if self.module_type() => self.identifier(),
 
             TokenType::Symbol => match self.traveler.current_content().as_str() {
                 "(" => {
+                    let open = self.traveler.current().position;
                     self.traveler.next();
                     if self.traveler.current_content() == ")" {
                         return Err(ParserError::new_pos(self.traveler.current().position, "empty clause '()'"))
@@ -323,6 +683,8 @@ impl Parser {
                     let mut inside = 1;
 
                     while inside != 0 {
+                        self.unfinished(open, "unclosed '('")?;
+
                         match self.traveler.current_content().as_str() {
                             "(" => inside += 1,
                             ")" => inside -= 1,
@@ -342,7 +704,7 @@ impl Parser {
                         self.traveler.expect_content(":")?;
                         self.traveler.next();
                         
-                        let t = Rc::new(self.try_type()?);
+                        let t = Some(Rc::new(self.try_type()?));
                         
                         self.traveler.expect_content("->")?;
                         self.traveler.next();
@@ -351,14 +713,14 @@ impl Parser {
     
                         Ok(Expression::Lambda(Lambda {t, params, body}))
                         
-                    } else if self.traveler.current_content() == "->" {
+                    } else if self.traveler.current_content() == "->" && !self.guard {
                         for _ in 0 .. acc {
                             self.traveler.prev();
                         }
                         
                         let params = self.params()?;
                         
-                        let t = Rc::new(Type::Any);
+                        let t = None;
                         
                         self.traveler.expect_content("->")?;
                         self.traveler.next();
@@ -372,8 +734,8 @@ impl Parser {
                         }
                         
                         self.traveler.next();
-                        
-                        let a = self.expression()?;
+
+                        let a = self.enclosed(Parser::expression)?;
 
                         self.skip_whitespace()?;
                         self.traveler.expect_content(")")?;
@@ -388,7 +750,7 @@ impl Parser {
                         }
                     }
                 }
-                "{" => self.array(),
+                "{" => self.enclosed(Parser::array),
                 _ => Err(ParserError::new_pos(self.traveler.current().position, &format!("unexpected symbol: {}", self.traveler.current_content()))),
             },
 
@@ -397,8 +759,10 @@ impl Parser {
                     self.traveler.next();
                     let body = Rc::new(self.body()?);
 
-                    Ok(Expression::Lambda(Lambda {t: Rc::new(Type::Any), params: Vec::new(), body}))
+                    Ok(Expression::Lambda(Lambda {t: None, params: Vec::new(), body}))
                 },
+                "if"    => self.if_expression(),
+                "match" => self.match_expression(),
                 _ => Err(ParserError::new_pos(self.traveler.current().position, &format!("unexpected keyword: {}", self.traveler.current_content()))),
             },
 
@@ -406,10 +770,10 @@ impl Parser {
         }
     }
 
-    fn assignment(&mut self, left: Rc<Expression>) -> ParserResult<Statement> {
+    fn assignment(&mut self, left: Rc<Expression>, position: TokenPosition) -> ParserResult<Statement> {
         self.traveler.next();
 
-        if self.traveler.current_content() == "\n" {
+        if self.traveler.current().token_type == TokenType::EOL {
             Err(ParserError::new_pos(self.traveler.current().position, &format!("expected expression, found: {:?}", self.traveler.current_content())))
         } else {
             let right = Rc::new(self.expression()?);
@@ -419,13 +783,14 @@ impl Parser {
                     Assignment {
                         left,
                         right,
+                        position,
                     }
                 )
             )
         }
     }
 
-    fn definition(&mut self, name: Rc<Expression>) -> ParserResult<Statement> {
+    fn definition(&mut self, name: Rc<Expression>, position: TokenPosition) -> ParserResult<Statement> {
         self.traveler.expect_content(":")?;
         self.traveler.next();
         
@@ -446,40 +811,121 @@ impl Parser {
 
             let right = Some(Rc::new(self.expression()?));
 
-            Ok(Statement::Definition(Definition { t, name, right }))
+            Ok(Statement::Definition(Definition { t, name, right, position, public: false }))
 
         } else {
-            Ok(Statement::Definition(Definition { t, name, right: None }))
+            Ok(Statement::Definition(Definition { t, name, right: None, position, public: false }))
+        }
+    }
+
+    // `test "name" ->` followed by a body like a lambda's, `test` is only a
+    // keyword here and remains a name everywhere else
+    fn is_test(&self) -> bool {
+        let top    = self.traveler.top();
+        let tokens = &self.traveler.tokens;
+
+        self.traveler.current_content() == "test" &&
+        top + 3 < tokens.len() &&
+        tokens[top + 1].token_type == TokenType::StringLiteral &&
+        tokens[top + 2].content() == "->"
+    }
+
+    fn test(&mut self) -> ParserResult<Statement> {
+        let position = self.traveler.current().position;
+
+        if self.nested {
+            return Err(ParserError::new_pos(position, "tests go at the top level of a file"))
+        }
+
+        self.traveler.next();
+
+        let name = Rc::new(self.traveler.current_content());
+        self.traveler.next();
+        self.traveler.next();
+
+        let body = Rc::new(self.body()?);
+
+        Ok(Statement::Test(Test { name, body, position }))
+    }
+
+    // `import a/b/c`, names joined by slashes to the end of the line
+    fn import(&mut self) -> ParserResult<Statement> {
+        let position = self.traveler.current().position;
+        self.traveler.next();
+
+        let mut path = Vec::new();
+
+        loop {
+            self.traveler.expect(TokenType::Identifier)?;
+            path.push(Rc::new(self.traveler.current_content()));
+            self.traveler.next();
+
+            if self.traveler.current_content() != "/" {
+                break
+            }
+
+            self.traveler.next();
+        }
+
+        Ok(Statement::Import(Import { path, position }))
+    }
+
+    // `pub name := ...`, only a definition can be made public
+    fn public(&mut self) -> ParserResult<Statement> {
+        let position = self.traveler.current().position;
+        self.traveler.next();
+
+        self.traveler.expect(TokenType::Identifier)?;
+
+        let name = Rc::new(Expression::Identifier(Rc::new(self.traveler.current_content())));
+        let at   = self.traveler.current().position;
+        self.traveler.next();
+
+        match self.definition(name, at) {
+            Ok(Statement::Definition(definition)) => Ok(Statement::Definition(Definition { public: true, .. definition })),
+            Ok(_)                                 => Err(ParserError::new_pos(position, "expected definition after `pub`")),
+            Err(err)                              => Err(err),
         }
     }
 
     fn statement(&mut self) -> ParserResult<Statement> {
         self.skip_whitespace()?;
+
+        let start = self.traveler.current().position;
+
         match self.traveler.current().token_type {
+            TokenType::Keyword if self.traveler.current_content() == "import" => self.import(),
+            TokenType::Keyword if self.traveler.current_content() == "pub"    => self.public(),
             TokenType::Symbol => match self.traveler.current_content().as_str() {
                 "\n" => {
                     self.traveler.next();
                     self.statement()
                 },
-                _ => Ok(Statement::Expression(Rc::new(self.expression()?))),
+                _ => Ok(Statement::Expression(Rc::new(self.expression()?), start)),
             },
+            TokenType::Identifier if self.is_test() => self.test(),
             TokenType::Identifier => {
-                let a = Expression::Identifier(Rc::new(self.traveler.current_content().clone()));
+                let a        = Expression::Identifier(Rc::new(self.traveler.current_content().clone()));
+                let position = self.traveler.current().position;
                 self.traveler.next();
 
                 if self.traveler.current_content() == "=" {
-                    self.assignment(Rc::new(a))
+                    self.assignment(Rc::new(a), position)
                 } else if self.traveler.current_content() == ":" {
-                    self.definition(Rc::new(a))
+                    self.definition(Rc::new(a), position)
                 } else {
                     self.traveler.prev();
-                    Ok(Statement::Expression(Rc::new(self.expression()?)))
+
+                    let expression = self.expression()?;
+
+                    if self.traveler.current_content() == "=" {
+                        self.assignment(Rc::new(expression), position)
+                    } else {
+                        Ok(Statement::Expression(Rc::new(expression), start))
+                    }
                 }
             },
-            TokenType::Keyword => match self.traveler.current_content().as_str() {
-                _ => Ok(Statement::Expression(Rc::new(self.expression()?))),
-            },
-            _ => Ok(Statement::Expression(Rc::new(self.expression()?))),
+            _ => Ok(Statement::Expression(Rc::new(self.expression()?), start)),
         }
     }
 
@@ -488,7 +934,7 @@ impl Parser {
 
         let mut acc = 0;
 
-        while self.traveler.current_content() != "\n" {
+        while self.traveler.current().token_type != TokenType::EOL {
             if self.traveler.current_content() == "," {
                 self.traveler.next();
 
@@ -534,71 +980,61 @@ impl Parser {
         let mut ex_stack = vec![expression];
         let mut op_stack: Vec<(Operand, u8)> = Vec::new();
 
-        op_stack.push(Operand::from_str(&self.traveler.current_content()).unwrap());
-        self.traveler.next();
+        while self.traveler.current().token_type == TokenType::Operator {
+            let (op, precedence) = Operand::from_str(&self.traveler.current_content()).unwrap();
+            let position         = self.traveler.current().position;
 
-        if self.traveler.current_content() == "\n" {
             self.traveler.next();
-        }
-
-        let term = self.term()?;
 
-        ex_stack.push(term);
-
-        let mut done = false;
-
-        while ex_stack.len() > 1 {
-            if !done {
-                if self.traveler.current().token_type != TokenType::Operator {
-                    done = true;
-                    continue
-                }
-
-                let (op, precedence) = Operand::from_str(&self.traveler.current_content()).unwrap();
+            // the right hand side may start on the next line
+            if self.traveler.current().token_type == TokenType::EOL {
                 self.traveler.next();
 
-                if precedence >= op_stack.last().unwrap().1 {
-                    let left  = ex_stack.pop().unwrap();
-                    let right = ex_stack.pop().unwrap();
-
-                    ex_stack.push(
-                        Expression::Operation(
-                            Operation {
-                                right: Rc::new(left),
-                                op:    op_stack.pop().unwrap().0,
-                                left:  Rc::new(right)
-                            }
-                        )
-                    );
-
-                    let term = self.term()?;
-
-                    ex_stack.push(term);
-                    op_stack.push((op, precedence));
-
-                    continue
+                while self.traveler.remaining() > 1 && self.traveler.current().token_type == TokenType::Indent {
+                    self.traveler.next();
                 }
+            }
 
-                let term = self.term()?;
+            self.unfinished(position, &format!("expected an operand after `{}`", op.as_str()))?;
 
-                ex_stack.push(term);
-                op_stack.push((op, precedence));
+            // whatever binds at least as tight is done, equal precedence groups to the left
+            while op_stack.last().is_some_and(|&(_, top)| top <= precedence) {
+                Self::reduce(&mut ex_stack, &mut op_stack)
             }
 
-            let left  = ex_stack.pop().unwrap();
-            let right = ex_stack.pop().unwrap();
+            op_stack.push((op, precedence));
+            ex_stack.push(self.term()?);
+        }
 
-            ex_stack.push(
-                Expression::Operation(
-                    Operation {
-                        right: Rc::new(left),
-                        op:    op_stack.pop().unwrap().0,
-                        left:  Rc::new(right)
-                    }
-                )
-            );
+        while !op_stack.is_empty() {
+            Self::reduce(&mut ex_stack, &mut op_stack)
         }
 
         Ok(ex_stack.pop().unwrap())
     }
+
+    // past the last token `current` keeps handing that one back, so loops
+    // waiting on a closing token have to stop on their own
+    fn unfinished(&self, position: TokenPosition, message: &str) -> ParserResult<()> {
+        if self.traveler.top() >= self.traveler.tokens.len() {
+            return Err(ParserError::new_pos(position, message))
+        }
+
+        Ok(())
+    }
+
+    fn reduce(ex_stack: &mut Vec<Expression>, op_stack: &mut Vec<(Operand, u8)>) {
+        let right = ex_stack.pop().unwrap();
+        let left  = ex_stack.pop().unwrap();
+
+        ex_stack.push(
+            Expression::Operation(
+                Operation {
+                    left:  Rc::new(left),
+                    op:    op_stack.pop().unwrap().0,
+                    right: Rc::new(right),
+                }
+            )
+        );
+    }
 }
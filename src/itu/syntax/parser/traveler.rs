@@ -32,6 +32,10 @@ impl Traveler {
         false
     }
 
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
     pub fn remaining(&self) -> usize {
         self.tokens.len() - self.top + 1
     }
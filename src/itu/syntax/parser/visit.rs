@@ -0,0 +1,543 @@
+#![allow(dead_code)]
+
+use std::rc::Rc;
+
+use super::*;
+
+// a pass over the tree overrides the nodes it cares about and leaves the rest
+// to the `walk_*` functions, which visit every child in source order
+pub trait Visitor: Sized {
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_assignment(&mut self, assignment: &Assignment) {
+        walk_assignment(self, assignment)
+    }
+
+    fn visit_definition(&mut self, definition: &Definition) {
+        walk_definition(self, definition)
+    }
+
+    fn visit_test(&mut self, test: &Test) {
+        walk_test(self, test)
+    }
+
+    fn visit_import(&mut self, _import: &Import) {}
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) {
+        walk_block(self, statements)
+    }
+
+    fn visit_number(&mut self, _n: f64) {}
+
+    fn visit_bool(&mut self, _b: bool) {}
+
+    fn visit_str(&mut self, _s: &Rc<String>) {}
+
+    fn visit_char(&mut self, _c: char) {}
+
+    fn visit_identifier(&mut self, _name: &Rc<String>) {}
+
+    fn visit_operation(&mut self, operation: &Operation) {
+        walk_operation(self, operation)
+    }
+
+    fn visit_operand(&mut self, _op: &Operand) {}
+
+    fn visit_call(&mut self, call: &Call) {
+        walk_call(self, call)
+    }
+
+    fn visit_lambda(&mut self, lambda: &Lambda) {
+        walk_lambda(self, lambda)
+    }
+
+    // parameters bind a name rather than refer to one
+    fn visit_param(&mut self, t: Option<&Type>, _name: &Rc<String>) {
+        if let Some(t) = t {
+            self.visit_type(t)
+        }
+    }
+
+    fn visit_array(&mut self, content: &[Rc<Expression>]) {
+        walk_array(self, content)
+    }
+
+    fn visit_index(&mut self, index: &Index) {
+        walk_index(self, index)
+    }
+
+    fn visit_field(&mut self, field: &Field) {
+        walk_field(self, field)
+    }
+
+    fn visit_if(&mut self, branch: &If) {
+        walk_if(self, branch)
+    }
+
+    fn visit_match(&mut self, subject: &Match) {
+        walk_match(self, subject)
+    }
+
+    fn visit_arm(&mut self, arm: &Arm) {
+        walk_arm(self, arm)
+    }
+
+    // patterns bind names as parameters do, and hold no expressions
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern)
+    }
+
+    fn visit_type(&mut self, t: &Type) {
+        walk_type(self, t)
+    }
+}
+
+pub fn walk_statement<V: Visitor>(visitor: &mut V, statement: &Statement) {
+    match *statement {
+        Statement::Expression(ref expression, _) => visitor.visit_expression(expression),
+        Statement::Assignment(ref assignment) => visitor.visit_assignment(assignment),
+        Statement::Definition(ref definition) => visitor.visit_definition(definition),
+        Statement::Test(ref test)             => visitor.visit_test(test),
+        Statement::Import(ref import)         => visitor.visit_import(import),
+    }
+}
+
+pub fn walk_assignment<V: Visitor>(visitor: &mut V, assignment: &Assignment) {
+    visitor.visit_expression(&assignment.left);
+    visitor.visit_expression(&assignment.right)
+}
+
+// like a parameter the name is bound here rather than referred to, passes
+// after it look at `definition.name` themselves
+pub fn walk_definition<V: Visitor>(visitor: &mut V, definition: &Definition) {
+    if let Some(ref t) = definition.t {
+        visitor.visit_type(t)
+    }
+
+    if let Some(ref right) = definition.right {
+        visitor.visit_expression(right)
+    }
+}
+
+pub fn walk_test<V: Visitor>(visitor: &mut V, test: &Test) {
+    visitor.visit_expression(&test.body)
+}
+
+pub fn walk_expression<V: Visitor>(visitor: &mut V, expression: &Expression) {
+    match *expression {
+        Expression::Block(ref statements) => visitor.visit_block(statements),
+        Expression::Number(n)             => visitor.visit_number(n),
+        Expression::Bool(b)               => visitor.visit_bool(b),
+        Expression::Str(ref s)            => visitor.visit_str(s),
+        Expression::Char(c)               => visitor.visit_char(c),
+        Expression::Identifier(ref name)  => visitor.visit_identifier(name),
+        Expression::Operation(ref op)     => visitor.visit_operation(op),
+        Expression::Call(ref call)        => visitor.visit_call(call),
+        Expression::Lambda(ref lambda)    => visitor.visit_lambda(lambda),
+        Expression::Array(ref content)    => visitor.visit_array(content),
+        Expression::Index(ref index)      => visitor.visit_index(index),
+        Expression::Field(ref field)      => visitor.visit_field(field),
+        Expression::If(ref branch)        => visitor.visit_if(branch),
+        Expression::Match(ref subject)    => visitor.visit_match(subject),
+        Expression::EOF                   => (),
+    }
+}
+
+pub fn walk_block<V: Visitor>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement)
+    }
+}
+
+pub fn walk_operation<V: Visitor>(visitor: &mut V, operation: &Operation) {
+    visitor.visit_expression(&operation.left);
+    visitor.visit_operand(&operation.op);
+    visitor.visit_expression(&operation.right)
+}
+
+pub fn walk_call<V: Visitor>(visitor: &mut V, call: &Call) {
+    visitor.visit_expression(&call.callee);
+
+    for arg in &call.args {
+        visitor.visit_expression(arg)
+    }
+}
+
+pub fn walk_lambda<V: Visitor>(visitor: &mut V, lambda: &Lambda) {
+    for (t, name) in &lambda.params {
+        visitor.visit_param(t.as_ref(), name)
+    }
+
+    if let Some(ref t) = lambda.t {
+        visitor.visit_type(t)
+    }
+
+    visitor.visit_expression(&lambda.body)
+}
+
+pub fn walk_array<V: Visitor>(visitor: &mut V, content: &[Rc<Expression>]) {
+    for expression in content {
+        visitor.visit_expression(expression)
+    }
+}
+
+pub fn walk_index<V: Visitor>(visitor: &mut V, index: &Index) {
+    visitor.visit_expression(&index.id);
+    visitor.visit_expression(&index.index)
+}
+
+// the field name belongs to the object, it is not looked up on its own
+pub fn walk_field<V: Visitor>(visitor: &mut V, field: &Field) {
+    visitor.visit_expression(&field.object)
+}
+
+pub fn walk_if<V: Visitor>(visitor: &mut V, branch: &If) {
+    visitor.visit_expression(&branch.condition);
+    visitor.visit_expression(&branch.body);
+
+    if let Some(ref otherwise) = branch.otherwise {
+        visitor.visit_expression(otherwise)
+    }
+}
+
+pub fn walk_match<V: Visitor>(visitor: &mut V, subject: &Match) {
+    visitor.visit_expression(&subject.subject);
+
+    for arm in &subject.arms {
+        visitor.visit_arm(arm)
+    }
+}
+
+pub fn walk_arm<V: Visitor>(visitor: &mut V, arm: &Arm) {
+    visitor.visit_pattern(&arm.pattern);
+
+    if let Some(ref guard) = arm.guard {
+        visitor.visit_expression(guard)
+    }
+
+    visitor.visit_expression(&arm.body)
+}
+
+pub fn walk_pattern<V: Visitor>(visitor: &mut V, pattern: &Pattern) {
+    if let Pattern::Array(ref elements, ref rest) = *pattern {
+        for element in elements {
+            visitor.visit_pattern(element)
+        }
+
+        if let Some(ref rest) = *rest {
+            visitor.visit_pattern(rest)
+        }
+    }
+}
+
+pub fn walk_type<V: Visitor>(visitor: &mut V, t: &Type) {
+    match *t {
+        Type::Mut(Some(ref t)) => visitor.visit_type(t),
+        Type::Array(ref t, ref len) => {
+            visitor.visit_type(t);
+
+            if let Some(ref len) = *len {
+                visitor.visit_expression(len)
+            }
+        },
+        _ => (),
+    }
+}
+
+// the same walk over a tree that may be rewritten in place, shared children
+// are copied on write through `Rc::make_mut`
+pub trait VisitorMut: Sized {
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement)
+    }
+
+    fn visit_assignment_mut(&mut self, assignment: &mut Assignment) {
+        walk_assignment_mut(self, assignment)
+    }
+
+    fn visit_definition_mut(&mut self, definition: &mut Definition) {
+        walk_definition_mut(self, definition)
+    }
+
+    fn visit_test_mut(&mut self, test: &mut Test) {
+        walk_test_mut(self, test)
+    }
+
+    fn visit_import_mut(&mut self, _import: &mut Import) {}
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression)
+    }
+
+    fn visit_block_mut(&mut self, statements: &mut Vec<Statement>) {
+        walk_block_mut(self, statements)
+    }
+
+    fn visit_number_mut(&mut self, _n: &mut f64) {}
+
+    fn visit_bool_mut(&mut self, _b: &mut bool) {}
+
+    fn visit_str_mut(&mut self, _s: &mut Rc<String>) {}
+
+    fn visit_char_mut(&mut self, _c: &mut char) {}
+
+    fn visit_identifier_mut(&mut self, _name: &mut Rc<String>) {}
+
+    fn visit_operation_mut(&mut self, operation: &mut Operation) {
+        walk_operation_mut(self, operation)
+    }
+
+    fn visit_operand_mut(&mut self, _op: &mut Operand) {}
+
+    fn visit_call_mut(&mut self, call: &mut Call) {
+        walk_call_mut(self, call)
+    }
+
+    fn visit_lambda_mut(&mut self, lambda: &mut Lambda) {
+        walk_lambda_mut(self, lambda)
+    }
+
+    fn visit_param_mut(&mut self, t: Option<&mut Type>, _name: &mut Rc<String>) {
+        if let Some(t) = t {
+            self.visit_type_mut(t)
+        }
+    }
+
+    fn visit_array_mut(&mut self, content: &mut Vec<Rc<Expression>>) {
+        walk_array_mut(self, content)
+    }
+
+    fn visit_index_mut(&mut self, index: &mut Index) {
+        walk_index_mut(self, index)
+    }
+
+    fn visit_field_mut(&mut self, field: &mut Field) {
+        walk_field_mut(self, field)
+    }
+
+    fn visit_if_mut(&mut self, branch: &mut If) {
+        walk_if_mut(self, branch)
+    }
+
+    fn visit_match_mut(&mut self, subject: &mut Match) {
+        walk_match_mut(self, subject)
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut Arm) {
+        walk_arm_mut(self, arm)
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern_mut(self, pattern)
+    }
+
+    fn visit_type_mut(&mut self, t: &mut Type) {
+        walk_type_mut(self, t)
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut>(visitor: &mut V, statement: &mut Statement) {
+    match *statement {
+        Statement::Expression(ref mut expression, _) => visitor.visit_expression_mut(Rc::make_mut(expression)),
+        Statement::Assignment(ref mut assignment) => visitor.visit_assignment_mut(assignment),
+        Statement::Definition(ref mut definition) => visitor.visit_definition_mut(definition),
+        Statement::Test(ref mut test)             => visitor.visit_test_mut(test),
+        Statement::Import(ref mut import)         => visitor.visit_import_mut(import),
+    }
+}
+
+pub fn walk_assignment_mut<V: VisitorMut>(visitor: &mut V, assignment: &mut Assignment) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut assignment.left));
+    visitor.visit_expression_mut(Rc::make_mut(&mut assignment.right))
+}
+
+pub fn walk_definition_mut<V: VisitorMut>(visitor: &mut V, definition: &mut Definition) {
+    if let Some(ref mut t) = definition.t {
+        visitor.visit_type_mut(t)
+    }
+
+    if let Some(ref mut right) = definition.right {
+        visitor.visit_expression_mut(Rc::make_mut(right))
+    }
+}
+
+pub fn walk_test_mut<V: VisitorMut>(visitor: &mut V, test: &mut Test) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut test.body))
+}
+
+pub fn walk_expression_mut<V: VisitorMut>(visitor: &mut V, expression: &mut Expression) {
+    match *expression {
+        Expression::Block(ref mut statements) => visitor.visit_block_mut(statements),
+        Expression::Number(ref mut n)         => visitor.visit_number_mut(n),
+        Expression::Bool(ref mut b)           => visitor.visit_bool_mut(b),
+        Expression::Str(ref mut s)            => visitor.visit_str_mut(s),
+        Expression::Char(ref mut c)           => visitor.visit_char_mut(c),
+        Expression::Identifier(ref mut name)  => visitor.visit_identifier_mut(name),
+        Expression::Operation(ref mut op)     => visitor.visit_operation_mut(op),
+        Expression::Call(ref mut call)        => visitor.visit_call_mut(call),
+        Expression::Lambda(ref mut lambda)    => visitor.visit_lambda_mut(lambda),
+        Expression::Array(ref mut content)    => visitor.visit_array_mut(content),
+        Expression::Index(ref mut index)      => visitor.visit_index_mut(index),
+        Expression::Field(ref mut field)      => visitor.visit_field_mut(field),
+        Expression::If(ref mut branch)        => visitor.visit_if_mut(branch),
+        Expression::Match(ref mut subject)    => visitor.visit_match_mut(subject),
+        Expression::EOF                       => (),
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut>(visitor: &mut V, statements: &mut Vec<Statement>) {
+    for statement in statements {
+        visitor.visit_statement_mut(statement)
+    }
+}
+
+pub fn walk_operation_mut<V: VisitorMut>(visitor: &mut V, operation: &mut Operation) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut operation.left));
+    visitor.visit_operand_mut(&mut operation.op);
+    visitor.visit_expression_mut(Rc::make_mut(&mut operation.right))
+}
+
+pub fn walk_call_mut<V: VisitorMut>(visitor: &mut V, call: &mut Call) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut call.callee));
+
+    for arg in &mut call.args {
+        visitor.visit_expression_mut(Rc::make_mut(arg))
+    }
+}
+
+pub fn walk_lambda_mut<V: VisitorMut>(visitor: &mut V, lambda: &mut Lambda) {
+    for (t, name) in &mut lambda.params {
+        visitor.visit_param_mut(t.as_mut(), name)
+    }
+
+    if let Some(ref mut t) = lambda.t {
+        visitor.visit_type_mut(Rc::make_mut(t))
+    }
+
+    visitor.visit_expression_mut(Rc::make_mut(&mut lambda.body))
+}
+
+pub fn walk_array_mut<V: VisitorMut>(visitor: &mut V, content: &mut Vec<Rc<Expression>>) {
+    for expression in content {
+        visitor.visit_expression_mut(Rc::make_mut(expression))
+    }
+}
+
+pub fn walk_index_mut<V: VisitorMut>(visitor: &mut V, index: &mut Index) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut index.id));
+    visitor.visit_expression_mut(Rc::make_mut(&mut index.index))
+}
+
+pub fn walk_field_mut<V: VisitorMut>(visitor: &mut V, field: &mut Field) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut field.object))
+}
+
+pub fn walk_if_mut<V: VisitorMut>(visitor: &mut V, branch: &mut If) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut branch.condition));
+    visitor.visit_expression_mut(Rc::make_mut(&mut branch.body));
+
+    if let Some(ref mut otherwise) = branch.otherwise {
+        visitor.visit_expression_mut(Rc::make_mut(otherwise))
+    }
+}
+
+pub fn walk_match_mut<V: VisitorMut>(visitor: &mut V, subject: &mut Match) {
+    visitor.visit_expression_mut(Rc::make_mut(&mut subject.subject));
+
+    for arm in &mut subject.arms {
+        visitor.visit_arm_mut(arm)
+    }
+}
+
+pub fn walk_arm_mut<V: VisitorMut>(visitor: &mut V, arm: &mut Arm) {
+    visitor.visit_pattern_mut(&mut arm.pattern);
+
+    if let Some(ref mut guard) = arm.guard {
+        visitor.visit_expression_mut(Rc::make_mut(guard))
+    }
+
+    visitor.visit_expression_mut(Rc::make_mut(&mut arm.body))
+}
+
+pub fn walk_pattern_mut<V: VisitorMut>(visitor: &mut V, pattern: &mut Pattern) {
+    if let Pattern::Array(ref mut elements, ref mut rest) = *pattern {
+        for element in elements {
+            visitor.visit_pattern_mut(element)
+        }
+
+        if let Some(ref mut rest) = *rest {
+            visitor.visit_pattern_mut(Rc::make_mut(rest))
+        }
+    }
+}
+
+pub fn walk_type_mut<V: VisitorMut>(visitor: &mut V, t: &mut Type) {
+    match *t {
+        Type::Mut(Some(ref mut t)) => visitor.visit_type_mut(Rc::make_mut(t)),
+        Type::Array(ref mut t, ref mut len) => {
+            visitor.visit_type_mut(Rc::make_mut(t));
+
+            if let Some(ref mut len) = *len {
+                visitor.visit_expression_mut(len)
+            }
+        },
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Bindings(Vec<String>);
+
+    impl Visitor for Bindings {
+        fn visit_pattern(&mut self, pattern: &Pattern) {
+            if let Pattern::Binding(ref name) = *pattern {
+                self.0.push(name.to_string())
+            }
+
+            walk_pattern(self, pattern)
+        }
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+            if let Pattern::Binding(ref mut name) = *pattern {
+                *name = Rc::new(name.to_uppercase())
+            }
+
+            walk_pattern_mut(self, pattern)
+        }
+    }
+
+    fn parse(source: &str) -> Vec<Statement> {
+        Parser::new(Traveler::new(lexer(&mut source.chars()).collect())).parse().unwrap()
+    }
+
+    #[test]
+    fn patterns_are_walked_into() {
+        let mut ast = parse("match xs\n  {a, {b, c}, ...rest} -> a\n");
+
+        let mut bindings = Bindings::default();
+        bindings.visit_block(&ast);
+
+        assert_eq!(bindings.0, vec!["a", "b", "c", "rest"]);
+
+        Rename.visit_block_mut(&mut ast);
+
+        let mut bindings = Bindings::default();
+        bindings.visit_block(&ast);
+
+        assert_eq!(bindings.0, vec!["A", "B", "C", "REST"]);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod runner;
+
+pub use super::*;
+
+pub use self::runner::*;
@@ -0,0 +1,55 @@
+use std::rc::Rc;
+
+use super::*;
+
+// how one test went, `failure` holding what stopped it if anything did
+pub struct Outcome {
+    pub name:     Rc<String>,
+    pub position: TokenPosition,
+    pub failure:  Option<RuntimeError>,
+}
+
+// tests are for `itu test`, programs run and compiled go without them
+pub fn without_tests(ast: &[Statement]) -> Vec<Statement> {
+    ast.iter()
+        .filter(|statement| !matches!(statement, Statement::Test(_)))
+        .cloned()
+        .collect()
+}
+
+// runs every test of the source, each on a fresh evaluator that has seen
+// the definitions of the file and its imported `units` first, so no test
+// sees what another did, and what the program itself prints or fails at
+// stays out of the report
+pub fn run(source: &str, units: &[Rc<loader::Unit>]) -> ParserResult<Vec<Outcome>> {
+    let lexer = lexer(&mut source.chars());
+    let ast   = Parser::new(Traveler::new(lexer.collect())).parse()?;
+
+    let definitions = ast.iter()
+        .filter(|statement| matches!(statement, Statement::Definition(_)))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut outcomes = Vec::new();
+
+    for statement in &ast {
+        let test = match *statement {
+            Statement::Test(ref test) => test,
+            _                         => continue,
+        };
+
+        let mut evaluator = Evaluator::new();
+        stdlib::register(&evaluator);
+        loader::register(units, &evaluator);
+
+        let result = evaluator.run(&definitions).and_then(|_| evaluator.test(test));
+
+        outcomes.push(Outcome {
+            name:     test.name.clone(),
+            position: test.position,
+            failure:  result.err(),
+        })
+    }
+
+    Ok(outcomes)
+}
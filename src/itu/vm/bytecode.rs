@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use super::*;
+
+// operands index into the constant pool, the local slots of the running
+// function, its captured upvalues or the program's function table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Constant(u32),
+    Unit,
+    True,
+    False,
+    Pop,
+
+    GetLocal(u32),
+    SetLocal(u32),
+    GetUpvalue(u32),
+    SetUpvalue(u32),
+    GetGlobal(u32),
+    SetGlobal(u32),
+    DefineGlobal(u32),
+
+    GetField(u32),
+    GetIndex,
+    SetIndex,
+    Array(u32),
+    Length,
+    Slice(u32),
+    // whether the value popped is an array, for patterns to test first
+    IsArray,
+
+    Binary(Operand),
+
+    Jump(u32),
+    JumpIfFalse(u32),
+
+    Call(u32),
+    Closure(u32),
+    Return,
+
+    // pops the value no arm of a match took, the operand naming the error
+    Fail(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Number(f64),
+    Str(Rc<String>),
+    Char(char),
+}
+
+// where a closure finds a captured name once created: a local slot of the
+// enclosing function, or one of the enclosing function's own upvalues
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capture {
+    pub local: bool,
+    pub index: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name:      Option<Rc<String>>,
+    pub arity:     usize,
+    pub slots:     usize,
+    pub captures:  Vec<Capture>,
+    pub code:      Vec<Op>,
+    pub positions: Vec<Option<TokenPosition>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub constants: Vec<Constant>,
+    pub functions: Vec<Rc<Function>>,
+    pub main:      usize,
+}
+
+#[allow(dead_code)]
+impl Constant {
+    pub fn value(&self) -> Value {
+        match *self {
+            Constant::Number(n)  => Value::Number(n),
+            Constant::Str(ref s) => Value::Str(s.clone()),
+            Constant::Char(c)    => Value::Char(c),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Function {
+    pub fn new(name: Option<Rc<String>>, arity: usize) -> Function {
+        Function {
+            name,
+            arity,
+            slots:     arity,
+            captures:  Vec::new(),
+            code:      Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+}
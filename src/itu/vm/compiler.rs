@@ -0,0 +1,594 @@
+use std::rc::Rc;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct Local {
+    name:    Rc<String>,
+    depth:   usize,
+    slot:    u32,
+    // a block's lambdas get their slot up front so closures created before
+    // the definition is reached can still capture it
+    pending: bool,
+}
+
+struct State {
+    function: Function,
+    locals:   Vec<Local>,
+    depth:    usize,
+}
+
+pub struct Compiler {
+    program:  Program,
+    states:   Vec<State>,
+    position: Option<TokenPosition>,
+}
+
+#[allow(dead_code)]
+impl Compiler {
+    pub fn new() -> Compiler {
+        Compiler {
+            program:  Program::default(),
+            states:   Vec::new(),
+            position: None,
+        }
+    }
+
+    // compiles the program into a main function taking no arguments, whose
+    // result is the value of the last statement
+    pub fn compile(mut self, ast: &[Statement]) -> RuntimeResult<Program> {
+        self.states.push(State { function: Function::new(None, 0), locals: Vec::new(), depth: 0 });
+
+        if ast.is_empty() {
+            self.emit(Op::Unit);
+        }
+
+        for (i, statement) in ast.iter().enumerate() {
+            self.statement(statement)?;
+
+            if i + 1 < ast.len() {
+                self.emit(Op::Pop);
+            }
+        }
+
+        self.emit(Op::Return);
+
+        self.program.main = self.finish() as usize;
+
+        Ok(self.program)
+    }
+
+    fn error(&self, message: &str) -> RuntimeError {
+        match self.position {
+            Some(p) => RuntimeError::new_pos(p, message),
+            None    => RuntimeError::new(message),
+        }
+    }
+
+    fn state(&mut self) -> &mut State {
+        self.states.last_mut().unwrap()
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        let position = self.position;
+        let function = &mut self.state().function;
+
+        function.code.push(op);
+        function.positions.push(position);
+
+        function.code.len() - 1
+    }
+
+    // points the jump at `at` to the next instruction to be emitted
+    fn patch(&mut self, at: usize) {
+        let function = &mut self.state().function;
+        let target   = function.code.len() as u32;
+
+        function.code[at] = match function.code[at] {
+            Op::Jump(_)        => Op::Jump(target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            op                 => op,
+        }
+    }
+
+    fn constant(&mut self, constant: Constant) -> u32 {
+        match self.program.constants.iter().position(|c| *c == constant) {
+            Some(i) => i as u32,
+            None    => {
+                self.program.constants.push(constant);
+                self.program.constants.len() as u32 - 1
+            },
+        }
+    }
+
+    fn name(&mut self, name: &Rc<String>) -> u32 {
+        self.constant(Constant::Str(name.clone()))
+    }
+
+    fn finish(&mut self) -> u32 {
+        let state = self.states.pop().unwrap();
+
+        self.program.functions.push(Rc::new(state.function));
+        self.program.functions.len() as u32 - 1
+    }
+
+    fn global(&self) -> bool {
+        self.states.len() == 1 && self.states[0].depth == 0
+    }
+
+    fn declare(&mut self, name: Rc<String>, pending: bool) -> u32 {
+        let state = self.state();
+        let slot  = state.function.slots as u32;
+
+        state.function.slots += 1;
+        state.locals.push(Local { name, depth: state.depth, slot, pending });
+
+        slot
+    }
+
+    fn local(&self, level: usize, name: &str, pending: bool) -> Option<u32> {
+        self.states[level].locals.iter().rev()
+            .find(|local| *local.name == name && (pending || !local.pending))
+            .map(|local| local.slot)
+    }
+
+    // finds `name` in the functions enclosing `level`, threading it through
+    // the captures of every function in between
+    fn upvalue(&mut self, level: usize, name: &str) -> Option<u32> {
+        if level == 0 {
+            return None
+        }
+
+        let capture = if let Some(slot) = self.local(level - 1, name, true) {
+            Capture { local: true, index: slot }
+        } else {
+            Capture { local: false, index: self.upvalue(level - 1, name)? }
+        };
+
+        let captures = &mut self.states[level].function.captures;
+
+        match captures.iter().position(|c| *c == capture) {
+            Some(i) => Some(i as u32),
+            None    => {
+                captures.push(capture);
+                Some(captures.len() as u32 - 1)
+            },
+        }
+    }
+
+    fn load(&mut self, name: &Rc<String>) {
+        let level = self.states.len() - 1;
+
+        let op = if let Some(slot) = self.local(level, name, false) {
+            Op::GetLocal(slot)
+        } else if let Some(index) = self.upvalue(level, name) {
+            Op::GetUpvalue(index)
+        } else {
+            Op::GetGlobal(self.name(name))
+        };
+
+        self.emit(op);
+    }
+
+    fn store(&mut self, name: &Rc<String>) {
+        let level = self.states.len() - 1;
+
+        let op = if let Some(slot) = self.local(level, name, false) {
+            Op::SetLocal(slot)
+        } else if let Some(index) = self.upvalue(level, name) {
+            Op::SetUpvalue(index)
+        } else {
+            Op::SetGlobal(self.name(name))
+        };
+
+        self.emit(op);
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> RuntimeResult<()> {
+        self.state().depth += 1;
+
+        let depth = self.state().depth;
+
+        for statement in statements {
+            if let Statement::Definition(ref definition) = *statement {
+                if let (Expression::Identifier(name), Some(Expression::Lambda(_))) = (&*definition.name, definition.right.as_deref()) {
+                    let declared = self.state().locals.iter().any(|local| local.depth == depth && local.name == *name);
+
+                    if !declared {
+                        self.declare(name.clone(), true);
+                    }
+                }
+            }
+        }
+
+        if statements.is_empty() {
+            self.emit(Op::Unit);
+        }
+
+        for (i, statement) in statements.iter().enumerate() {
+            self.statement(statement)?;
+
+            if i + 1 < statements.len() {
+                self.emit(Op::Pop);
+            }
+        }
+
+        self.leave();
+
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> RuntimeResult<()> {
+        self.position = Some(statement.position());
+
+        match *statement {
+            Statement::Expression(ref expression, _) => self.expression(expression),
+
+            Statement::Assignment(ref assignment) => {
+                self.expression(&assignment.right)?;
+
+                match *assignment.left {
+                    Expression::Identifier(ref name) => self.store(name),
+
+                    Expression::Index(ref index) => {
+                        self.expression(&index.id)?;
+                        self.expression(&index.index)?;
+                        self.emit(Op::SetIndex);
+                    },
+
+                    _ => return Err(self.error("invalid left-hand side of assignment")),
+                }
+
+                self.emit(Op::Unit);
+
+                Ok(())
+            },
+
+            Statement::Definition(ref definition) => {
+                let name = match *definition.name {
+                    Expression::Identifier(ref name) => name.clone(),
+                    _ => return Err(self.error("expected name")),
+                };
+
+                if self.global() {
+                    match definition.right {
+                        Some(ref right) => match **right {
+                            Expression::Lambda(ref lambda) => self.lambda(lambda, Some(name.clone()))?,
+                            ref right                      => self.expression(right)?,
+                        },
+
+                        None => {
+                            self.emit(Op::Unit);
+                        },
+                    }
+
+                    let index = self.name(&name);
+                    self.emit(Op::DefineGlobal(index));
+                } else {
+                    let slot = match definition.right {
+                        Some(ref right) => match **right {
+                            Expression::Lambda(ref lambda) => {
+                                let slot = self.reserve(&name);
+                                self.lambda(lambda, Some(name.clone()))?;
+
+                                slot
+                            },
+
+                            ref right => {
+                                self.expression(right)?;
+                                self.declare(name, false)
+                            },
+                        },
+
+                        None => {
+                            self.emit(Op::Unit);
+                            self.declare(name, false)
+                        },
+                    };
+
+                    self.emit(Op::SetLocal(slot));
+                }
+
+                self.emit(Op::Unit);
+
+                Ok(())
+            },
+
+            Statement::Test(_) => unreachable!(),
+
+            Statement::Import(_) => Err(self.error("imports need the tree-walking evaluator, run without --vm")),
+        }
+    }
+
+    // the slot a lambda definition is stored in, the one set aside when the
+    // block was entered if there is one
+    fn reserve(&mut self, name: &Rc<String>) -> u32 {
+        let state = self.state();
+        let depth = state.depth;
+
+        match state.locals.iter().rposition(|local| local.pending && local.depth == depth && local.name == *name) {
+            Some(i) => {
+                // moved to the end, it now shadows anything defined since
+                let mut local = state.locals.remove(i);
+                local.pending = false;
+
+                let slot = local.slot;
+                state.locals.push(local);
+
+                slot
+            },
+
+            None => self.declare(name.clone(), false),
+        }
+    }
+
+    fn lambda(&mut self, lambda: &Lambda, name: Option<Rc<String>>) -> RuntimeResult<()> {
+        let mut state = State { function: Function::new(name, lambda.params.len()), locals: Vec::new(), depth: 0 };
+
+        for (slot, (_, param)) in lambda.params.iter().enumerate() {
+            state.locals.push(Local { name: param.clone(), depth: 0, slot: slot as u32, pending: false })
+        }
+
+        self.states.push(state);
+
+        let outer = self.position;
+
+        self.expression(&lambda.body)?;
+        self.emit(Op::Return);
+
+        self.position = outer;
+
+        let index = self.finish();
+        self.emit(Op::Closure(index));
+
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> RuntimeResult<()> {
+        match *expression {
+            Expression::Block(ref statements) => return self.block(statements),
+
+            Expression::Number(n) => {
+                let index = self.constant(Constant::Number(n));
+                self.emit(Op::Constant(index));
+            },
+
+            Expression::Bool(b) => {
+                self.emit(if b { Op::True } else { Op::False });
+            },
+
+            Expression::Str(ref s) => {
+                let index = self.constant(Constant::Str(s.clone()));
+                self.emit(Op::Constant(index));
+            },
+
+            Expression::Char(c) => {
+                let index = self.constant(Constant::Char(c));
+                self.emit(Op::Constant(index));
+            },
+
+            Expression::Identifier(ref name) => self.load(name),
+
+            Expression::Operation(ref operation) => {
+                self.expression(&operation.left)?;
+                self.expression(&operation.right)?;
+                self.emit(Op::Binary(operation.op));
+            },
+
+            Expression::Call(ref call) => {
+                self.expression(&call.callee)?;
+
+                for arg in &call.args {
+                    self.expression(arg)?
+                }
+
+                self.emit(Op::Call(call.args.len() as u32));
+            },
+
+            Expression::Lambda(ref lambda) => return self.lambda(lambda, None),
+
+            Expression::Array(ref content) => {
+                for element in content {
+                    self.expression(element)?
+                }
+
+                self.emit(Op::Array(content.len() as u32));
+            },
+
+            Expression::Index(ref index) => {
+                self.expression(&index.id)?;
+                self.expression(&index.index)?;
+                self.emit(Op::GetIndex);
+            },
+
+            Expression::Field(ref field) => {
+                self.expression(&field.object)?;
+
+                let index = self.name(&field.name);
+                self.emit(Op::GetField(index));
+            },
+
+            Expression::If(ref branch) => {
+                self.expression(&branch.condition)?;
+
+                let otherwise = self.emit(Op::JumpIfFalse(0));
+                self.expression(&branch.body)?;
+
+                let end = self.emit(Op::Jump(0));
+                self.patch(otherwise);
+
+                match branch.otherwise {
+                    Some(ref otherwise) => self.expression(otherwise)?,
+                    None                => {
+                        self.emit(Op::Unit);
+                    },
+                }
+
+                self.patch(end);
+            },
+
+            Expression::Match(ref subject) => {
+                self.expression(&subject.subject)?;
+
+                self.state().depth += 1;
+
+                // the subject is kept in a slot no name refers to
+                let slot = self.declare(Rc::new(String::new()), false);
+                self.emit(Op::SetLocal(slot));
+
+                let outer    = self.position;
+                let mut ends = Vec::new();
+
+                for arm in &subject.arms {
+                    self.state().depth += 1;
+                    self.position = Some(arm.position);
+
+                    let mut fails = Vec::new();
+                    self.pattern(&arm.pattern, slot, &mut fails);
+
+                    if let Some(ref guard) = arm.guard {
+                        self.expression(guard)?;
+                        fails.push(self.emit(Op::JumpIfFalse(0)));
+                    }
+
+                    self.expression(&arm.body)?;
+                    ends.push(self.emit(Op::Jump(0)));
+
+                    for fail in fails {
+                        self.patch(fail)
+                    }
+
+                    self.leave();
+                }
+
+                self.position = Some(subject.position);
+
+                let message = self.name(&Rc::new("no arm matched".to_owned()));
+
+                self.emit(Op::GetLocal(slot));
+                self.emit(Op::Fail(message));
+
+                self.position = outer;
+
+                for end in ends {
+                    self.patch(end)
+                }
+
+                self.leave();
+            },
+
+            Expression::EOF => {
+                self.emit(Op::Unit);
+            },
+        }
+
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        let state = self.state();
+        let depth = state.depth;
+
+        state.locals.retain(|local| local.depth < depth);
+        state.depth -= 1;
+    }
+
+    // tests the value in `slot` against `pattern`, binding its names, with a
+    // jump to be patched to the next arm for every test that fails
+    fn pattern(&mut self, pattern: &Pattern, slot: u32, fails: &mut Vec<usize>) {
+        let constant = match *pattern {
+            Pattern::Wildcard => return,
+
+            Pattern::Binding(ref name) => {
+                self.emit(Op::GetLocal(slot));
+
+                let local = self.declare(name.clone(), false);
+                self.emit(Op::SetLocal(local));
+
+                return
+            },
+
+            Pattern::Bool(b) => {
+                self.emit(Op::GetLocal(slot));
+                self.emit(if b { Op::True } else { Op::False });
+                self.emit(Op::Binary(Operand::Equal));
+
+                fails.push(self.emit(Op::JumpIfFalse(0)));
+
+                return
+            },
+
+            Pattern::Array(ref elements, ref rest) => {
+                let len = self.constant(Constant::Number(elements.len() as f64));
+
+                // anything but an array falls through to the next arm, as in the evaluator
+                self.emit(Op::GetLocal(slot));
+                self.emit(Op::IsArray);
+
+                fails.push(self.emit(Op::JumpIfFalse(0)));
+
+                self.emit(Op::GetLocal(slot));
+                self.emit(Op::Length);
+                self.emit(Op::Constant(len));
+                self.emit(Op::Binary(if rest.is_some() { Operand::GtEqual } else { Operand::Equal }));
+
+                fails.push(self.emit(Op::JumpIfFalse(0)));
+
+                for (i, element) in elements.iter().enumerate() {
+                    if *element == Pattern::Wildcard {
+                        continue
+                    }
+
+                    let at = self.constant(Constant::Number(i as f64));
+
+                    self.emit(Op::GetLocal(slot));
+                    self.emit(Op::Constant(at));
+                    self.emit(Op::GetIndex);
+
+                    self.unpack(element, fails)
+                }
+
+                if let Some(ref rest) = *rest {
+                    if **rest != Pattern::Wildcard {
+                        self.emit(Op::GetLocal(slot));
+                        self.emit(Op::Slice(elements.len() as u32));
+
+                        self.unpack(rest, fails)
+                    }
+                }
+
+                return
+            },
+
+            Pattern::Number(n)  => Constant::Number(n),
+            Pattern::Str(ref s) => Constant::Str(s.clone()),
+            Pattern::Char(c)    => Constant::Char(c),
+        };
+
+        let index = self.constant(constant);
+
+        self.emit(Op::GetLocal(slot));
+        self.emit(Op::Constant(index));
+        self.emit(Op::Binary(Operand::Equal));
+
+        fails.push(self.emit(Op::JumpIfFalse(0)));
+    }
+
+    // matches the value on top of the stack, stored straight into the slot
+    // of a binding and into one of its own to be tested otherwise
+    fn unpack(&mut self, pattern: &Pattern, fails: &mut Vec<usize>) {
+        let name = match *pattern {
+            Pattern::Binding(ref name) => name.clone(),
+            _                          => Rc::new(String::new()),
+        };
+
+        let slot = self.declare(name, false);
+        self.emit(Op::SetLocal(slot));
+
+        if let Pattern::Binding(_) = *pattern {
+            return
+        }
+
+        self.pattern(pattern, slot, fails)
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt::Write;
+
+use super::*;
+
+// a readable listing of every function in the program, each instruction
+// with the source line it came from and what its operand refers to; given
+// the source, the line itself is shown wherever it changes
+pub fn disassemble(program: &Program, source: Option<&str>) -> String {
+    let lines = source.map(|source| source.lines().collect::<Vec<_>>());
+
+    let mut out = String::new();
+
+    for (index, function) in program.functions.iter().enumerate() {
+        let name = match function.name {
+            Some(ref name) => name.to_string(),
+            None if index == program.main => "<main>".to_owned(),
+            None           => "<lambda>".to_owned(),
+        };
+
+        if index > 0 {
+            out.push('\n')
+        }
+
+        writeln!(out, "fn {} #{}: arity {}, slots {}", name, index, function.arity, function.slots).unwrap();
+
+        for (i, capture) in function.captures.iter().enumerate() {
+            let from = if capture.local { "local" } else { "upvalue" };
+            writeln!(out, "  capture {} <- {} {}", i, from, capture.index).unwrap();
+        }
+
+        let mut last = None;
+
+        for (at, op) in function.code.iter().enumerate() {
+            let position = function.positions[at];
+
+            if position != last {
+                if let (Some(p), Some(ref lines)) = (position, &lines) {
+                    if let Some(line) = lines.get(p.line - 1) {
+                        writeln!(out, "  ; {}", line.trim()).unwrap();
+                    }
+                }
+
+                last = position
+            }
+
+            let line = match position {
+                Some(p) => format!("{:>4}", p.line),
+                None    => "   -".to_owned(),
+            };
+
+            let (mnemonic, operand) = mnemonic(op);
+
+            let mut instruction = match operand {
+                Some(operand) => format!("{:<14} {}", mnemonic, operand),
+                None          => mnemonic.to_owned(),
+            };
+
+            if let Some(comment) = comment(program, op) {
+                instruction = format!("{:<22} ; {}", instruction, comment)
+            }
+
+            writeln!(out, "  {:04} {} | {}", at, line, instruction).unwrap();
+        }
+    }
+
+    out
+}
+
+fn mnemonic(op: &Op) -> (&'static str, Option<String>) {
+    match *op {
+        Op::Constant(i)     => ("CONSTANT", Some(i.to_string())),
+        Op::Unit            => ("UNIT", None),
+        Op::True            => ("TRUE", None),
+        Op::False           => ("FALSE", None),
+        Op::Pop             => ("POP", None),
+        Op::GetLocal(i)     => ("GET_LOCAL", Some(i.to_string())),
+        Op::SetLocal(i)     => ("SET_LOCAL", Some(i.to_string())),
+        Op::GetUpvalue(i)   => ("GET_UPVALUE", Some(i.to_string())),
+        Op::SetUpvalue(i)   => ("SET_UPVALUE", Some(i.to_string())),
+        Op::GetGlobal(i)    => ("GET_GLOBAL", Some(i.to_string())),
+        Op::SetGlobal(i)    => ("SET_GLOBAL", Some(i.to_string())),
+        Op::DefineGlobal(i) => ("DEFINE_GLOBAL", Some(i.to_string())),
+        Op::GetField(i)     => ("GET_FIELD", Some(i.to_string())),
+        Op::GetIndex        => ("GET_INDEX", None),
+        Op::SetIndex        => ("SET_INDEX", None),
+        Op::Array(n)        => ("ARRAY", Some(n.to_string())),
+        Op::Length          => ("LENGTH", None),
+        Op::Slice(n)        => ("SLICE", Some(n.to_string())),
+        Op::IsArray         => ("IS_ARRAY", None),
+        Op::Binary(ref op)  => ("BINARY", Some(format!("{:?}", op))),
+        Op::Jump(i)         => ("JUMP", Some(format!("{:04}", i))),
+        Op::JumpIfFalse(i)  => ("JUMP_IF_FALSE", Some(format!("{:04}", i))),
+        Op::Call(n)         => ("CALL", Some(n.to_string())),
+        Op::Closure(i)      => ("CLOSURE", Some(format!("#{}", i))),
+        Op::Return          => ("RETURN", None),
+        Op::Fail(i)         => ("FAIL", Some(i.to_string())),
+    }
+}
+
+fn comment(program: &Program, op: &Op) -> Option<String> {
+    match *op {
+        Op::Constant(i)     |
+        Op::GetGlobal(i)    |
+        Op::SetGlobal(i)    |
+        Op::DefineGlobal(i) |
+        Op::GetField(i)     |
+        Op::Fail(i)         => program.constants.get(i as usize).map(|c| match *c {
+            Constant::Number(n)  => n.to_string(),
+            Constant::Str(ref s) => format!("{:?}", s),
+            Constant::Char(c)    => format!("{:?}", c),
+        }),
+
+        Op::Closure(i) => program.functions.get(i as usize).map(|f| match f.name {
+            Some(ref name) => name.to_string(),
+            None           => "<lambda>".to_owned(),
+        }),
+
+        _ => None,
+    }
+}
@@ -0,0 +1,563 @@
+use std::rc::Rc;
+
+use super::*;
+
+// layout of a .ituc file, integers are little endian:
+//
+//   magic     b"ITUC"
+//   version   u16
+//   constants u32 count, then a tag byte and payload each
+//   functions u32 count, then per function its name, arity, slots,
+//             captures, code and a run-length table of source positions
+//   main      u32 index into the function table
+pub const MAGIC:   &[u8; 4] = b"ITUC";
+pub const VERSION: u16      = 3;
+
+const OPERANDS: [Operand; 12] = [
+    Operand::Pow,
+    Operand::Mul, Operand::Div, Operand::Mod,
+    Operand::Add, Operand::Sub,
+    Operand::Equal, Operand::NEqual,
+    Operand::Lt, Operand::Gt, Operand::LtEqual, Operand::GtEqual,
+];
+
+pub fn encode(program: &Program) -> Vec<u8> {
+    let mut writer = Writer { bytes: Vec::new() };
+
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+    writer.u32(program.constants.len() as u32);
+
+    for constant in &program.constants {
+        match *constant {
+            Constant::Number(n) => {
+                writer.u8(0);
+                writer.bytes.extend_from_slice(&n.to_le_bytes())
+            },
+
+            Constant::Str(ref s) => {
+                writer.u8(1);
+                writer.string(s)
+            },
+
+            Constant::Char(c) => {
+                writer.u8(2);
+                writer.u32(c as u32)
+            },
+        }
+    }
+
+    writer.u32(program.functions.len() as u32);
+
+    for function in &program.functions {
+        writer.function(function)
+    }
+
+    writer.u32(program.main as u32);
+
+    writer.bytes
+}
+
+pub fn decode(bytes: &[u8]) -> RuntimeResult<Program> {
+    let mut reader = Reader { bytes, at: 0 };
+
+    if reader.take(4)? != MAGIC {
+        return Err(RuntimeError::new("not an .ituc file"))
+    }
+
+    let version = u16::from_le_bytes([reader.u8()?, reader.u8()?]);
+
+    if version != VERSION {
+        return Err(RuntimeError::new(&format!("unsupported .ituc version {}, expected {}", version, VERSION)))
+    }
+
+    let mut program = Program::default();
+
+    for _ in 0 .. reader.u32()? {
+        let constant = match reader.u8()? {
+            0   => Constant::Number(reader.f64()?),
+            1   => Constant::Str(Rc::new(reader.string()?)),
+            2   => match ::std::char::from_u32(reader.u32()?) {
+                Some(c) => Constant::Char(c),
+                None    => return Err(RuntimeError::new("invalid char constant")),
+            },
+            tag => return Err(RuntimeError::new(&format!("unknown constant tag {}", tag))),
+        };
+
+        program.constants.push(constant)
+    }
+
+    for _ in 0 .. reader.u32()? {
+        let function = reader.function()?;
+        program.functions.push(Rc::new(function))
+    }
+
+    program.main = reader.u32()? as usize;
+
+    if program.main >= program.functions.len() {
+        return Err(RuntimeError::new("main function out of range"))
+    }
+
+    for function in &program.functions {
+        verify(&program, function)?
+    }
+
+    Ok(program)
+}
+
+// checks every operand points at something that exists and every
+// instruction finds the values it takes on the stack, so a damaged file is
+// rejected up front instead of tripping up the vm
+fn verify(program: &Program, function: &Function) -> RuntimeResult<()> {
+    let name = |i: u32| matches!(program.constants.get(i as usize), Some(&Constant::Str(_)));
+
+    // a closure captures from the slots and upvalues of the function creating it
+    let captures = |i: u32| program.functions.get(i as usize).is_some_and(|closure| {
+        closure.captures.iter().all(|capture| match capture.local {
+            true  => (capture.index as usize) < function.slots,
+            false => (capture.index as usize) < function.captures.len(),
+        })
+    });
+
+    if function.arity > function.slots {
+        return Err(RuntimeError::new("function takes more arguments than it has slots"))
+    }
+
+    for op in &function.code {
+        let valid = match *op {
+            Op::Constant(i)     => (i as usize) < program.constants.len(),
+            Op::GetLocal(i)     |
+            Op::SetLocal(i)     => (i as usize) < function.slots,
+            Op::GetUpvalue(i)   |
+            Op::SetUpvalue(i)   => (i as usize) < function.captures.len(),
+            Op::GetGlobal(i)    |
+            Op::SetGlobal(i)    |
+            Op::DefineGlobal(i) |
+            Op::GetField(i)     |
+            Op::Fail(i)         => name(i),
+            Op::Jump(i)         |
+            Op::JumpIfFalse(i)  => (i as usize) < function.code.len(),
+            Op::Closure(i)      => captures(i),
+            _                   => true,
+        };
+
+        if !valid {
+            return Err(RuntimeError::new(&format!("invalid operand in {:?}", op)))
+        }
+    }
+
+    match function.code.last() {
+        Some(&Op::Return) => heights(function),
+        _                 => Err(RuntimeError::new("function does not end in a return")),
+    }
+}
+
+// follows every path through the code with the number of values above the
+// slots, which has to be the same however an instruction is reached
+fn heights(function: &Function) -> RuntimeResult<()> {
+    let mut heights = vec![None; function.code.len()];
+    let mut pending = vec![(0, 0)];
+
+    while let Some((at, height)) = pending.pop() {
+        match heights[at] {
+            Some(known) if known == height => continue,
+            Some(known) => {
+                return Err(RuntimeError::new(&format!("stack heights {} and {} meet at instruction {}", known, height, at)))
+            },
+            None => heights[at] = Some(height),
+        }
+
+        let op = function.code[at];
+        let (takes, gives) = effect(&op);
+
+        if height < takes {
+            return Err(RuntimeError::new(&format!("{:?} at instruction {} takes {} value(s) from a stack of {}", op, at, takes, height)))
+        }
+
+        let height = height - takes + gives;
+
+        // the last instruction is a return, so the next one always exists
+        match op {
+            Op::Jump(target)        => pending.push((target as usize, height)),
+            Op::JumpIfFalse(target) => pending.extend(vec![(target as usize, height), (at + 1, height)]),
+            Op::Return | Op::Fail(_) => (),
+            _                       => pending.push((at + 1, height)),
+        }
+    }
+
+    Ok(())
+}
+
+// how many values an instruction takes off the stack and how many it leaves
+fn effect(op: &Op) -> (usize, usize) {
+    match *op {
+        Op::Constant(_)   |
+        Op::Unit          |
+        Op::True          |
+        Op::False         |
+        Op::GetLocal(_)   |
+        Op::GetUpvalue(_) |
+        Op::GetGlobal(_)  |
+        Op::Closure(_)    => (0, 1),
+
+        Op::Pop             |
+        Op::SetLocal(_)     |
+        Op::SetUpvalue(_)   |
+        Op::SetGlobal(_)    |
+        Op::DefineGlobal(_) |
+        Op::JumpIfFalse(_)  |
+        Op::Return          |
+        Op::Fail(_)         => (1, 0),
+
+        Op::GetField(_) |
+        Op::Length      |
+        Op::Slice(_)    |
+        Op::IsArray     => (1, 1),
+
+        Op::GetIndex  |
+        Op::Binary(_) => (2, 1),
+
+        Op::SetIndex => (3, 0),
+        Op::Array(n) => (n as usize, 1),
+        Op::Call(n)  => (n as usize + 1, 1),
+        Op::Jump(_)  => (0, 0),
+    }
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value)
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes())
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes())
+    }
+
+    fn function(&mut self, function: &Function) {
+        match function.name {
+            Some(ref name) => {
+                self.u8(1);
+                self.string(name)
+            },
+            None => self.u8(0),
+        }
+
+        self.u32(function.arity as u32);
+        self.u32(function.slots as u32);
+
+        self.u32(function.captures.len() as u32);
+
+        for capture in &function.captures {
+            self.u8(capture.local as u8);
+            self.u32(capture.index)
+        }
+
+        self.u32(function.code.len() as u32);
+
+        for op in &function.code {
+            self.op(op)
+        }
+
+        // only the instructions where the position changes are recorded,
+        // line 0 stands for no position
+        let mut lines = Vec::new();
+        let mut last  = None;
+
+        for (i, position) in function.positions.iter().enumerate() {
+            if i == 0 || *position != last {
+                lines.push((i, *position));
+                last = *position
+            }
+        }
+
+        self.u32(lines.len() as u32);
+
+        for (i, position) in lines {
+            let (line, col) = position.map_or((0, 0), |p| (p.line, p.col));
+
+            self.u32(i as u32);
+            self.u32(line as u32);
+            self.u32(col as u32)
+        }
+    }
+
+    fn op(&mut self, op: &Op) {
+        let (code, operand) = match *op {
+            Op::Constant(i)     => (0, Some(i)),
+            Op::Unit            => (1, None),
+            Op::True            => (2, None),
+            Op::False           => (3, None),
+            Op::Pop             => (4, None),
+            Op::GetLocal(i)     => (5, Some(i)),
+            Op::SetLocal(i)     => (6, Some(i)),
+            Op::GetUpvalue(i)   => (7, Some(i)),
+            Op::SetUpvalue(i)   => (8, Some(i)),
+            Op::GetGlobal(i)    => (9, Some(i)),
+            Op::SetGlobal(i)    => (10, Some(i)),
+            Op::DefineGlobal(i) => (11, Some(i)),
+            Op::GetField(i)     => (12, Some(i)),
+            Op::GetIndex        => (13, None),
+            Op::SetIndex        => (14, None),
+            Op::Array(n)        => (15, Some(n)),
+            Op::Binary(operand) => (16, Some(OPERANDS.iter().position(|o| *o == operand).unwrap() as u32)),
+            Op::Jump(i)         => (17, Some(i)),
+            Op::JumpIfFalse(i)  => (18, Some(i)),
+            Op::Call(n)         => (19, Some(n)),
+            Op::Closure(i)      => (20, Some(i)),
+            Op::Return          => (21, None),
+            Op::Length          => (22, None),
+            Op::Slice(n)        => (23, Some(n)),
+            Op::Fail(i)         => (24, Some(i)),
+            Op::IsArray         => (25, None),
+        };
+
+        self.u8(code);
+
+        if let Some(operand) = operand {
+            self.u32(operand)
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at:    usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> RuntimeResult<&'a [u8]> {
+        if self.at + len > self.bytes.len() {
+            return Err(RuntimeError::new("unexpected end of .ituc file"))
+        }
+
+        let bytes = &self.bytes[self.at .. self.at + len];
+        self.at += len;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> RuntimeResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> RuntimeResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn f64(&mut self) -> RuntimeResult<f64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    fn string(&mut self) -> RuntimeResult<String> {
+        let len = self.u32()? as usize;
+
+        match String::from_utf8(self.take(len)?.to_vec()) {
+            Ok(s)  => Ok(s),
+            Err(_) => Err(RuntimeError::new("invalid utf-8 in string constant")),
+        }
+    }
+
+    fn function(&mut self) -> RuntimeResult<Function> {
+        let name = match self.u8()? {
+            0 => None,
+            _ => Some(Rc::new(self.string()?)),
+        };
+
+        let arity = self.u32()? as usize;
+
+        let mut function = Function::new(name, arity);
+        function.slots   = self.u32()? as usize;
+
+        for _ in 0 .. self.u32()? {
+            let local = self.u8()? != 0;
+            let index = self.u32()?;
+
+            function.captures.push(Capture { local, index })
+        }
+
+        for _ in 0 .. self.u32()? {
+            let op = self.op()?;
+            function.code.push(op)
+        }
+
+        function.positions = vec![None; function.code.len()];
+
+        let mut lines = Vec::new();
+
+        for _ in 0 .. self.u32()? {
+            let (i, line, col) = (self.u32()? as usize, self.u32()? as usize, self.u32()? as usize);
+            lines.push((i, if line == 0 { None } else { Some(TokenPosition { line, col }) }))
+        }
+
+        for (n, &(start, position)) in lines.iter().enumerate() {
+            let end = lines.get(n + 1).map_or(function.code.len(), |&(i, _)| i);
+
+            if start > end || end > function.code.len() {
+                return Err(RuntimeError::new("malformed line table"))
+            }
+
+            for slot in &mut function.positions[start .. end] {
+                *slot = position
+            }
+        }
+
+        Ok(function)
+    }
+
+    fn op(&mut self) -> RuntimeResult<Op> {
+        let op = match self.u8()? {
+            0  => Op::Constant(self.u32()?),
+            1  => Op::Unit,
+            2  => Op::True,
+            3  => Op::False,
+            4  => Op::Pop,
+            5  => Op::GetLocal(self.u32()?),
+            6  => Op::SetLocal(self.u32()?),
+            7  => Op::GetUpvalue(self.u32()?),
+            8  => Op::SetUpvalue(self.u32()?),
+            9  => Op::GetGlobal(self.u32()?),
+            10 => Op::SetGlobal(self.u32()?),
+            11 => Op::DefineGlobal(self.u32()?),
+            12 => Op::GetField(self.u32()?),
+            13 => Op::GetIndex,
+            14 => Op::SetIndex,
+            15 => Op::Array(self.u32()?),
+            16 => match OPERANDS.get(self.u32()? as usize) {
+                Some(operand) => Op::Binary(*operand),
+                None          => return Err(RuntimeError::new("unknown operator")),
+            },
+            17 => Op::Jump(self.u32()?),
+            18 => Op::JumpIfFalse(self.u32()?),
+            19 => Op::Call(self.u32()?),
+            20 => Op::Closure(self.u32()?),
+            21 => Op::Return,
+            22 => Op::Length,
+            23 => Op::Slice(self.u32()?),
+            24 => Op::Fail(self.u32()?),
+            25 => Op::IsArray,
+            op => return Err(RuntimeError::new(&format!("unknown opcode {}", op))),
+        };
+
+        Ok(op)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(source: &str) -> Program {
+        let lexer      = lexer(&mut source.chars());
+        let mut parser = Parser::new(Traveler::new(lexer.collect()));
+
+        Compiler::new().compile(&parser.parse().unwrap()).unwrap()
+    }
+
+    // a program of a single main function running `code`
+    fn main(code: Vec<Op>, slots: usize) -> Vec<u8> {
+        let mut function = Function::new(None, 0);
+
+        function.slots     = slots;
+        function.positions = vec![None; code.len()];
+        function.code      = code;
+
+        encode(&Program { constants: vec![Constant::Number(1.0)], functions: vec![Rc::new(function)], main: 0 })
+    }
+
+    const SOURCE: &str = "add := (a, b) -> a + b\nxs := {1, 2, 3}\nadd xs[0], match xs\n  {_, ...rest} -> rest[1]\n  _ -> 0\n";
+
+    #[test]
+    fn round_trips() {
+        let bytes = encode(&program(SOURCE));
+        assert_eq!(encode(&decode(&bytes).unwrap()), bytes);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = encode(&program(SOURCE));
+
+        for len in 0 .. bytes.len() {
+            assert!(decode(&bytes[.. len]).is_err(), "a file cut at {} of {} bytes was accepted", len, bytes.len())
+        }
+    }
+
+    #[test]
+    fn garbled_files_do_not_panic() {
+        let bytes = encode(&program(SOURCE));
+
+        for at in 0 .. bytes.len() {
+            for garbage in &[0x00, 0x01, 0x7f, 0xff] {
+                let mut garbled = bytes.clone();
+                garbled[at] ^= garbage;
+
+                let _ = decode(&garbled);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_instructions_short_of_values() {
+        let short = vec![
+            vec![Op::Call(2), Op::Return],
+            vec![Op::Constant(0), Op::Array(5), Op::Return],
+            vec![Op::Slice(1), Op::Return],
+            vec![Op::Binary(Operand::Add), Op::Return],
+            vec![Op::Return],
+            vec![Op::Unit, Op::Pop, Op::Fail(0), Op::Return],
+        ];
+
+        for code in short {
+            assert!(decode(&main(code.clone(), 0)).is_err(), "{:?} was accepted", code)
+        }
+    }
+
+    #[test]
+    fn rejects_paths_meeting_at_different_heights() {
+        // the branch skips a push the fall through makes
+        let code = vec![Op::True, Op::JumpIfFalse(3), Op::Unit, Op::Unit, Op::Return];
+        assert!(decode(&main(code, 0)).is_err());
+
+        let code = vec![Op::True, Op::JumpIfFalse(4), Op::Unit, Op::Jump(5), Op::Unit, Op::Return];
+        assert!(decode(&main(code, 0)).is_ok());
+    }
+
+    #[test]
+    fn slices_past_the_end_are_errors() {
+        let code    = vec![Op::Constant(0), Op::Array(1), Op::Slice(3), Op::Return];
+        let program = decode(&main(code, 0)).unwrap();
+
+        assert!(Machine::new().run(program).is_err());
+    }
+
+    #[test]
+    fn rejects_captures_past_the_creating_function() {
+        let mut closure = Function::new(None, 0);
+
+        closure.captures  = vec![Capture { local: true, index: 3 }];
+        closure.code      = vec![Op::Unit, Op::Return];
+        closure.positions = vec![None; 2];
+
+        let mut main = Function::new(None, 0);
+
+        main.slots     = 1;
+        main.code      = vec![Op::Closure(0), Op::Return];
+        main.positions = vec![None; 2];
+
+        let bytes = encode(&Program { constants: Vec::new(), functions: vec![Rc::new(closure), Rc::new(main)], main: 1 });
+        assert!(decode(&bytes).is_err());
+    }
+}
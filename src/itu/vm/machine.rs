@@ -0,0 +1,394 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::*;
+
+// a captured variable, it points into the stack while the function owning
+// the slot is running and holds the value itself once that function returns
+#[derive(Debug, Clone)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Value),
+}
+
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+struct Frame {
+    closure: Rc<Closure>,
+    ip:      usize,
+    base:    usize,
+}
+
+pub struct Machine {
+    pub globals: HashMap<Rc<String>, Value>,
+    program:     Program,
+    stack:       Vec<Value>,
+    frames:      Vec<Frame>,
+    open:        Vec<Rc<RefCell<Upvalue>>>,
+}
+
+#[allow(dead_code)]
+impl Machine {
+    pub fn new() -> Machine {
+        Machine {
+            globals: HashMap::new(),
+            program: Program::default(),
+            stack:   Vec::new(),
+            frames:  Vec::new(),
+            open:    Vec::new(),
+        }
+    }
+
+    pub fn define(&mut self, name: Rc<String>, value: Value) {
+        self.globals.insert(name, value);
+    }
+
+    // runs the main function of the program, giving back its result
+    pub fn run(&mut self, program: Program) -> RuntimeResult<Value> {
+        let main = Closure {
+            function: program.functions[program.main].clone(),
+            upvalues: Vec::new(),
+        };
+
+        self.program = program;
+
+        self.stack.clear();
+        self.frames.clear();
+        self.open.clear();
+
+        Caller::call(self, &Value::Compiled(Rc::new(main)), Vec::new())
+    }
+
+    fn error(&self, message: &str) -> RuntimeError {
+        let position = self.frames.last().and_then(|frame| {
+            frame.closure.function.positions[frame.ip.saturating_sub(1)]
+        });
+
+        match position {
+            Some(p) => RuntimeError::new_pos(p, message),
+            None    => RuntimeError::new(message),
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+
+    fn name(&self, index: u32) -> Rc<String> {
+        match self.program.constants[index as usize] {
+            Constant::Str(ref name) => name.clone(),
+            ref c                   => panic!("expected name constant, found {:?}", c),
+        }
+    }
+
+    fn capture(&mut self, at: usize) -> Rc<RefCell<Upvalue>> {
+        for upvalue in &self.open {
+            if let Upvalue::Open(i) = *upvalue.borrow() {
+                if i == at {
+                    return upvalue.clone()
+                }
+            }
+        }
+
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(at)));
+        self.open.push(upvalue.clone());
+
+        upvalue
+    }
+
+    // moves every captured slot at or above `from` off the stack
+    fn close(&mut self, from: usize) {
+        let stack = &self.stack;
+
+        self.open.retain(|upvalue| {
+            let at = match *upvalue.borrow() {
+                Upvalue::Open(at) if at >= from => at,
+                _                               => return true,
+            };
+
+            *upvalue.borrow_mut() = Upvalue::Closed(stack[at].clone());
+
+            false
+        })
+    }
+
+    // calls the callee sitting below the top `argc` values, a compiled
+    // closure gets a frame of its own and a builtin leaves its result
+    fn invoke(&mut self, argc: usize) -> RuntimeResult<()> {
+        let callee = self.stack[self.stack.len() - 1 - argc].clone();
+
+        match callee {
+            Value::Compiled(ref closure) => {
+                let function = closure.function.clone();
+
+                if function.arity != argc {
+                    return Err(self.error(&format!("{} expected {} argument(s), found {}", callee, function.arity, argc)))
+                }
+
+                // the frame of main is not a call
+                if self.frames.len() > DEPTH {
+                    return Err(self.error(&format!("stack overflow, calls nested deeper than {}", DEPTH)))
+                }
+
+                let base = self.stack.len() - argc;
+
+                for _ in function.arity .. function.slots {
+                    self.stack.push(Value::Unit)
+                }
+
+                self.frames.push(Frame { closure: closure.clone(), ip: 0, base });
+
+                Ok(())
+            },
+
+            Value::Builtin(ref builtin) => {
+                if builtin.arity != argc {
+                    return Err(self.error(&format!("{} expected {} argument(s), found {}", callee, builtin.arity, argc)))
+                }
+
+                let args = self.stack.split_off(self.stack.len() - argc);
+                self.pop();
+
+                let value = (builtin.function)(self, args).map_err(|err| match err.position {
+                    Some(_) => err,
+                    None    => self.error(&format!("{}", err)),
+                })?;
+
+                self.stack.push(value);
+
+                Ok(())
+            },
+
+            ref v => Err(self.error(&format!("cannot call {}", v.type_name()))),
+        }
+    }
+
+    // executes until the frame at `depth` returns
+    fn execute(&mut self, depth: usize) -> RuntimeResult<Value> {
+        loop {
+            let op = {
+                let frame = self.frames.last_mut().unwrap();
+                let op    = frame.closure.function.code[frame.ip];
+
+                frame.ip += 1;
+
+                op
+            };
+
+            match op {
+                Op::Constant(index) => {
+                    let value = self.program.constants[index as usize].value();
+                    self.stack.push(value)
+                },
+
+                Op::Unit  => self.stack.push(Value::Unit),
+                Op::True  => self.stack.push(Value::Bool(true)),
+                Op::False => self.stack.push(Value::Bool(false)),
+
+                Op::Pop => {
+                    self.pop();
+                },
+
+                Op::GetLocal(slot) => {
+                    let base = self.frames.last().unwrap().base;
+                    let value = self.stack[base + slot as usize].clone();
+
+                    self.stack.push(value)
+                },
+
+                Op::SetLocal(slot) => {
+                    let base = self.frames.last().unwrap().base;
+                    let value = self.pop();
+
+                    self.stack[base + slot as usize] = value
+                },
+
+                Op::GetUpvalue(index) => {
+                    let upvalue = self.frames.last().unwrap().closure.upvalues[index as usize].clone();
+
+                    let value = match *upvalue.borrow() {
+                        Upvalue::Open(at)      => self.stack[at].clone(),
+                        Upvalue::Closed(ref v) => v.clone(),
+                    };
+
+                    self.stack.push(value)
+                },
+
+                Op::SetUpvalue(index) => {
+                    let upvalue = self.frames.last().unwrap().closure.upvalues[index as usize].clone();
+                    let value   = self.pop();
+
+                    let open = match *upvalue.borrow() {
+                        Upvalue::Open(at)  => Some(at),
+                        Upvalue::Closed(_) => None,
+                    };
+
+                    match open {
+                        Some(at) => self.stack[at] = value,
+                        None     => *upvalue.borrow_mut() = Upvalue::Closed(value),
+                    }
+                },
+
+                Op::GetGlobal(index) => {
+                    let name = self.name(index);
+
+                    match self.globals.get(&name) {
+                        Some(value) => {
+                            let value = value.clone();
+                            self.stack.push(value)
+                        },
+                        None => return Err(self.error(&format!("undefined name: {}", name))),
+                    }
+                },
+
+                Op::SetGlobal(index) => {
+                    let name  = self.name(index);
+                    let value = self.pop();
+
+                    match self.globals.get_mut(&name) {
+                        Some(global) => *global = value,
+                        None         => return Err(self.error(&format!("undefined name: {}", name))),
+                    }
+                },
+
+                Op::DefineGlobal(index) => {
+                    let name  = self.name(index);
+                    let value = self.pop();
+
+                    self.globals.insert(name, value);
+                },
+
+                Op::GetField(index) => {
+                    let name   = self.name(index);
+                    let object = self.pop();
+
+                    let value = object.field(&name).map_err(|err| self.error(&err))?;
+                    self.stack.push(value)
+                },
+
+                Op::GetIndex => {
+                    let at = self.pop();
+                    let id = self.pop();
+
+                    let value = id.index(&at).map_err(|err| self.error(&err))?;
+                    self.stack.push(value)
+                },
+
+                Op::SetIndex => {
+                    let at    = self.pop();
+                    let array = self.pop();
+                    let value = self.pop();
+
+                    array.set_index(&at, value).map_err(|err| self.error(&err))?
+                },
+
+                Op::Array(len) => {
+                    let content = self.stack.split_off(self.stack.len() - len as usize);
+                    self.stack.push(Value::array(content))
+                },
+
+                Op::Length => match self.pop() {
+                    Value::Array(ref content) => {
+                        let len = content.borrow().len();
+                        self.stack.push(Value::Number(len as f64))
+                    },
+                    ref v => return Err(self.error(&format!("expected array, found {}", v.type_name()))),
+                },
+
+                Op::Slice(from) => match self.pop() {
+                    Value::Array(ref content) => {
+                        let rest = match content.borrow().get(from as usize ..) {
+                            Some(rest) => rest.to_vec(),
+                            None       => return Err(self.error(&format!("slice from {} of an array of {}", from, content.borrow().len()))),
+                        };
+
+                        self.stack.push(Value::array(rest))
+                    },
+                    ref v => return Err(self.error(&format!("expected array, found {}", v.type_name()))),
+                },
+
+                Op::IsArray => {
+                    let array = matches!(self.pop(), Value::Array(_));
+                    self.stack.push(Value::Bool(array))
+                },
+
+                Op::Binary(ref operand) => {
+                    let right = self.pop();
+                    let left  = self.pop();
+
+                    let value = Value::operate(operand, left, right).map_err(|err| self.error(&err))?;
+                    self.stack.push(value)
+                },
+
+                Op::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+
+                Op::JumpIfFalse(target) => match self.pop() {
+                    Value::Bool(true)  => (),
+                    Value::Bool(false) => self.frames.last_mut().unwrap().ip = target as usize,
+                    ref v              => return Err(self.error(&format!("expected bool condition, found {}", v.type_name()))),
+                },
+
+                Op::Call(argc) => self.invoke(argc as usize)?,
+
+                Op::Closure(index) => {
+                    let function = self.program.functions[index as usize].clone();
+                    let frame    = self.frames.last().unwrap();
+
+                    let (base, enclosing) = (frame.base, frame.closure.clone());
+
+                    let upvalues = function.captures.iter()
+                        .map(|capture| if capture.local {
+                            self.capture(base + capture.index as usize)
+                        } else {
+                            enclosing.upvalues[capture.index as usize].clone()
+                        })
+                        .collect();
+
+                    self.stack.push(Value::Compiled(Rc::new(Closure { function, upvalues })))
+                },
+
+                Op::Return => {
+                    let value = self.pop();
+                    let frame = self.frames.pop().unwrap();
+
+                    self.close(frame.base);
+                    self.stack.truncate(frame.base - 1);
+
+                    if self.frames.len() == depth {
+                        return Ok(value)
+                    }
+
+                    self.stack.push(value)
+                },
+
+                Op::Fail(index) => {
+                    let message = self.name(index);
+                    let value   = self.pop();
+
+                    return Err(self.error(&format!("{} {}", message, value)))
+                },
+            }
+        }
+    }
+}
+
+impl Caller for Machine {
+    fn call(&mut self, callee: &Value, args: Vec<Value>) -> RuntimeResult<Value> {
+        let depth = self.frames.len();
+        let argc  = args.len();
+
+        self.stack.push(callee.clone());
+        self.stack.extend(args);
+
+        self.invoke(argc)?;
+
+        match *callee {
+            Value::Compiled(_) => self.execute(depth),
+            _                  => Ok(self.pop()),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+pub mod bytecode;
+pub mod compiler;
+pub mod machine;
+pub mod format;
+pub mod disasm;
+
+pub use super::*;
+
+pub use self::bytecode::*;
+pub use self::compiler::*;
+pub use self::format::{encode, decode};
+pub use self::disasm::*;
+pub use self::machine::{Closure, Machine};
@@ -1,59 +1,995 @@
+#![allow(clippy::upper_case_acronyms, clippy::module_inception)]
+
 extern crate colored;
 use colored::*;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(any(feature = "serde", feature = "lsp"))]
+#[macro_use]
+extern crate serde_json;
+#[cfg(test)]
+extern crate wasmparser;
+
 mod itu;
 use itu::*;
 
-fn main() {
-    let test = r#"
-a := (b): vector ->
-  10 + b
-    "#;
+use std::env;
+use std::fs;
+use std::process;
+use std::thread;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+fn report(source: &str, position: Option<TokenPosition>, message: &str, color: Color) {
+    match position {
+        Some(pos) => {
+            let mut lines = source.lines();
+
+            for _ in 0 .. pos.line - 1 {
+                lines.next();
+            }
+
+            let source_pos = format!("ln {}, cl {}| ", pos.line, pos.col).yellow();
+
+            match lines.next() {
+                Some(line) => println!("{}{}", source_pos, line),
+                None       => unreachable!(),
+            }
+
+            let mut error = String::from("");
+
+            for _ in 0 .. pos.col + source_pos.len() {
+                error.push(' ')
+            }
+
+            error.push_str("^ ");
+            error.push_str(message);
 
-    let lexer = lexer(&mut test.chars());
+            println!("{}", error.color(color));
+        },
+
+        None => println!("{}", message.color(color)),
+    }
+}
+
+fn parse(source: &str) -> Option<Vec<Statement>> {
+    let lexer = lexer(&mut source.chars());
 
     let traveler   = Traveler::new(lexer.collect());
     let mut parser = Parser::new(traveler);
 
     match parser.parse() {
-        Err(err)  => match err {
-            ParserError {ref value, ref position} => {
-                match *position {
-                    Some(ref pos) => {
-                        let mut lines = test.lines();
-
-                        for _ in 0 .. pos.line - 1 {
-                            lines.next();
-                        }
-
-                        let source_pos = format!("ln {}, cl {}| ", pos.line, pos.col).yellow();
-
-                        match lines.next() {
-                            Some(line) => println!("{}{}", source_pos, line),
-                            None       => unreachable!(),
-                        }
-
-                        let mut error = String::from("");
-                        
-                        for _ in 0 .. pos.col + source_pos.len() {
-                            error.push_str(" ")
-                        }
-                        
-                        error.push_str("^ ");
-
-                        
-                        match *value {
-                            ParserErrorValue::Constant(ref a) => error.push_str(a),
-                        }
-                        
-                        println!("{}", error.red());
-                        
-                    },
-                    
-                    None => (),
-                }
+        Ok(ast)  => Some(ast),
+        Err(err) => {
+            report_syntax(source, &err);
+            None
+        },
+    }
+}
+
+fn report_syntax(source: &str, err: &ParserError) {
+    let ParserErrorValue::Constant(ref value) = err.value;
+    report(source, err.position, value, Color::Red)
+}
+
+fn tree(source: &str, style: Option<&str>) -> bool {
+    // the lossless tree has room for what does not parse
+    if style == Some("cst") {
+        print!("{:#?}", cst(source));
+        return true
+    }
+
+    match parse(source) {
+        Some(ast) => show(&ast, style),
+        None      => false,
+    }
+}
+
+fn show(ast: &[Statement], style: Option<&str>) -> bool {
+    match style {
+        None | Some("debug") => println!("{:#?}", ast),
+        Some("itu")          => print!("{}", format::print(ast)),
+        #[cfg(feature = "serde")]
+        Some("json")         => println!("{}", format::to_json(ast)),
+        #[cfg(not(feature = "serde"))]
+        Some("json")         => {
+            println!("{}", "json output needs itu built with the serde feature".red());
+            return false
+        },
+        Some(style)          => {
+            println!("{}", format!("unknown format: {}", style).red());
+            return false
+        },
+    }
+
+    true
+}
+
+#[cfg(feature = "serde")]
+fn unjson(source: &str, style: Option<&str>) -> bool {
+    match format::from_json(source) {
+        Ok(ast)  => show(&ast, style),
+        Err(why) => {
+            println!("{}", format!("invalid syntax tree: {}", why).red());
+            false
+        },
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn unjson(_: &str, _: Option<&str>) -> bool {
+    println!("{}", "reading json needs itu built with the serde feature".red());
+    false
+}
+
+fn report_semantic(source: &str, err: &SemanticError, color: Color) {
+    let SemanticErrorValue::Constant(ref value) = err.value;
+    report(source, err.position, value, color);
+
+    if let Some((position, ref note)) = err.note {
+        report(source, Some(position), note, Color::Cyan)
+    }
+}
+
+// loads what the program imports, from its package if it is in one, running
+// the modules only if it is run too
+fn imports(source: &str, path: &str, ast: &[Statement], run: bool) -> Option<Vec<Rc<loader::Unit>>> {
+    let units = project::loader(Path::new(path), run)
+        .map_err(|why| loader::LoadError::new(&why))
+        .and_then(|mut loader| loader.imports(ast, Some(Path::new(path))));
+
+    match units {
+        Ok(units) => Some(units),
+        Err(err)  => {
+            report_load(source, &err);
+            None
+        },
+    }
+}
+
+fn report_load(source: &str, err: &loader::LoadError) {
+    let loader::LoadErrorValue::Constant(ref value) = err.value;
+
+    // errors inside a module point into that module's file
+    let file = match err.file {
+        Some(ref file) => file,
+        None           => return report(source, err.position, value, Color::Red),
+    };
+
+    println!("{}", format!("in {}", file.display()).red());
+
+    match fs::read_to_string(file) {
+        Ok(module) => report(&module, err.position, value, Color::Red),
+        Err(_)     => println!("{}", value.red()),
+    }
+}
+
+fn types(source: &str, path: &str) -> bool {
+    let ast = match parse(source) {
+        Some(ast) => ast,
+        None      => return false,
+    };
+
+    let units = match imports(source, path, &ast, false) {
+        Some(units) => units,
+        None        => return false,
+    };
+
+    let mut inferer = Inferer::new();
+    stdlib::declare(&mut inferer);
+    loader::declare(&units, &mut inferer);
+
+    match inferer.infer(&ast) {
+        Ok(types) => {
+            for (name, scheme) in types {
+                println!("{}: {}", name, scheme)
+            }
+
+            true
+        },
+
+        Err(err) => {
+            report_semantic(source, &err, Color::Red);
+            false
+        },
+    }
+}
+
+fn check(source: &str, path: &str) -> bool {
+    let ast = match parse(source) {
+        Some(ast) => ast,
+        None      => return false,
+    };
+
+    let units = match imports(source, path, &ast, false) {
+        Some(units) => units,
+        None        => return false,
+    };
+
+    let mut resolver = Resolver::new();
+    stdlib::declare_names(&mut resolver);
+    loader::declare_names(&units, &mut resolver);
+
+    let resolution = resolver.resolve(&ast);
+
+    for warning in &resolution.warnings {
+        report_semantic(source, warning, Color::Yellow)
+    }
+
+    for err in &resolution.errors {
+        report_semantic(source, err, Color::Red)
+    }
+
+    if !resolution.errors.is_empty() {
+        return false
+    }
+
+    let result = MutabilityChecker::new().check(&ast)
+        .and_then(|_| {
+            let mut inferer = Inferer::new();
+            stdlib::declare(&mut inferer);
+            loader::declare(&units, &mut inferer);
+
+            inferer.infer(&ast).map(|_| ())
+        });
+
+    match result {
+        Ok(_)    => true,
+        Err(err) => {
+            report_semantic(source, &err, Color::Red);
+            false
+        },
+    }
+}
+
+fn runtime_error(source: Option<&str>, err: &RuntimeError) {
+    match source {
+        Some(source) => {
+            let RuntimeErrorValue::Constant(ref value) = err.value;
+            report(source, err.position, value, Color::Red)
+        },
+
+        // without the source there is no line to point into
+        None => println!("{}", format!("{}", err).red()),
+    }
+}
+
+fn compile(source: &str) -> Option<vm::Program> {
+    let ast = testing::without_tests(&parse(source)?);
+
+    match vm::Compiler::new().compile(&ast) {
+        Ok(program) => Some(program),
+        Err(err)    => {
+            runtime_error(Some(source), &err);
+            None
+        },
+    }
+}
+
+fn execute(source: Option<&str>, program: vm::Program) -> bool {
+    let mut machine = vm::Machine::new();
+
+    for (name, value) in stdlib::globals() {
+        machine.define(name, value)
+    }
+
+    finish(source, machine.run(program))
+}
+
+fn finish(source: Option<&str>, result: RuntimeResult<Value>) -> bool {
+    match result {
+        Ok(Value::Unit) => true,
+        Ok(value)       => {
+            println!("{}", value);
+            true
+        },
+
+        Err(err) => {
+            runtime_error(source, &err);
+            false
+        },
+    }
+}
+
+fn run(source: &str, path: &str, vm: bool) -> bool {
+    if vm {
+        return match compile(source) {
+            Some(program) => execute(Some(source), program),
+            None          => false,
+        }
+    }
+
+    let ast = match parse(source) {
+        Some(ast) => testing::without_tests(&ast),
+        None      => return false,
+    };
+
+    let units = match imports(source, path, &ast, true) {
+        Some(units) => units,
+        None        => return false,
+    };
+
+    let mut evaluator = Evaluator::new();
+    stdlib::register(&evaluator);
+    loader::register(&units, &evaluator);
+
+    finish(Some(source), evaluator.run(&ast))
+}
+
+fn load(path: &str) -> Option<vm::Program> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(why)  => {
+            println!("{}", format!("unable to read {}: {}", path, why).red());
+            return None
+        },
+    };
+
+    match vm::decode(&bytes) {
+        Ok(program) => Some(program),
+        Err(err)    => {
+            println!("{}", format!("{}: {}", path, err).red());
+            None
+        },
+    }
+}
+
+fn save(out: &Path, bytes: &[u8]) -> bool {
+    match fs::write(out, bytes) {
+        Ok(_)    => true,
+        Err(why) => {
+            println!("{}", format!("unable to write {}: {}", out.display(), why).red());
+            false
+        },
+    }
+}
+
+fn write(source: &str, path: &str) -> bool {
+    match compile(source) {
+        Some(program) => save(&Path::new(path).with_extension("ituc"), &vm::encode(&program)),
+        None          => false,
+    }
+}
+
+fn emit(source: &str, path: &str, target: Option<&str>) -> bool {
+    let ast = match parse(source) {
+        Some(ast) => testing::without_tests(&ast),
+        None      => return false,
+    };
+
+    // backends only see the one file, what it imports would have to be emitted with it
+    let import = ast.iter().find_map(|statement| match *statement {
+        Statement::Import(ref import) => Some(import.position),
+        _                             => None,
+    });
+
+    if let Some(position) = import {
+        report(source, Some(position), "imports are not supported by emit", Color::Red);
+        return false
+    }
+
+    // backends lean on the inferred types, so only well-typed programs are emitted
+    let mut inferer = Inferer::new();
+    stdlib::declare(&mut inferer);
+
+    if let Err(err) = inferer.infer(&ast) {
+        report_semantic(source, &err, Color::Red);
+        return false
+    }
+
+    let result = match target {
+        Some("c")    => codegen::c::emit(&ast, &inferer).map(String::into_bytes),
+        Some("lua")  => codegen::lua::emit(&ast, &inferer).map(String::into_bytes),
+        Some("rust") => codegen::rust::emit(&ast, &inferer).map(String::into_bytes),
+        Some("wasm") => codegen::wasm::emit(&ast, &inferer),
+        Some(t)      => {
+            println!("{}", format!("unknown target: {}", t).red());
+            return false
+        },
+        None         => usage(),
+    };
+
+    match result {
+        // binary modules go beside the source, like `compile` output
+        Ok(code) if target == Some("wasm") => save(&Path::new(path).with_extension("wasm"), &code),
+
+        Ok(code) => {
+            print!("{}", String::from_utf8_lossy(&code));
+            true
+        },
+
+        Err(err) => {
+            let codegen::CodegenErrorValue::Constant(ref value) = err.value;
+            report(source, err.position, value, Color::Red);
+
+            false
+        },
+    }
+}
+
+fn fmt(source: &str, path: &str, check: bool, align: bool) -> bool {
+    let formatted = match format::format(source, align) {
+        Ok(formatted) => formatted,
+        Err(err)      => {
+            report_syntax(source, &err);
+            return false
+        },
+    };
+
+    if formatted == source {
+        return true
+    }
+
+    if !check {
+        return save(Path::new(path), formatted.as_bytes())
+    }
+
+    // point at the first line that would change
+    let message = format!("{} would be reformatted", path);
+
+    match source.lines().zip(formatted.lines()).position(|(a, b)| a != b) {
+        Some(line) => report(source, Some(TokenPosition::new(line + 1, 0)), &message, Color::Yellow),
+        None       => println!("{}", message.yellow()),
+    }
+
+    false
+}
+
+// a site for all the files at once, in a doc directory beside the first
+fn doc(paths: &[&String], style: Option<&str>) -> bool {
+    let mut pages = Vec::new();
+
+    for path in paths {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(why)   => {
+                println!("{}", format!("unable to read {}: {}", path, why).red());
+                return false
             },
+        };
+
+        let name = Path::new(path).file_stem().map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+
+        match doc::Page::new(&name, &source) {
+            Ok(page)   => pages.push(page),
+            Err(err)   => {
+                report_syntax(&source, &err);
+                return false
+            },
+        }
+    }
+
+    let files = match style {
+        None | Some("html")   => doc::html::site(&pages),
+        Some("md")            => doc::markdown::site(&pages),
+        Some(style)           => {
+            println!("{}", format!("unknown format: {}", style).red());
+            return false
         },
-        Ok(stuff) => println!("{:#?}", stuff),
+    };
+
+    let out = Path::new(paths[0]).with_file_name("doc");
+
+    if let Err(why) = fs::create_dir_all(&out) {
+        println!("{}", format!("unable to create {}: {}", out.display(), why).red());
+        return false
+    }
+
+    files.iter().all(|(name, content)| save(&out.join(name), content.as_bytes()))
+}
+
+// the .itu files under `path`, or `path` itself when it is a file
+fn sources(path: &Path, found: &mut Vec<PathBuf>) -> bool {
+    if !path.is_dir() {
+        found.push(path.to_path_buf());
+        return true
+    }
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(why)    => {
+            println!("{}", format!("unable to read {}: {}", path.display(), why).red());
+            return false
+        },
+    };
+
+    let mut paths = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect::<Vec<_>>();
+    paths.sort();
+
+    paths.iter().all(|path| {
+        if path.is_dir() {
+            sources(path, found)
+        } else {
+            if path.extension().is_some_and(|extension| extension == "itu") {
+                found.push(path.clone())
+            }
+
+            true
+        }
+    })
+}
+
+fn test(path: &str) -> bool {
+    let mut paths = Vec::new();
+
+    if !sources(Path::new(path), &mut paths) {
+        return false
+    }
+
+    // imports are found from the directory tested, wherever the file is in it
+    let root = Path::new(path).join("file");
+    let root = if Path::new(path).is_dir() { root.as_path() } else { Path::new(path) };
+
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for path in &paths {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(why)   => {
+                println!("{}", format!("unable to read {}: {}", path.display(), why).red());
+                return false
+            },
+        };
+
+        // a file that does not parse is reported by the runner, and modules
+        // run when loaded, so only for files with something to test
+        let ast = match Parser::new(Traveler::new(lexer(&mut source.chars()).collect())).parse() {
+            Ok(ast) if !ast.iter().any(|statement| matches!(statement, Statement::Test(_))) => continue,
+            Ok(ast) => ast,
+            Err(_)  => Vec::new(),
+        };
+
+        let units = project::loader(root, true)
+            .map_err(|why| loader::LoadError::new(&why))
+            .and_then(|mut loader| loader.imports(&ast, Some(path)));
+
+        let units = match units {
+            Ok(units) => units,
+            Err(err)  => {
+                println!("{}", path.display());
+                report_load(&source, &err);
+                failed += 1;
+                continue
+            },
+        };
+
+        let outcomes = match testing::run(&source, &units) {
+            Ok(outcomes) => outcomes,
+            Err(err)     => {
+                println!("{}", path.display());
+                report_syntax(&source, &err);
+                failed += 1;
+                continue
+            },
+        };
+
+        if outcomes.is_empty() {
+            continue
+        }
+
+        println!("{}", path.display());
+
+        for outcome in &outcomes {
+            match outcome.failure {
+                None => {
+                    println!("  {} {}", "PASS".green(), outcome.name);
+                    passed += 1
+                },
+
+                Some(ref err) => {
+                    println!("  {} {} ({}:{})", "FAIL".red(), outcome.name, path.display(), outcome.position.line);
+                    runtime_error(Some(&source), err);
+                    failed += 1
+                },
+            }
+        }
+    }
+
+    let summary = format!("{} passed, {} failed", passed, failed);
+
+    if failed > 0 {
+        println!("{}", summary.red());
+    } else {
+        println!("{}", summary.green());
+    }
+
+    failed == 0
+}
+
+// the package `path` is in, telling why when there is none
+fn package(path: &Path) -> Option<project::Package> {
+    let root = match project::Package::find(path) {
+        Some(root) => root,
+        None       => {
+            println!("{}", format!("no {} in {} or any directory above it", project::MANIFEST, path.display()).red());
+            return None
+        },
+    };
+
+    match project::Package::open(&root) {
+        Ok(package) => Some(package),
+        Err(why)    => {
+            println!("{}", why.red());
+            None
+        },
+    }
+}
+
+// a directory for the package with its manifest and an entry point
+fn new(name: &str) -> bool {
+    if let Err(why) = project::check_name(name) {
+        println!("{}", why.red());
+        return false
+    }
+
+    let root = Path::new(name);
+
+    if root.exists() {
+        println!("{}", format!("{} already exists", root.display()).red());
+        return false
+    }
+
+    let entry = root.join("src").join("main.itu");
+
+    if let Err(why) = fs::create_dir_all(root.join("src")) {
+        println!("{}", format!("unable to create {}: {}", root.display(), why).red());
+        return false
+    }
+
+    if !save(&root.join(project::MANIFEST), project::Manifest::template(name).as_bytes()) || !save(&entry, b"io.print \"hello, world\"\n") {
+        return false
+    }
+
+    println!("{}", format!("created package {} in {}", name, root.display()).green());
+    true
+}
+
+// checks every source file of the package the way `check` does, which
+// loads whatever they import from the package and its dependencies
+fn build(path: &str) -> bool {
+    let package = match package(Path::new(path)) {
+        Some(package) => package,
+        None          => return false,
+    };
+
+    if let Err(why) = package.dependencies() {
+        println!("{}", why.red());
+        return false
+    }
+
+    if !package.entry().is_file() {
+        println!("{}", format!("the entry point {} does not exist", package.entry().display()).red());
+        return false
+    }
+
+    let mut files = Vec::new();
+
+    for dir in package.sources() {
+        if !dir.is_dir() {
+            println!("{}", format!("the source directory {} does not exist", dir.display()).red());
+            return false
+        }
+
+        if !sources(&dir, &mut files) {
+            return false
+        }
+    }
+
+    let mut failed = 0;
+
+    for file in &files {
+        println!("{} {}", "checking".green(), file.strip_prefix(&package.root).unwrap_or(file).display());
+
+        let success = match fs::read_to_string(file) {
+            Ok(source) => check(&source, &file.to_string_lossy()),
+            Err(why)   => {
+                println!("{}", format!("unable to read {}: {}", file.display(), why).red());
+                false
+            },
+        };
+
+        if !success {
+            failed += 1
+        }
+    }
+
+    let name = match package.manifest.version {
+        Some(ref version) => format!("{} {}", package.name(), version),
+        None              => package.name().to_string(),
+    };
+
+    if failed > 0 {
+        println!("{}", format!("{} failed to build, {} of {} file(s) with errors", name, failed, files.len()).red());
+    } else {
+        println!("{}", format!("built {}, {} file(s)", name, files.len()).green());
+    }
+
+    failed == 0
+}
+
+// the lint config nearest the file, in its directory or one above it
+fn lint_config(path: &str) -> Option<lint::Config> {
+    let path = fs::canonicalize(path).ok()?;
+
+    let file = path.ancestors().skip(1).map(|dir| dir.join(lint::CONFIG)).find(|file| file.is_file())?;
+
+    let text = match fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(why) => {
+            println!("{}", format!("unable to read {}: {}", file.display(), why).red());
+            process::exit(1)
+        },
+    };
+
+    match lint::Config::parse(&text) {
+        Ok(config) => Some(config),
+        Err(why)   => {
+            println!("{}", format!("{}: {}", file.display(), why).red());
+            process::exit(1)
+        },
+    }
+}
+
+fn lint(source: &str, path: &str, fix: bool) -> bool {
+    let config = lint_config(path).unwrap_or_default();
+
+    let mut lints = match lint::lint(source, &config) {
+        Ok(lints) => lints,
+        Err(err)  => {
+            report_syntax(source, &err);
+            return false
+        },
+    };
+
+    let mut source = source.to_string();
+
+    if fix {
+        let fixes = lints.iter().filter_map(|lint| lint.fix.as_ref()).collect::<Vec<_>>();
+
+        if !fixes.is_empty() {
+            let (fixed, applied) = lint::apply(&source, &fixes);
+
+            if !save(Path::new(path), fixed.as_bytes()) {
+                return false
+            }
+
+            println!("{}", format!("applied {} fix(es) to {}", applied, path).green());
+
+            source = fixed;
+            lints  = match lint::lint(&source, &config) {
+                Ok(lints) => lints,
+                Err(err)  => {
+                    report_syntax(&source, &err);
+                    return false
+                },
+            };
+        }
+    }
+
+    for lint in &lints {
+        let color = match lint.level {
+            lint::Level::Deny => Color::Red,
+            _                 => Color::Yellow,
+        };
+
+        report(&source, lint.position, &format!("{} [{}]", lint.message, lint.rule.id), color);
+
+        if let Some((position, ref note)) = lint.note {
+            report(&source, Some(position), note, Color::Cyan)
+        }
+
+        if let Some(ref fix) = lint.fix {
+            println!("{}", format!("  fix: {}", fix.message).cyan())
+        }
+    }
+
+    !lints.iter().any(|lint| lint.level == lint::Level::Deny)
+}
+
+fn highlight(source: &str, style: Option<&str>) -> bool {
+    match style {
+        None | Some("ansi") => print!("{}", format::ansi(source)),
+        Some("html")        => print!("{}", format::html(source)),
+        Some(style)         => {
+            println!("{}", format!("unknown format: {}", style).red());
+            return false
+        },
+    }
+
+    true
+}
+
+#[cfg(feature = "lsp")]
+fn lsp() -> bool {
+    let stdin  = ::std::io::stdin();
+    let stdout = ::std::io::stdout();
+
+    match lsp::Server::new().serve(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(shutdown) => shutdown,
+        Err(why)     => {
+            eprintln!("{}", format!("lsp: {}", why).red());
+            false
+        },
+    }
+}
+
+#[cfg(not(feature = "lsp"))]
+fn lsp() -> bool {
+    println!("{}", "the language server needs itu built with the lsp feature".red());
+    false
+}
+
+fn usage() -> ! {
+    println!("usage: itu <command> [options] <file>");
+    println!();
+    println!("commands:");
+    println!("  new        create a package of the name given, with a manifest and src/main.itu");
+    println!("  build      check every source of the package in the directory, the current one by default");
+    println!("  run        evaluate the program or .ituc file, printing its final value, or the package's entry");
+    println!("  parse      print the syntax tree of a program or .json tree, see --format");
+    println!("  check      report name, mutability and type errors");
+    println!("  types      print the inferred type of every top-level binding");
+    println!("  compile    write the program as bytecode to a .ituc file beside it, programs without imports only");
+    println!("  disasm     print the bytecode of a program or .ituc file");
+    println!("  emit       translate the program for another platform, see --target, programs without imports only");
+    println!("  fmt        rewrite the file in the canonical style");
+    println!("  lint       report likely mistakes by rule, levels set in a .itu-lint file");
+    println!("  doc        write html documentation for the files to a doc directory beside them");
+    println!("  highlight  print the program with its tokens colored, see --format");
+    println!("  test       run the `test` blocks of a file or of every .itu file under a directory");
+    println!("  grammar    print a textmate grammar for editors, takes no file");
+    println!("  lsp        serve the language server protocol over stdio, takes no file");
+    println!();
+    println!("options:");
+    println!("  --vm                       run on the bytecode vm instead of the tree-walking evaluator, without imports");
+    println!("  --format=debug|itu|json|cst");
+    println!("                             how `parse` prints the tree, as rust debug output, back as itu, as json");
+    println!("                             or as the lossless tree with every token and all trivia");
+    println!("  --format=html|md           what `doc` writes, a site or markdown pages");
+    println!("  --format=ansi|html         how `highlight` colors, for the terminal or as spans classed by token type");
+    println!("  --target=c|lua|rust|wasm   what `emit` writes, wasm to a .wasm file beside the source");
+    println!("  --check                    have `fmt` only report whether the file would change");
+    println!("  --align                    have `fmt` line up the `=` of consecutive definitions");
+    println!("  --fix                      have `lint` make the changes it suggests");
+    println!("  --rules                    have `lint` list its rules and their default levels, takes no file");
+    println!();
+    println!("environment:");
+    println!("  ITU_PATH                   directories searched for imported modules after the program's own");
+
+    process::exit(1)
+}
+
+// the interpreter recurses on the native stack once per call, which the
+// main thread has too little of to reach the depth it allows
+const STACK: usize = 1 << 30;
+
+fn main() {
+    let itu = thread::Builder::new().stack_size(STACK).spawn(itu).expect("unable to start itu");
+
+    if itu.join().is_err() {
+        process::exit(101)
+    }
+}
+
+fn itu() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let (flags, args): (Vec<&String>, Vec<&String>) = args.iter().partition(|arg| arg.starts_with("--"));
+
+    // the editor hands documents over the protocol instead
+    if args.first().is_some_and(|command| *command == "lsp") {
+        process::exit(if lsp() { 0 } else { 1 })
+    }
+
+    if args.first().is_some_and(|command| *command == "grammar") {
+        print!("{}", format::grammar());
+        return
+    }
+
+    if args.first().is_some_and(|command| *command == "lint") && flags.iter().any(|flag| *flag == "--rules") {
+        for rule in lint::RULES {
+            println!("{:<20} {:<6} {}", rule.id, rule.level, rule.summary)
+        }
+
+        return
+    }
+
+    // tests are found in a directory, the current one unless told otherwise
+    if args.first().is_some_and(|command| *command == "test") {
+        let path = args.get(1).map_or(".", |path| path.as_str());
+        process::exit(if test(path) { 0 } else { 1 })
+    }
+
+    if args.first().is_some_and(|command| *command == "new") {
+        match args.get(1) {
+            Some(name) => process::exit(if new(name) { 0 } else { 1 }),
+            None       => usage(),
+        }
+    }
+
+    // a package is found from a directory, the current one unless told otherwise
+    if args.first().is_some_and(|command| *command == "build") {
+        let path = args.get(1).map_or(".", |path| path.as_str());
+        process::exit(if build(path) { 0 } else { 1 })
+    }
+
+    // run without a file is the entry point of the package around
+    if args.len() == 1 && *args[0] == "run" {
+        let entry = match package(Path::new(".")) {
+            Some(package) => package.entry(),
+            None          => process::exit(1),
+        };
+
+        let source = match fs::read_to_string(&entry) {
+            Ok(source) => source,
+            Err(why)   => {
+                println!("{}", format!("unable to read {}: {}", entry.display(), why).red());
+                process::exit(1)
+            },
+        };
+
+        let vm = flags.iter().any(|flag| *flag == "--vm");
+        process::exit(if run(&source, &entry.to_string_lossy(), vm) { 0 } else { 1 })
+    }
+
+    if args.len() < 2 {
+        usage()
+    }
+
+    let vm     = flags.iter().any(|flag| *flag == "--vm");
+    let target = flags.iter().find_map(|flag| flag.strip_prefix("--target="));
+    let style  = flags.iter().find_map(|flag| flag.strip_prefix("--format="));
+    let dry    = flags.iter().any(|flag| *flag == "--check");
+    let align  = flags.iter().any(|flag| *flag == "--align");
+    let fix    = flags.iter().any(|flag| *flag == "--fix");
+
+    // documentation takes any number of files
+    if args[0] == "doc" {
+        process::exit(if doc(&args[1 ..], style) { 0 } else { 1 })
+    }
+
+    // compiled files skip the source entirely
+    if args[1].ends_with(".ituc") {
+        let success = match args[0].as_str() {
+            "run"    => load(args[1]).is_some_and(|program| execute(None, program)),
+            "disasm" => load(args[1]).map(|program| print!("{}", vm::disassemble(&program, None))).is_some(),
+            _        => usage(),
+        };
+
+        if !success {
+            process::exit(1)
+        }
+
+        return
+    }
+
+    let source = match fs::read_to_string(args[1]) {
+        Ok(source) => source,
+        Err(why)   => {
+            println!("{}", format!("unable to read {}: {}", args[1], why).red());
+            process::exit(1)
+        },
+    };
+
+    let success = match args[0].as_str() {
+        "parse" if args[1].ends_with(".json") => unjson(&source, style),
+        "parse"     => tree(&source, style),
+        "run"       => run(&source, args[1], vm),
+        "check"     => check(&source, args[1]),
+        "types"     => types(&source, args[1]),
+        "compile"   => write(&source, args[1]),
+        "emit"      => emit(&source, args[1], target),
+        "fmt"       => fmt(&source, args[1], dry, align),
+        "lint"      => lint(&source, args[1], fix),
+        "highlight" => highlight(&source, style),
+        "disasm"    => compile(&source).map(|program| print!("{}", vm::disassemble(&program, Some(&source)))).is_some(),
+        _           => usage(),
+    };
+
+    if !success {
+        process::exit(1)
     }
 }
@@ -0,0 +1,144 @@
+// programs emitted as c, built with the system compiler and run, have to
+// print what `itu run` prints for them
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const PROGRAMS: &[(&str, &str)] = &[
+    ("numbers", "fib := (n: i32): i32 ->
+  if n < 2
+    n
+  else
+    (fib n - 1) + (fib n - 2)
+
+io.print (fib 20)
+io.print 7 % 3
+io.print (math.sqrt 2)
+
+twice := (a: f64): f64 -> a * 2
+twice 21.5
+"),
+
+    ("division", "half := (n: f64): f64 -> n / 2
+io.print (half 7)
+io.print 7 / 2
+io.print (half 0.5)
+"),
+
+    ("arrays", "xs := {3, 1, 4, 1, 5}
+total: mut f64 = 0
+total = xs[0] + xs[2] * xs[4]
+xs[1] = 9
+
+io.print total
+io.print xs[1]
+io.print (total > 1)
+"),
+
+    ("functions", "square := (x: f64): f64 -> x * x
+inc := (x: f64): f64 -> x + 1
+
+pick := (a: bool): str -> if a then \"yes\" else \"no\"
+io.print (pick false)
+
+hyp := (a: f64, b: f64): f64 ->
+  aa := a * a
+  bb := b * b
+  math.sqrt aa + bb
+
+io.print (hyp 3, 4)
+io.print (inc (square 3))
+io.print 'z'
+"),
+];
+
+fn itu(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_itu")).args(args).env("NO_COLOR", "1").output().unwrap()
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("itu-c-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    dir.join(name)
+}
+
+#[test]
+fn compiled_programs_print_what_the_evaluator_prints() {
+    for &(name, source) in PROGRAMS {
+        let path = scratch(&format!("{}.itu", name));
+        fs::write(&path, source).unwrap();
+
+        let path = path.to_str().unwrap();
+
+        let emitted = itu(&["emit", "--target=c", path]);
+        assert!(emitted.status.success(), "{}: {}", name, String::from_utf8_lossy(&emitted.stdout));
+
+        let c   = scratch(&format!("{}.c", name));
+        let exe = scratch(name);
+        fs::write(&c, &emitted.stdout).unwrap();
+
+        let built = match Command::new("cc").arg("-std=c99").arg("-o").arg(&exe).arg(&c).arg("-lm").output() {
+            Ok(built) => built,
+            // nothing to check against without a c compiler
+            Err(_)    => return,
+        };
+
+        assert!(built.status.success(), "{}: {}", name, String::from_utf8_lossy(&built.stderr));
+
+        let compiled  = Command::new(&exe).output().unwrap();
+        let evaluated = itu(&["run", path]);
+
+        assert!(evaluated.status.success(), "{}: {}", name, String::from_utf8_lossy(&evaluated.stdout));
+        assert_eq!(
+            String::from_utf8_lossy(&compiled.stdout),
+            String::from_utf8_lossy(&evaluated.stdout),
+            "{}: the c program printed something else", name
+        );
+    }
+}
+
+#[test]
+fn errors_point_into_the_function() {
+    let path = scratch("capture.itu");
+    fs::write(&path, "f := (a: f64) ->\n  g := (b: f64) -> a + b\n  g 1\nio.print (f 1)\n").unwrap();
+
+    let emitted = itu(&["emit", "--target=c", path.to_str().unwrap()]);
+    let report  = String::from_utf8_lossy(&emitted.stdout);
+
+    assert!(!emitted.status.success());
+    assert!(report.starts_with("ln 2, cl 2|"), "{}", report);
+    assert!(report.contains("in `g`: lambdas capturing values"), "{}", report);
+}
+
+#[test]
+fn imports_are_refused_where_they_are() {
+    let path = scratch("import.itu");
+    fs::write(&path, "-- vectors\nimport math/vector\nio.print (vector.length 3, 4)\n").unwrap();
+
+    for target in &["c", "lua", "rust", "wasm"] {
+        let emitted = itu(&["emit", &format!("--target={}", target), path.to_str().unwrap()]);
+        let report  = String::from_utf8_lossy(&emitted.stdout);
+
+        assert!(!emitted.status.success(), "{}", target);
+        assert!(report.starts_with("ln 2, cl 0|"), "{}: {}", target, report);
+        assert!(report.contains("imports are not supported by emit"), "{}: {}", target, report);
+    }
+}
+
+#[test]
+fn integer_division_is_refused() {
+    let path = scratch("halves.itu");
+    fs::write(&path, "x := 1\nhalf := (n: i32): i32 -> n / 2\nio.print (half 7)\n").unwrap();
+
+    for target in &["c", "rust", "wasm"] {
+        let emitted = itu(&["emit", &format!("--target={}", target), path.to_str().unwrap()]);
+        let report  = String::from_utf8_lossy(&emitted.stdout);
+
+        assert!(!emitted.status.success(), "{}", target);
+        assert!(report.starts_with("ln 2, cl 0|"), "{}: {}", target, report);
+        assert!(report.contains("`/` on i32") || report.contains("`/` on integers"), "{}: {}", target, report);
+    }
+}
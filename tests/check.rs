@@ -0,0 +1,57 @@
+// `itu check` run on files written out for the purpose, its errors pointing
+// at the statement they were found in
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn itu(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_itu")).args(args).env("NO_COLOR", "1").output().unwrap()
+}
+
+fn scratch(name: &str, source: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("itu-check-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+
+    path
+}
+
+#[test]
+fn undefined_names_point_at_their_statement() {
+    let path   = scratch("undefined.itu", "a := 1\nf := (n) ->\n  io.print n\n  io.print missing\n  n\n\nio.print (g 2)\n");
+    let output = itu(&["check", path.to_str().unwrap()]);
+    let report = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(report.contains("ln 4, cl 2|   io.print missing"), "{}", report);
+    assert!(report.contains("ln 7, cl 0| io.print (g 2)"), "{}", report);
+}
+
+#[test]
+fn type_errors_point_at_their_statement() {
+    let path   = scratch("types.itu", "a := 1\nf := (n: i32): i32 ->\n  io.print n\n  n + \"s\"\n\nio.print 2\na + \"s\"\n");
+    let output = itu(&["check", path.to_str().unwrap()]);
+    let report = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(report.contains("ln 4, cl 2|   n + \"s\""), "{}", report);
+}
+
+#[test]
+fn untyped_mut_keeps_its_first_type() {
+    let sample = Path::new(env!("CARGO_MANIFEST_DIR")).join("samples").join("literals.itu");
+    let output = itu(&["check", sample.to_str().unwrap()]);
+    let report = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(report.contains("ln 3, cl 0| a = \"hello\""), "{}", report);
+    assert!(report.contains("`a` holds a number as its first value did, declare it `mut any`"), "{}", report);
+    assert!(report.contains("`a` declared `mut` without a type here"), "{}", report);
+
+    let path = scratch("any.itu", "a: mut any = 100\na = \"hello\"\nb: mut = 1\nb = 2\n");
+    assert!(itu(&["check", path.to_str().unwrap()]).status.success());
+}
@@ -0,0 +1,211 @@
+// the language server driven over stdio as an editor would, a session
+// being written out whole and the answers read back by id
+#![cfg(feature = "lsp")]
+
+#[macro_use]
+extern crate serde_json;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+const URI: &str = "file:///tmp/itu-lsp-test.itu";
+
+struct Session {
+    messages: Vec<Value>,
+    next:     i64,
+}
+
+impl Session {
+    fn open(source: &str) -> Session {
+        let mut session = Session { messages: Vec::new(), next: 0 };
+
+        session.request("initialize", json!({ "capabilities": {} }));
+        session.notify("initialized", json!({}));
+        session.notify("textDocument/didOpen", json!({
+            "textDocument": { "uri": URI, "languageId": "itu", "version": 1, "text": source },
+        }));
+
+        session
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.messages.push(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> i64 {
+        self.next += 1;
+        self.messages.push(json!({ "jsonrpc": "2.0", "id": self.next, "method": method, "params": params }));
+
+        self.next
+    }
+
+    // `line` and `character` count from 0, as the protocol does
+    fn at(&mut self, method: &str, line: u64, character: u64) -> i64 {
+        self.request(method, json!({
+            "textDocument": { "uri": URI },
+            "position":     { "line": line, "character": character },
+        }))
+    }
+
+    // every answer, by the id of the request it answers
+    fn run(self) -> Vec<(i64, Value)> {
+        self.received().into_iter()
+            .filter_map(|message| message["id"].as_i64().map(|id| (id, message["result"].clone())))
+            .collect()
+    }
+
+    // the diagnostics last published for the document
+    fn diagnostics(self) -> Vec<Value> {
+        self.received().into_iter()
+            .rfind(|message| message["method"] == "textDocument/publishDiagnostics")
+            .and_then(|message| message["params"]["diagnostics"].as_array().cloned())
+            .unwrap_or_default()
+    }
+
+    // everything the server wrote, in order
+    fn received(mut self) -> Vec<Value> {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_itu"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        {
+            let stdin = child.stdin.as_mut().unwrap();
+
+            for message in &self.messages {
+                let body = message.to_string();
+                write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap()
+            }
+        }
+
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "the server did not shut down cleanly");
+
+        let mut rest     = &*String::from_utf8(output.stdout).unwrap();
+        let mut received = Vec::new();
+
+        while let Some(start) = rest.find("\r\n\r\n") {
+            let length = rest[.. start].trim().strip_prefix("Content-Length: ").unwrap().parse::<usize>().unwrap();
+            let body   = &rest[start + 4 .. start + 4 + length];
+
+            received.push(serde_json::from_str::<Value>(body).unwrap());
+
+            rest = &rest[start + 4 + length ..]
+        }
+
+        received
+    }
+}
+
+fn answer(answers: &[(i64, Value)], id: i64) -> &Value {
+    &answers.iter().find(|&&(i, _)| i == id).unwrap().1
+}
+
+fn hover(answers: &[(i64, Value)], id: i64) -> Option<String> {
+    answer(answers, id)["contents"]["value"].as_str().map(|value| {
+        value.trim_start_matches("```itu\n").trim_end_matches("\n```").to_string()
+    })
+}
+
+const SOURCE: &str = "add := (a: i32, b) -> a + b
+total := add 1, 2
+
+io.print total
+";
+
+#[test]
+fn hovers_show_inferred_types() {
+    let mut session = Session::open(SOURCE);
+
+    let function  = session.at("textDocument/hover", 0, 1);
+    let parameter = session.at("textDocument/hover", 0, 26);
+    let value     = session.at("textDocument/hover", 3, 10);
+    let nothing   = session.at("textDocument/hover", 2, 0);
+
+    let answers = session.run();
+
+    assert_eq!(hover(&answers, function).as_deref(), Some("add: (i32, i32): i32"));
+    assert_eq!(hover(&answers, parameter).as_deref(), Some("b: i32"));
+    assert_eq!(hover(&answers, value).as_deref(), Some("total: i32"));
+    assert_eq!(*answer(&answers, nothing), Value::Null);
+}
+
+#[test]
+fn definitions_lead_to_where_names_are_bound() {
+    let mut session = Session::open(SOURCE);
+
+    let call      = session.at("textDocument/definition", 1, 9);
+    let parameter = session.at("textDocument/definition", 0, 26);
+    let builtin   = session.at("textDocument/definition", 3, 4);
+
+    let answers = session.run();
+
+    assert_eq!(answer(&answers, call)["range"]["start"], json!({ "line": 0, "character": 0 }));
+    assert_eq!(answer(&answers, parameter)["range"]["start"], json!({ "line": 0, "character": 16 }));
+    assert_eq!(answer(&answers, call)["uri"], URI);
+    assert_eq!(*answer(&answers, builtin), Value::Null);
+}
+
+#[test]
+fn an_undefined_name_leaves_other_hovers_alone() {
+    let mut session = Session::open("double := (x: f64) -> x * 2\nbroken := missing + 1\nhalf := (y: f64) -> y / 2\n");
+
+    let before = session.at("textDocument/hover", 0, 1);
+    let local  = session.at("textDocument/hover", 0, 22);
+    let after  = session.at("textDocument/hover", 2, 1);
+
+    let answers = session.run();
+
+    assert_eq!(hover(&answers, before).as_deref(), Some("double: (f64): f64"));
+    assert_eq!(hover(&answers, local).as_deref(), Some("x: f64"));
+    assert_eq!(hover(&answers, after).as_deref(), Some("half: (f64): f64"));
+}
+
+#[test]
+fn parameters_and_locals_hover_with_their_types() {
+    let mut session = Session::open("id := (b) -> b\nf := (a: i32) ->\n  c := a + 1\n  c\n");
+
+    let parameter = session.at("textDocument/hover", 0, 7);
+    let generic   = session.at("textDocument/hover", 0, 13);
+    let local     = session.at("textDocument/hover", 2, 2);
+    let used      = session.at("textDocument/hover", 3, 2);
+    let function  = session.at("textDocument/hover", 0, 0);
+
+    let answers = session.run();
+
+    assert_eq!(hover(&answers, parameter).as_deref(), Some("b: a"));
+    assert_eq!(hover(&answers, generic).as_deref(), Some("b: a"));
+    assert_eq!(hover(&answers, local).as_deref(), Some("c: i32"));
+    assert_eq!(hover(&answers, used).as_deref(), Some("c: i32"));
+    assert_eq!(hover(&answers, function).as_deref(), Some("id: <a>(a): a"));
+}
+
+#[test]
+fn semantic_errors_are_published_at_their_statement() {
+    let session = Session::open("f := (n: i32): i32 ->\n  io.print n\n  n + \"s\"\n\nio.print missing\n");
+
+    let diagnostics = session.diagnostics();
+    let starts      = diagnostics.iter().map(|diagnostic| diagnostic["range"]["start"].clone()).collect::<Vec<_>>();
+
+    assert!(!diagnostics.is_empty());
+    assert!(starts.iter().all(|start| *start != json!({ "line": 0, "character": 0 })), "{:?}", diagnostics);
+    assert!(starts.contains(&json!({ "line": 4, "character": 0 })), "{:?}", diagnostics);
+}
+
+#[test]
+fn malformed_literals_are_diagnosed_without_stopping_the_server() {
+    let session = Session::open("double := (x: f64) -> x * 2\nhuge := 99999999999999999999999\n");
+
+    // the session asserts the server shut down cleanly
+    let diagnostics = session.diagnostics();
+
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 1, "character": 8 }));
+}
@@ -0,0 +1,127 @@
+// every sample run on the tree-walking evaluator and on the vm, which have
+// to agree on what is printed and on whether the run succeeded
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn samples() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("samples");
+
+    let mut samples = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "itu"))
+        .collect::<Vec<_>>();
+
+    samples.sort();
+    samples
+}
+
+fn run(sample: &Path, vm: bool) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_itu"));
+    command.arg("run");
+
+    if vm {
+        command.arg("--vm");
+    }
+
+    command.arg(sample).env("NO_COLOR", "1").output().unwrap()
+}
+
+fn scratch(name: &str, source: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("itu-samples-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+
+    path
+}
+
+#[test]
+fn evaluator_and_vm_agree_on_samples() {
+    let samples = samples();
+    assert!(!samples.is_empty(), "no samples found");
+
+    for sample in samples {
+        let evaluated = run(&sample, false);
+        let compiled  = run(&sample, true);
+
+        let name = sample.file_name().unwrap().to_string_lossy();
+
+        assert_eq!(evaluated.status.success(), compiled.status.success(), "{}: only one backend succeeded", name);
+        assert_eq!(
+            String::from_utf8_lossy(&evaluated.stdout),
+            String::from_utf8_lossy(&compiled.stdout),
+            "{}: the backends printed different things", name
+        );
+    }
+}
+
+#[test]
+fn samples_that_parse_run() {
+    for sample in samples() {
+        // structs are written about but not parsed yet
+        if sample.file_stem().unwrap() == "structs" {
+            continue
+        }
+
+        let output = run(&sample, false);
+        assert!(output.status.success(), "{} failed:\n{}", sample.display(), String::from_utf8_lossy(&output.stdout));
+    }
+}
+
+#[test]
+fn runtime_errors_point_at_their_statement() {
+    let bounds = "index 5 out of bounds";
+
+    let cases = [
+        ("top.itu",       "xs := {1, 2}\n\nb := 2\nxs[5]\n",                                   "ln 4, cl 0|", bounds),
+        ("nested.itu",    "xs := {1, 2}\nf := (n) ->\n  b := n\n  io.print b\n  xs[5]\n\nf 1\n", "ln 5, cl 2|", bounds),
+        ("arm.itu",       "xs := {1, 2}\nf := (n) ->\n  match n\n    0 -> 0\n    _ -> xs[5]\n\nf 1\n",  "ln 5, cl 4|", bounds),
+        ("unmatched.itu", "f := (n) ->\n  io.print n\n  match n\n    0 -> 0\n\nf 1\n",                "ln 3, cl 2|", "no arm matched 1"),
+    ];
+
+    for &(name, source, line, message) in &cases {
+        let path = scratch(name, source);
+
+        for &vm in &[false, true] {
+            let output = run(&path, vm);
+            let report = String::from_utf8_lossy(&output.stdout);
+
+            assert!(!output.status.success(), "{}", name);
+            assert!(report.contains(line), "{} (vm: {}):\n{}", name, vm, report);
+            assert!(report.contains(message), "{} (vm: {}):\n{}", name, vm, report);
+        }
+    }
+}
+
+#[test]
+fn runaway_recursion_is_a_runtime_error() {
+    let path = scratch("deep.itu", "c := (n) -> if n == 0 then 0 else 1 + (c n - 1)\nio.print (c 9000)\nio.print (c 100000)\n");
+
+    for &vm in &[false, true] {
+        let output = run(&path, vm);
+        let report = String::from_utf8_lossy(&output.stdout);
+
+        assert!(!output.status.success(), "vm: {}", vm);
+        assert!(report.starts_with("9000\n"), "vm: {}\n{}", vm, report);
+        assert!(report.contains("\nln "), "vm: {}\n{}", vm, report);
+        assert!(report.contains("stack overflow, calls nested deeper than 10000"), "vm: {}\n{}", vm, report);
+    }
+}
+
+#[test]
+fn disassembly_follows_the_lines_of_a_function() {
+    let sample = Path::new(env!("CARGO_MANIFEST_DIR")).join("samples").join("closures.itu");
+    let output = Command::new(env!("CARGO_BIN_EXE_itu")).arg("disasm").arg(&sample).output().unwrap();
+    let text   = String::from_utf8_lossy(&output.stdout);
+
+    // fib's condition and both its branches are on lines of their own
+    let fib = text.split("\n\n").next().unwrap();
+
+    for line in &["    2 | GET_LOCAL", "    3 | GET_LOCAL", "    5 | GET_GLOBAL"] {
+        assert!(fib.contains(line), "no `{}` in:\n{}", line, fib);
+    }
+}
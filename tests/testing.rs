@@ -0,0 +1,45 @@
+// `itu test` run on files written out for the purpose
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn itu(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_itu")).args(args).env("NO_COLOR", "1").output().unwrap()
+}
+
+fn scratch(name: &str, source: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("itu-testing-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+
+    path
+}
+
+#[test]
+fn tests_see_the_definitions_and_not_the_program() {
+    let path   = scratch("program.itu", "a := 1\nio.print 5\nxs := {1}\nxs[3]\n\ntest \"one\" ->\n  assert a == 1\n\ntest \"two\" ->\n  assert a < 2\n");
+    let output = itu(&["test", path.to_str().unwrap()]);
+    let report = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", report);
+    assert!(report.contains("PASS one") && report.contains("PASS two"), "{}", report);
+    assert!(!report.lines().any(|line| line == "5"), "{}", report);
+}
+
+#[test]
+fn nested_tests_are_refused() {
+    let path = scratch("nested.itu", "next := (x) ->\n  test \"inside\" ->\n    assert true\n\n  x + 1\n");
+
+    for command in &["run", "test"] {
+        let output = itu(&[command, path.to_str().unwrap()]);
+        let report = String::from_utf8_lossy(&output.stdout);
+
+        assert!(!output.status.success(), "{}", command);
+        assert!(report.contains("ln 2, cl 2|"), "{}: {}", command, report);
+        assert!(report.contains("tests go at the top level of a file"), "{}: {}", command, report);
+    }
+}